@@ -19,11 +19,17 @@ fn cmd_main() -> Result<()> {
     let mut options = cli::MainOptions::parse();
     options.update();
 
-    // Handle the "completion" and "init" commands before building the context.
+    // Handle the "cache", "completion", "hook" and "init" commands before building the context.
     match options.command.clone() {
+        cli::Command::Cache(cache) => {
+            return cmds::cache::main(&options, &cache);
+        }
         cli::Command::Completion(completion) => {
             return cmds::completion::main(&options, &completion);
         }
+        cli::Command::Hook(hook) => {
+            return cmds::hook::main(&options, &hook);
+        }
         cli::Command::Init(mut init_options) => {
             return cmds::init::main(&options, &mut init_options);
         }
@@ -43,6 +49,16 @@ fn cmd_main() -> Result<()> {
         cli::Command::Plant(plant) => cmds::plant::main(&app, &plant),
         cli::Command::Prune(mut prune) => cmds::prune::main(&app, &mut prune),
         cli::Command::Shell(shell) => cmds::shell::main(&app, &shell),
+        cli::Command::Status(mut status) => cmds::status::main(&app, &mut status),
+        cli::Command::Sync(sync) => cmds::sync::main(&app, &sync),
+        cli::Command::Lock(lock) => cmds::lock::main(&app, &lock),
+        cli::Command::Log(log) => cmds::log::main_log(&app, &log),
+        cli::Command::Diff(diff) => cmds::log::main_diff(&app, &diff),
+        cli::Command::Check(check) => cmds::check::main(&app, &check),
+        cli::Command::Vars(vars) => cmds::vars::main(&app, &vars),
+        cli::Command::Cache(_) => Ok(()), // Handled above
+        cli::Command::Env(env) => cmds::env::main(&app, &env),
+        cli::Command::Hook(_) => Ok(()), // Handled above
     }
 }
 
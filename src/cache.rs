@@ -0,0 +1,115 @@
+use crate::{errors, path};
+
+/// $XDG_CACHE_HOME/garden/exec (typically ~/.cache/garden/exec)
+pub fn exec_cache_dir() -> std::path::PathBuf {
+    let mut cache_dir;
+    if let Ok(xdg_dirs) = xdg::BaseDirectories::new() {
+        cache_dir = xdg_dirs.get_cache_home();
+    } else {
+        cache_dir = path::home_dir();
+        cache_dir.push(".cache");
+    }
+    cache_dir.push("garden");
+    cache_dir.push("exec");
+
+    cache_dir
+}
+
+/// Return the cache key for an exec expression that runs in the specified directory.
+/// The key changes when any of the watched files are created, modified or removed.
+pub(crate) fn exec_key(command: &str, dir: &std::path::Path, watch: &[String]) -> String {
+    let mut hash = Fnv1a::new();
+    hash.write(command.as_bytes());
+    hash.write(dir.to_string_lossy().as_bytes());
+    for filename in watch {
+        let pathbuf = dir.join(filename);
+        hash.write(pathbuf.to_string_lossy().as_bytes());
+        let modified = std::fs::metadata(&pathbuf)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok());
+        match modified {
+            Some(modified) => hash.write(modified.as_nanos().to_string().as_bytes()),
+            None => hash.write(b"missing"),
+        }
+    }
+
+    format!("{:016x}", hash.finish())
+}
+
+/// Read a cached value. Values older than the specified duration are ignored.
+pub(crate) fn read(key: &str, duration: Option<std::time::Duration>) -> Option<String> {
+    let pathbuf = exec_cache_dir().join(key);
+    if let Some(duration) = duration {
+        let age = std::fs::metadata(&pathbuf)
+            .and_then(|metadata| metadata.modified())
+            .ok()?
+            .elapsed()
+            .unwrap_or_default();
+        if age > duration {
+            return None;
+        }
+    }
+
+    std::fs::read_to_string(pathbuf).ok()
+}
+
+/// Store a value in the cache. The cache is best-effort and errors are ignored.
+pub(crate) fn write(key: &str, value: &str) {
+    let cache_dir = exec_cache_dir();
+    if std::fs::create_dir_all(&cache_dir).is_err() {
+        return;
+    }
+    // Write to a temporary file and rename it so that concurrent readers
+    // never observe a partially-written value.
+    let tmp_path = cache_dir.join(format!("{key}.{}.tmp", std::process::id()));
+    if std::fs::write(&tmp_path, value).is_ok()
+        && std::fs::rename(&tmp_path, cache_dir.join(key)).is_err()
+    {
+        std::fs::remove_file(&tmp_path).unwrap_or(());
+    }
+}
+
+/// Remove all cached values and return the number of values that were removed.
+pub fn clear() -> Result<usize, errors::GardenError> {
+    let cache_dir = exec_cache_dir();
+    let entries = match std::fs::read_dir(&cache_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => {
+            return Err(errors::GardenError::IOError(format!(
+                "{cache_dir:?}: {err}"
+            )))
+        }
+    };
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let pathbuf = entry.path();
+        std::fs::remove_file(&pathbuf)
+            .map_err(|err| errors::GardenError::IOError(format!("{pathbuf:?}: {err}")))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// 64-bit FNV-1a hash. Cache keys must be stable across builds and Rust versions.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Hash a field. Fields are terminated so that adjacent fields cannot collide.
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter().chain(std::iter::once(&0xff)) {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
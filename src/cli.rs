@@ -116,6 +116,27 @@ pub enum Command {
     /// Open a shell in a garden environment
     #[command(alias = "sh")]
     Shell(cmds::shell::ShellOptions),
+    /// Display the branch and status of trees
+    Status(cmds::status::StatusOptions),
+    /// Fetch remotes and fast-forward branches
+    #[command(alias = "pull")]
+    Sync(cmds::sync::SyncOptions),
+    /// Record the current commit of trees in "garden.lock"
+    Lock(cmds::lock::LockOptions),
+    /// Show commit logs across trees
+    Log(cmds::log::LogOptions),
+    /// Show changes across trees
+    Diff(cmds::log::DiffOptions),
+    /// Check the configuration for problems
+    Check(cmds::check::CheckOptions),
+    /// List variables and the scopes that define them
+    Vars(cmds::vars::VarsOptions),
+    /// Manage the exec expression cache
+    Cache(cmds::cache::CacheOptions),
+    /// Print the environment for a tree
+    Env(cmds::env::EnvOptions),
+    /// Print a shell hook that loads tree environments when changing directories
+    Hook(cmds::hook::HookOptions),
 }
 
 impl std::default::Default for Command {
@@ -13,6 +13,24 @@ pub fn status(exec: subprocess::Exec) -> i32 {
     status_code(exec.join())
 }
 
+/// Run a subprocess::Exec instance and return its exit status, stdout and stderr.
+/// Commands run with an empty stdin so that concurrent commands cannot block on input.
+pub(crate) fn status_with_output(exec: subprocess::Exec) -> (i32, Vec<u8>, Vec<u8>) {
+    let capture = exec
+        .stdin(subprocess::NullFile {})
+        .stdout(subprocess::Redirection::Pipe)
+        .stderr(subprocess::Redirection::Pipe)
+        .capture();
+    match capture {
+        Ok(result) => (
+            exit_status(result.exit_status),
+            result.stdout,
+            result.stderr,
+        ),
+        Err(_) => (errors::EX_ERROR, Vec::new(), Vec::new()),
+    }
+}
+
 /// Return the status code from subprocess::Result<subprocess::ExitStatus>.
 fn status_code(result: subprocess::Result<subprocess::ExitStatus>) -> i32 {
     match result {
@@ -64,6 +82,65 @@ pub(crate) fn capture_stdout(
     }
 }
 
+/// The output captured from a command that was run with an optional timeout.
+pub(crate) struct TimedCapture {
+    /// The exit status or None when the command timed out.
+    pub(crate) status: Option<i32>,
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+}
+
+/// Run a subprocess::Exec instance and capture its stdout and stderr.
+/// The command is killed when it runs longer than the specified timeout.
+pub(crate) fn capture_with_timeout(
+    exec: subprocess::Exec,
+    timeout: Option<std::time::Duration>,
+) -> Result<TimedCapture, errors::CommandError> {
+    let command = exec.to_cmdline_lossy();
+    let deadline = timeout.map(|timeout| std::time::Instant::now() + timeout);
+    let mut popen = exec
+        .stdin(subprocess::NullFile {})
+        .stdout(subprocess::Redirection::Pipe)
+        .stderr(subprocess::Redirection::Pipe)
+        .popen()
+        .map_err(|err| command_error_from_popen_error(command.clone(), err))?;
+    let mut communicator = popen.communicate_start(None);
+    if let Some(timeout) = timeout {
+        communicator = communicator.limit_time(timeout);
+    }
+    let (capture, timed_out) = match communicator.read() {
+        Ok(capture) => (capture, false),
+        Err(err) if err.error.kind() == std::io::ErrorKind::TimedOut => (err.capture, true),
+        Err(err) => {
+            return Err(errors::CommandError::ExitStatus {
+                command,
+                status: err.error.raw_os_error().unwrap_or(errors::EX_ERROR),
+            })
+        }
+    };
+    // The command can close its output streams and keep running.
+    let status = if timed_out {
+        None
+    } else if let Some(deadline) = deadline {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        popen.wait_timeout(remaining).unwrap_or(None)
+    } else {
+        popen.wait().ok()
+    };
+    if status.is_none() {
+        popen.kill().unwrap_or(());
+        popen.wait().ok();
+    }
+    let to_string =
+        |bytes: Option<Vec<u8>>| String::from_utf8_lossy(&bytes.unwrap_or_default()).to_string();
+
+    Ok(TimedCapture {
+        status: status.map(exit_status),
+        stdout: to_string(capture.0).trim_end().to_string(),
+        stderr: to_string(capture.1).trim_end().to_string(),
+    })
+}
+
 /// Convert subprocess::ExitStatus into a CommandError
 pub(crate) fn exit_status(status: subprocess::ExitStatus) -> i32 {
     match status {
@@ -107,7 +184,6 @@ where
 /// - quiet: Suppress messages when set true.
 /// - verbose: increase verbosity of messages.
 /// - command: String vector of the command to run.
-
 pub(crate) fn exec_in_context<S>(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
@@ -146,7 +222,7 @@ where
         return Ok(());
     }
     // Evaluate the tree environment and run the command.
-    let env = eval::environment(app_context, config, context);
+    let env = eval::environment(app_context, config, context)?;
     let command_vec = resolve_command(command, &env);
     if verbose > 1 || dry_run {
         display::print_command_string_vec(&command_vec);
@@ -169,7 +245,6 @@ where
 /// The command might be a path that only exists inside the resolved
 /// environment.  Resolve the path by looking for the presence of PATH
 /// and updating the command when it exists.
-
 fn resolve_command<S>(command: &[S], env: &[(String, String)]) -> Vec<String>
 where
     S: AsRef<std::ffi::OsStr>,
@@ -223,7 +298,7 @@ pub(crate) fn get_command_values(
     app_context: &model::ApplicationContext,
     context: &model::TreeContext,
     name: &str,
-) -> Vec<String> {
+) -> Result<Vec<String>, errors::GardenError> {
     let config = match context.config {
         Some(config_id) => app_context.get_config(config_id),
         None => app_context.get_root_config(),
@@ -259,11 +334,11 @@ pub(crate) fn get_command_values(
 
     let mut commands = Vec::with_capacity(vec_variables.len() * 2);
     for variables in vec_variables.iter_mut() {
-        let values = eval::variables_for_shell(app_context, config, variables, context);
+        let values = eval::variables_for_shell(app_context, config, variables, context)?;
         commands.extend(values);
     }
 
-    commands
+    Ok(commands)
 }
 
 /// Recursively expand a command name to include its pre-commands and post-commands.
@@ -272,18 +347,18 @@ pub(crate) fn expand_command_names(
     app_context: &model::ApplicationContext,
     context: &model::TreeContext,
     name: &str,
-) -> Vec<String> {
+) -> Result<Vec<String>, errors::GardenError> {
     let pre_name = syntax::pre_command(name);
     let post_name = syntax::post_command(name);
-    let pre_commands = get_command_values(app_context, context, &pre_name);
-    let post_commands = get_command_values(app_context, context, &post_name);
+    let pre_commands = get_command_values(app_context, context, &pre_name)?;
+    let post_commands = get_command_values(app_context, context, &post_name)?;
 
     let mut command_names = Vec::with_capacity(pre_commands.len() + 1 + post_commands.len());
     // Recursively expand pre-commands.
     for cmd_name in pre_commands.iter() {
         if cmd_name != name {
             // Avoid self-referential loops.
-            command_names.extend(expand_command_names(app_context, context, cmd_name));
+            command_names.extend(expand_command_names(app_context, context, cmd_name)?);
         }
     }
     command_names.push(name.to_string());
@@ -291,11 +366,11 @@ pub(crate) fn expand_command_names(
     for cmd_name in post_commands.iter() {
         if cmd_name != name {
             // Avoid self-referential loops.
-            command_names.extend(expand_command_names(app_context, context, cmd_name));
+            command_names.extend(expand_command_names(app_context, context, cmd_name)?);
         }
     }
 
-    command_names
+    Ok(command_names)
 }
 
 /// Shell quote a single command argument. Intended for or display purposes only.
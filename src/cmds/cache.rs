@@ -0,0 +1,35 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use crate::{cache, cli};
+
+/// Manage the exec expression cache
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct CacheOptions {
+    #[command(subcommand)]
+    command: CacheCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum CacheCommand {
+    /// Remove all cached exec expression values
+    Clear,
+}
+
+/// Main entry point for the "garden cache" command
+pub fn main(options: &cli::MainOptions, cache_options: &CacheOptions) -> Result<()> {
+    match cache_options.command {
+        CacheCommand::Clear => {
+            let count = cache::clear()?;
+            if options.verbose > 0 {
+                eprintln!(
+                    "removed {count} cached value(s) from {}",
+                    cache::exec_cache_dir().display()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,964 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use clap::Parser;
+use yaml_rust::{Yaml, YamlLoader};
+
+use crate::config::markers;
+use crate::{cmds, constants, errors, model, path, query, syntax};
+
+/// Check the configuration for problems
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct CheckOptions {}
+
+/// Keys accepted at the top level of a garden file.
+const TOP_LEVEL_KEYS: &[&str] = &[
+    constants::COMMANDS,
+    constants::ENVIRONMENT,
+    constants::GARDEN,
+    constants::GARDENS,
+    constants::GRAFTS,
+    constants::GROUPS,
+    constants::TEMPLATES,
+    constants::TREES,
+    constants::VARIABLES,
+];
+
+/// Keys accepted in the "garden" block.
+const GARDEN_KEYS: &[&str] = &[
+    constants::ENV_DEDUPE,
+    constants::ENV_SEPARATORS,
+    constants::EXEC_TIMEOUT,
+    constants::INCLUDES,
+    constants::INTERACTIVE_SHELL,
+    constants::ROOT,
+    constants::SHELL,
+    constants::SHELL_ERREXIT,
+    constants::SHELL_WORDSPLIT,
+    constants::TREE_BRANCHES,
+];
+
+/// Keys accepted in both tree and template blocks.
+const TEMPLATE_KEYS: &[&str] = &[
+    constants::BARE,
+    constants::BRANCH,
+    constants::BRANCHES,
+    constants::COMMANDS,
+    constants::DEFAULT_REMOTE,
+    constants::DEPENDS,
+    constants::DEPTH,
+    constants::DESCRIPTION,
+    constants::ENVIRONMENT,
+    constants::EXTEND,
+    constants::GITCONFIG,
+    constants::LINKS,
+    constants::REMOTES,
+    constants::SINGLE_BRANCH,
+    constants::SYMLINK,
+    constants::TAGS,
+    constants::URL,
+    constants::VARIABLES,
+    constants::WORKTREE,
+];
+
+/// Keys that are only accepted in tree blocks.
+const TREE_KEYS: &[&str] = &[constants::PATH, constants::REPLACE, constants::TEMPLATES];
+
+/// Keys accepted in garden blocks.
+const GARDEN_ENTRY_KEYS: &[&str] = &[
+    constants::COMMANDS,
+    constants::ENVIRONMENT,
+    constants::GITCONFIG,
+    constants::GROUPS,
+    constants::TREES,
+    constants::VARIABLES,
+];
+
+/// Keys accepted in variables that are defined using a mapping.
+const VARIABLE_KEYS: &[&str] = &[constants::CACHE, constants::VALUE, constants::WATCH];
+
+/// Keys accepted in commands that are defined using a mapping.
+const COMMAND_KEYS: &[&str] = &[
+    constants::ARGS,
+    constants::CWD,
+    constants::DESCRIPTION,
+    constants::ENVIRONMENT,
+    constants::OPTIONS,
+    constants::RUN,
+    constants::SHELL,
+];
+
+/// Keys accepted in mapping commands defined in tree, template and garden blocks.
+/// Options and arguments are only declared by the top-level commands block.
+const BLOCK_COMMAND_KEYS: &[&str] = &[
+    constants::CWD,
+    constants::ENVIRONMENT,
+    constants::RUN,
+    constants::SHELL,
+];
+
+/// Keys accepted in command arguments.
+const COMMAND_ARG_KEYS: &[&str] = &[
+    constants::CHOICES,
+    constants::DEFAULT,
+    constants::DESCRIPTION,
+    constants::REQUIRED,
+];
+
+/// Keys accepted in command options.
+const COMMAND_OPTION_KEYS: &[&str] = &[constants::SHORT];
+
+/// Keys accepted in graft blocks.
+const GRAFT_KEYS: &[&str] = &[constants::CONFIG, constants::ROOT];
+
+/// Main entry point for the "garden check" command
+pub fn main(app_context: &model::ApplicationContext, _options: &CheckOptions) -> Result<()> {
+    let mut checker = Checker::new(app_context);
+    checker.check_config(app_context.get_root_config());
+    checker
+        .problems
+        .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+
+    for problem in &checker.problems {
+        println!(
+            "{}:{}: {}",
+            path::display_relative(&problem.path),
+            problem.line,
+            problem.message
+        );
+    }
+    if checker.problems.is_empty() {
+        return Ok(());
+    }
+    if !app_context.options.quiet {
+        eprintln!("{} problem(s) found", checker.problems.len());
+    }
+
+    Err(errors::GardenError::ExitStatus(errors::EX_CONFIG).into())
+}
+
+/// A problem found in a configuration file.
+struct Problem {
+    path: std::path::PathBuf,
+    line: usize,
+    message: String,
+}
+
+/// A configuration file and the line numbers for its keys.
+struct Source<'a> {
+    path: &'a std::path::Path,
+    markers: markers::Markers,
+}
+
+impl Source<'_> {
+    /// Return the line for a key path. Parent keys are used when the key is not found.
+    fn line(&self, key: &str) -> usize {
+        let mut key = key;
+        loop {
+            if let Some(line) = self.markers.get(key) {
+                return *line;
+            }
+            match key.rfind('.') {
+                Some(index) => key = &key[..index],
+                None => return 1,
+            }
+        }
+    }
+
+    /// Return the file and line for a key path.
+    fn location(&self, key: &str) -> (std::path::PathBuf, usize) {
+        (self.path.to_path_buf(), self.line(key))
+    }
+}
+
+struct Checker<'a> {
+    app_context: &'a model::ApplicationContext,
+    problems: Vec<Problem>,
+    /// Files that have already been checked. Used to avoid include cycles.
+    visited: HashSet<std::path::PathBuf>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(app_context: &'a model::ApplicationContext) -> Self {
+        Self {
+            app_context,
+            problems: Vec::new(),
+            visited: HashSet::new(),
+        }
+    }
+
+    fn add(&mut self, location: (std::path::PathBuf, usize), message: String) {
+        let (path, line) = location;
+        self.problems.push(Problem {
+            path,
+            line,
+            message,
+        });
+    }
+
+    /// Return the file and line where a configuration entry is defined.
+    fn source_location(config: &model::Configuration, key: &str) -> (std::path::PathBuf, usize) {
+        match config.get_source(key) {
+            Some(location) => (location.path.clone(), location.line),
+            None => (config.path.clone().unwrap_or_default(), 1),
+        }
+    }
+
+    /// Check a configuration, its includes and its grafts.
+    fn check_config(&mut self, config: &model::Configuration) {
+        let path = match &config.path {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        self.check_file(config, &path, None);
+        self.check_variable_cycles(config);
+        self.check_tree_paths(config);
+
+        for graft in config.grafts.values() {
+            if let Some(graft_id) = graft.get_id() {
+                self.check_config(self.app_context.get_config(graft_id));
+            }
+        }
+    }
+
+    /// Check a single garden file and the files that it includes.
+    fn check_file(
+        &mut self,
+        config: &model::Configuration,
+        path: &std::path::Path,
+        current_include: Option<&std::path::Path>,
+    ) {
+        if !self.visited.insert(path.to_path_buf()) {
+            return;
+        }
+        let string = match std::fs::read_to_string(path) {
+            Ok(string) => string,
+            Err(err) => {
+                self.add((path.to_path_buf(), 1), format!("unable to read: {err}"));
+                return;
+            }
+        };
+        let docs = match YamlLoader::load_from_str(&string) {
+            Ok(docs) => docs,
+            Err(err) => {
+                self.add((path.to_path_buf(), err.marker().line()), err.to_string());
+                return;
+            }
+        };
+        let doc = match docs.first() {
+            Some(doc) => doc,
+            None => return,
+        };
+        let source = Source {
+            path,
+            markers: markers::read(&string),
+        };
+
+        self.check_keys(&source, "", doc, &[TOP_LEVEL_KEYS]);
+        self.check_keys(
+            &source,
+            constants::GARDEN,
+            &doc[constants::GARDEN],
+            &[GARDEN_KEYS],
+        );
+        // Includes are read before the entries in the current file.
+        self.check_includes(config, &source, doc, current_include);
+        self.check_variables(&source, constants::VARIABLES, &doc[constants::VARIABLES]);
+        self.check_commands(config, &source, &doc[constants::COMMANDS]);
+        self.check_trees(config, &source, &doc[constants::TREES]);
+        self.check_templates(config, &source, &doc[constants::TEMPLATES]);
+        self.check_groups(config, &source, &doc[constants::GROUPS]);
+        self.check_gardens(config, &source, &doc[constants::GARDENS]);
+        self.check_grafts(config, &source, &doc[constants::GRAFTS]);
+        self.check_variable_references(config, &source, "", doc);
+    }
+
+    /// Report keys that are not in the list of allowed keys.
+    fn check_keys(&mut self, source: &Source, parent: &str, yaml: &Yaml, allowed: &[&[&str]]) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for key in hash.keys().filter_map(Yaml::as_str) {
+            if allowed.iter().any(|keys| keys.contains(&key)) {
+                continue;
+            }
+            let message = if parent.is_empty() {
+                format!("unknown key: {key}")
+            } else {
+                format!("{parent}: unknown key: {key}")
+            };
+            self.add(source.location(&markers::join(parent, key)), message);
+        }
+    }
+
+    /// Check variables that are defined using mappings.
+    fn check_variables(&mut self, source: &Source, key: &str, yaml: &Yaml) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            if !matches!(value, Yaml::Hash(_)) {
+                continue;
+            }
+            let variable_key = markers::join(key, name);
+            self.check_keys(source, &variable_key, value, &[VARIABLE_KEYS]);
+        }
+    }
+
+    /// Check the variables block in a tree, template or garden block.
+    fn check_block_variables(&mut self, source: &Source, key: &str, yaml: &Yaml) {
+        let variables_key = markers::join(key, constants::VARIABLES);
+        self.check_variables(source, &variables_key, &yaml[constants::VARIABLES]);
+    }
+
+    /// Check the top-level commands that are defined using mappings.
+    fn check_commands(&mut self, config: &model::Configuration, source: &Source, yaml: &Yaml) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            if !matches!(value, Yaml::Hash(_)) {
+                continue;
+            }
+            let key = markers::join(constants::COMMANDS, name);
+            self.check_keys(source, &key, value, &[COMMAND_KEYS]);
+            self.check_command_params(
+                source,
+                &markers::join(&key, constants::OPTIONS),
+                &value[constants::OPTIONS],
+                &[COMMAND_ARG_KEYS, COMMAND_OPTION_KEYS],
+            );
+            self.check_command_params(
+                source,
+                &markers::join(&key, constants::ARGS),
+                &value[constants::ARGS],
+                &[COMMAND_ARG_KEYS],
+            );
+            if let Some(spec) = config.command_specs.get(name) {
+                for (param_key, problem) in cmds::cmd::command_spec_problems(name, spec) {
+                    self.add(
+                        source.location(&param_key),
+                        format!("{param_key}: {problem}"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check the options or arguments declared by a command.
+    fn check_command_params(
+        &mut self,
+        source: &Source,
+        key: &str,
+        yaml: &Yaml,
+        allowed: &[&[&str]],
+    ) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            if let Some(name) = name.as_str() {
+                self.check_keys(source, &markers::join(key, name), value, allowed);
+            }
+        }
+    }
+
+    /// Check the mapping commands in a tree, template or garden block.
+    fn check_block_commands(&mut self, source: &Source, key: &str, yaml: &Yaml) {
+        let hash = match &yaml[constants::COMMANDS] {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        let commands_key = markers::join(key, constants::COMMANDS);
+        for (name, value) in hash {
+            if let Some(name) = name.as_str() {
+                let command_key = markers::join(&commands_key, name);
+                self.check_keys(source, &command_key, value, &[BLOCK_COMMAND_KEYS]);
+            }
+        }
+    }
+
+    /// Check "garden.includes" entries and the included files.
+    fn check_includes(
+        &mut self,
+        config: &model::Configuration,
+        source: &Source,
+        doc: &Yaml,
+        current_include: Option<&std::path::Path>,
+    ) {
+        let includes_key = markers::join(constants::GARDEN, constants::INCLUDES);
+        let includes = match &doc[constants::GARDEN][constants::INCLUDES] {
+            Yaml::String(include) => vec![(includes_key, include.as_str())],
+            Yaml::Array(includes) => includes
+                .iter()
+                .enumerate()
+                .filter_map(|(index, include)| {
+                    let key = markers::join(&includes_key, &index.to_string());
+                    include.as_str().map(|include| (key, include))
+                })
+                .collect(),
+            _ => return,
+        };
+        for (key, include) in includes {
+            let pathbuf = match config.eval_config_pathbuf_from_include(
+                self.app_context,
+                current_include,
+                include,
+            ) {
+                Ok(Some(pathbuf)) => pathbuf,
+                Ok(None) => continue,
+                Err(err) => {
+                    self.add(source.location(&key), format!("{key}: {err}"));
+                    continue;
+                }
+            };
+            if !pathbuf.exists() {
+                self.add(
+                    source.location(&key),
+                    format!("{key}: include not found: {include}"),
+                );
+                continue;
+            }
+            self.check_file(config, &pathbuf, Some(&pathbuf));
+        }
+    }
+
+    /// Check tree blocks.
+    fn check_trees(&mut self, config: &model::Configuration, source: &Source, yaml: &Yaml) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let key = markers::join(constants::TREES, name);
+            self.check_keys(source, &key, value, &[TEMPLATE_KEYS, TREE_KEYS]);
+            self.check_block_variables(source, &key, value);
+            self.check_block_commands(source, &key, value);
+
+            for (entry_key, template) in string_entries(&key, constants::TEMPLATES, value) {
+                if !config.templates.contains_key(template) {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: template does not exist: {template}"),
+                    );
+                }
+            }
+            for (entry_key, tree) in string_entries(&key, constants::EXTEND, value) {
+                if !config.trees.contains_key(tree) {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: tree does not exist: {tree}"),
+                    );
+                }
+            }
+            for (entry_key, tree) in string_entries(&key, constants::WORKTREE, value) {
+                if !syntax::is_eval_candidate(tree) && !config.trees.contains_key(tree) {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: tree does not exist: {tree}"),
+                    );
+                }
+            }
+            for (entry_key, tree) in string_entries(&key, constants::DEPENDS, value) {
+                if query::find_dependency(self.app_context, config, tree).is_none() {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: dependency does not exist: {tree}"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check template blocks.
+    fn check_templates(&mut self, config: &model::Configuration, source: &Source, yaml: &Yaml) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let key = markers::join(constants::TEMPLATES, name);
+            self.check_keys(source, &key, value, &[TEMPLATE_KEYS]);
+            self.check_block_variables(source, &key, value);
+            self.check_block_commands(source, &key, value);
+            for (entry_key, template) in string_entries(&key, constants::EXTEND, value) {
+                if !config.templates.contains_key(template) {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: template does not exist: {template}"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check that group members match at least one tree.
+    fn check_groups(&mut self, config: &model::Configuration, source: &Source, yaml: &Yaml) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let key = markers::join(constants::GROUPS, name);
+            for (entry_key, member) in values(&key, value) {
+                if query::trees_from_pattern(self.app_context, config, None, member, None, None)
+                    .is_empty()
+                {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: group member does not match any trees: {member}"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check that garden groups and trees match existing entries.
+    fn check_gardens(&mut self, config: &model::Configuration, source: &Source, yaml: &Yaml) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let key = markers::join(constants::GARDENS, name);
+            self.check_keys(source, &key, value, &[GARDEN_ENTRY_KEYS]);
+            self.check_block_variables(source, &key, value);
+            self.check_block_commands(source, &key, value);
+            for (entry_key, group) in string_entries(&key, constants::GROUPS, value) {
+                let pattern = glob::Pattern::new(group).unwrap_or_default();
+                if !syntax::is_graft(group) && !config.groups.keys().any(|g| pattern.matches(g)) {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: group does not exist: {group}"),
+                    );
+                }
+            }
+            for (entry_key, tree) in string_entries(&key, constants::TREES, value) {
+                if query::trees_from_pattern(self.app_context, config, None, tree, None, None)
+                    .is_empty()
+                {
+                    self.add(
+                        source.location(&entry_key),
+                        format!("{entry_key}: garden tree does not match any trees: {tree}"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check that grafted configurations were found.
+    fn check_grafts(&mut self, config: &model::Configuration, source: &Source, yaml: &Yaml) {
+        let hash = match yaml {
+            Yaml::Hash(hash) => hash,
+            _ => return,
+        };
+        for (name, value) in hash {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let key = markers::join(constants::GRAFTS, name);
+            self.check_keys(source, &key, value, &[GRAFT_KEYS]);
+            let is_loaded = config
+                .grafts
+                .get(name)
+                .and_then(|graft| graft.get_id())
+                .is_some();
+            if !is_loaded {
+                self.add(
+                    source.location(&key),
+                    format!("{key}: graft configuration not found"),
+                );
+            }
+        }
+    }
+
+    /// Report ${variable} references that are not defined.
+    fn check_variable_references(
+        &mut self,
+        config: &model::Configuration,
+        source: &Source,
+        key: &str,
+        yaml: &Yaml,
+    ) {
+        match yaml {
+            Yaml::Hash(hash) => {
+                for (name, value) in hash {
+                    let name = match name.as_str() {
+                        Some(name) => name,
+                        None => continue,
+                    };
+                    let child_key = markers::join(key, name);
+                    // Environment variable names can contain ${variable} expressions.
+                    self.check_string_references(config, source, &child_key, name);
+                    self.check_variable_references(config, source, &child_key, value);
+                }
+            }
+            Yaml::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    let child_key = markers::join(key, &index.to_string());
+                    self.check_variable_references(config, source, &child_key, value);
+                }
+            }
+            Yaml::String(value) => self.check_string_references(config, source, key, value),
+            _ => (),
+        }
+    }
+
+    fn check_string_references(
+        &mut self,
+        config: &model::Configuration,
+        source: &Source,
+        key: &str,
+        value: &str,
+    ) {
+        // Expressions in tree blocks are evaluated in the context of the tree.
+        let tree_name = key
+            .strip_prefix(constants::TREES)
+            .and_then(|remainder| remainder.strip_prefix('.'))
+            .and_then(|remainder| {
+                config
+                    .trees
+                    .keys()
+                    .filter(|name| {
+                        remainder == name.as_str() || remainder.starts_with(&format!("{name}."))
+                    })
+                    .max_by_key(|name| name.len())
+            });
+        for (name, modifier) in syntax::variable_references(value) {
+            // Variables with ":-" defaults and ":?" errors can be undefined.
+            if modifier.starts_with(":-") || modifier.starts_with(":?") {
+                continue;
+            }
+            if !self.is_defined(config, tree_name.map(String::as_str), name) {
+                self.add(
+                    source.location(key),
+                    format!("{key}: undefined variable: ${{{name}}}"),
+                );
+            }
+        }
+    }
+
+    /// Return true when a variable can be resolved.
+    /// Variables outside of tree blocks can be resolved from any tree or garden.
+    fn is_defined(
+        &self,
+        config: &model::Configuration,
+        tree_name: Option<&str>,
+        name: &str,
+    ) -> bool {
+        if syntax::is_digit(name) || name == constants::TREE_NAME || name == constants::TREE_PATH {
+            return true;
+        }
+        if syntax::is_graft(name) {
+            let graft = config
+                .get_graft_id(name)
+                .or_else(|_| self.app_context.get_root_config().get_graft_id(name));
+            return match graft {
+                Ok((graft_id, remainder)) => {
+                    self.is_defined(self.app_context.get_config(graft_id), None, remainder)
+                }
+                Err(_) => false,
+            };
+        }
+        if config.override_variables.contains_key(name) {
+            return true;
+        }
+        // Options and arguments declared by commands are defined when the command runs.
+        let is_command_param = self
+            .app_context
+            .get_root_config()
+            .command_specs
+            .values()
+            .flat_map(model::CommandSpec::params)
+            .any(|param| param.name == name);
+        if is_command_param {
+            return true;
+        }
+        // Global variables are inherited from graft parents.
+        let mut current = Some(config);
+        while let Some(cfg) = current {
+            if cfg.variables.contains_key(name) {
+                return true;
+            }
+            current = cfg.parent_id.map(|id| self.app_context.get_config(id));
+        }
+        let has_tree_variable = match tree_name {
+            Some(tree_name) => config
+                .trees
+                .get(tree_name)
+                .is_some_and(|tree| tree.variables.contains_key(name)),
+            None => {
+                config
+                    .trees
+                    .values()
+                    .any(|tree| tree.variables.contains_key(name))
+                    || config
+                        .templates
+                        .values()
+                        .any(|template| template.tree.variables.contains_key(name))
+            }
+        };
+        if has_tree_variable
+            || config
+                .gardens
+                .values()
+                .any(|garden| garden.variables.contains_key(name))
+        {
+            return true;
+        }
+        // Variables can also be resolved from environment blocks and the OS environment.
+        let environments = std::iter::once(&config.environment)
+            .chain(config.gardens.values().map(|garden| &garden.environment))
+            .chain(config.trees.values().map(|tree| &tree.environment));
+        for environment in environments {
+            for variable in environment {
+                if variable.get_name().trim_end_matches(['+', '^', '-', '=']) == name {
+                    return true;
+                }
+            }
+        }
+
+        std::env::var_os(name).is_some()
+    }
+
+    /// Report variables that refer to themselves directly or through other variables.
+    fn check_variable_cycles(&mut self, config: &model::Configuration) {
+        let global_lookup = |name: &str| config.variables.get(name);
+        let mut names: Vec<&String> = config.variables.keys().collect();
+        names.sort();
+        for cycle in variable_cycles(&names, &global_lookup) {
+            let key = markers::join(constants::VARIABLES, &cycle[0]);
+            self.add(
+                Self::source_location(config, &key),
+                format!("{key}: self-referential variable: {}", cycle.join(" -> ")),
+            );
+        }
+        for (tree_name, tree) in &config.trees {
+            let tree_lookup = |name: &str| {
+                tree.variables
+                    .get(name)
+                    .or_else(|| config.variables.get(name))
+            };
+            let mut names: Vec<&String> = tree.variables.keys().collect();
+            names.sort();
+            for cycle in variable_cycles(&names, &tree_lookup) {
+                // Cycles between global variables were reported above.
+                if !cycle.iter().any(|name| tree.variables.contains_key(name)) {
+                    continue;
+                }
+                let key = format!(
+                    "{}.{tree_name}.{}.{}",
+                    constants::TREES,
+                    constants::VARIABLES,
+                    cycle[0]
+                );
+                self.add(
+                    Self::source_location(config, &key),
+                    format!("{key}: self-referential variable: {}", cycle.join(" -> ")),
+                );
+            }
+        }
+    }
+
+    /// Report trees that share the same path.
+    fn check_tree_paths(&mut self, config: &model::Configuration) {
+        let mut paths: HashMap<String, &str> = HashMap::new();
+        for (tree_name, tree) in &config.trees {
+            let path = match tree.path_as_ref() {
+                Ok(path) => path,
+                Err(_) => continue,
+            };
+            match paths.get(path) {
+                Some(other) => {
+                    let key = markers::join(constants::TREES, tree_name);
+                    self.add(
+                        Self::source_location(config, &key),
+                        format!("{key}: path is also used by tree {other}: {path}"),
+                    );
+                }
+                None => {
+                    paths.insert(path.to_string(), tree_name);
+                }
+            }
+        }
+    }
+}
+
+/// Return the key paths and values for a string or list of strings.
+fn values<'y>(key: &str, yaml: &'y Yaml) -> Vec<(String, &'y str)> {
+    match yaml {
+        Yaml::String(value) => vec![(key.to_string(), value.as_str())],
+        Yaml::Array(entries) => entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                entry
+                    .as_str()
+                    .map(|value| (markers::join(key, &index.to_string()), value))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Return the key paths and values for a string or list of strings stored in a field.
+fn string_entries<'y>(parent: &str, field: &str, yaml: &'y Yaml) -> Vec<(String, &'y str)> {
+    values(&markers::join(parent, field), &yaml[field])
+}
+
+/// Return one reference cycle for each set of variables that refer to each other.
+/// Each cycle starts from the first of its variables in the specified order.
+fn variable_cycles<'v, F>(names: &[&String], lookup: &F) -> Vec<Vec<String>>
+where
+    F: Fn(&str) -> Option<&'v model::Variable>,
+{
+    let mut components = ReferenceComponents {
+        lookup,
+        indexes: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+    for name in names {
+        if !components.indexes.contains_key(name.as_str()) {
+            components.visit(name);
+        }
+    }
+
+    components
+        .components
+        .iter()
+        .filter_map(|component| {
+            let start = names
+                .iter()
+                .find(|name| component.contains(name))
+                .map_or(component[0].as_str(), |name| name.as_str());
+            component_cycle(start, component, lookup)
+        })
+        .collect()
+}
+
+/// Find the strongly connected components of the variable reference graph
+/// using Tarjan's algorithm. Only components that contain a cycle are kept.
+struct ReferenceComponents<'f, F> {
+    lookup: &'f F,
+    /// The order in which each variable was visited.
+    indexes: HashMap<String, usize>,
+    stack: Vec<String>,
+    on_stack: HashSet<String>,
+    components: Vec<Vec<String>>,
+}
+
+impl<'v, F> ReferenceComponents<'_, F>
+where
+    F: Fn(&str) -> Option<&'v model::Variable>,
+{
+    /// Visit a variable and return the lowest index reachable from it.
+    fn visit(&mut self, name: &str) -> usize {
+        let index = self.indexes.len();
+        self.indexes.insert(name.to_string(), index);
+        self.stack.push(name.to_string());
+        self.on_stack.insert(name.to_string());
+
+        let references = references(name, self.lookup);
+        let mut low_link = index;
+        for reference in &references {
+            match self.indexes.get(reference) {
+                None => low_link = low_link.min(self.visit(reference)),
+                Some(reference_index) if self.on_stack.contains(reference) => {
+                    low_link = low_link.min(*reference_index);
+                }
+                Some(_) => (),
+            }
+        }
+        if low_link == index {
+            let position = self
+                .stack
+                .iter()
+                .rposition(|entry| entry == name)
+                .unwrap_or_default();
+            let component = self.stack.split_off(position);
+            for entry in &component {
+                self.on_stack.remove(entry);
+            }
+            if component.len() > 1 || references.iter().any(|reference| reference == name) {
+                self.components.push(component);
+            }
+        }
+
+        low_link
+    }
+}
+
+/// Return the shortest reference cycle from the start variable back to itself
+/// through the variables in a strongly connected component.
+fn component_cycle<'v, F>(start: &str, component: &[String], lookup: &F) -> Option<Vec<String>>
+where
+    F: Fn(&str) -> Option<&'v model::Variable>,
+{
+    let mut previous: HashMap<String, String> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([start.to_string()]);
+    while let Some(current) = queue.pop_front() {
+        for reference in references(&current, lookup) {
+            if reference == start {
+                let mut cycle = vec![current];
+                while let Some(name) = cycle.last().and_then(|last| previous.get(last)) {
+                    cycle.push(name.to_string());
+                }
+                cycle.reverse();
+                cycle.push(start.to_string());
+                return Some(cycle);
+            }
+            if component.contains(&reference) && !previous.contains_key(&reference) {
+                previous.insert(reference.clone(), current.clone());
+                queue.push_back(reference);
+            }
+        }
+    }
+
+    None
+}
+
+/// Return the names of the variables referenced by a variable.
+fn references<'v, F>(name: &str, lookup: &F) -> Vec<String>
+where
+    F: Fn(&str) -> Option<&'v model::Variable>,
+{
+    match lookup(name) {
+        Some(variable) => syntax::variable_references(variable.get_expr())
+            .into_iter()
+            .map(|(reference, _)| reference.to_string())
+            .collect(),
+        None => Vec::new(),
+    }
+}
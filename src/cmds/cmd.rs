@@ -1,8 +1,10 @@
+use std::io::prelude::*;
+
 use anyhow::Result;
 use clap::{CommandFactory, FromArgMatches, Parser};
 use derivative::Derivative;
 
-use crate::{cli, cmd, constants, display, errors, eval, model, query};
+use crate::{cli, cmd, constants, display, errors, eval, git, model, query};
 
 /// Run one or more custom commands over a tree query
 #[derive(Parser, Clone, Debug)]
@@ -11,9 +13,22 @@ pub struct CmdOptions {
     /// Run a command in all trees before running the next command
     #[arg(long, short)]
     breadth_first: bool,
+    /// Only include trees with uncommitted changes or with commits that differ from REF
+    #[arg(long, value_name = "REF")]
+    changed_since: Option<String>,
     /// Continue to the next tree when errors occur
     #[arg(long, short)]
     keep_going: bool,
+    /// Run commands in multiple trees concurrently using the specified number of jobs.
+    /// Output is buffered per tree and displayed in tree order.
+    #[arg(long = "jobs", short = 'j', default_value_t = 1)]
+    num_jobs: usize,
+    /// Display a summary of the commands that passed, failed or were skipped in each tree
+    #[arg(long)]
+    summary: bool,
+    /// Write the summary as JSON to the specified file. Use "-" to write to stdout.
+    #[arg(long, value_name = "FILE")]
+    summary_json: Option<String>,
     /// Filter trees by name post-query using a glob pattern
     #[arg(long, short, default_value = "*")]
     trees: String,
@@ -49,9 +64,22 @@ pub struct CmdOptions {
 #[derive(Parser, Clone, Debug)]
 #[command(bin_name = constants::GARDEN)]
 pub struct CustomOptions {
+    /// Only include trees with uncommitted changes or with commits that differ from REF
+    #[arg(long, value_name = "REF")]
+    changed_since: Option<String>,
     /// Continue to the next tree when errors occur
     #[arg(long, short)]
     keep_going: bool,
+    /// Run commands in multiple trees concurrently using the specified number of jobs.
+    /// Output is buffered per tree and displayed in tree order.
+    #[arg(long = "jobs", short = 'j', default_value_t = 1)]
+    num_jobs: usize,
+    /// Display a summary of the commands that passed, failed or were skipped in each tree
+    #[arg(long)]
+    summary: bool,
+    /// Write the summary as JSON to the specified file. Use "-" to write to stdout.
+    #[arg(long, value_name = "FILE")]
+    summary_json: Option<String>,
     /// Filter trees by name post-query using a glob pattern
     #[arg(long, short, default_value = "*")]
     trees: String,
@@ -88,6 +116,8 @@ pub fn main_cmd(app_context: &model::ApplicationContext, options: &mut CmdOption
         debug!("commands: {:?}", options.commands);
         debug!("arguments: {:?}", options.arguments);
         debug!("trees: {:?}", options.trees);
+        debug!("changed since: {:?}", options.changed_since);
+        debug!("jobs: {}", options.num_jobs);
     }
     if !app_context.get_root_config().shell_exit_on_error {
         options.exit_on_error = false;
@@ -95,8 +125,13 @@ pub fn main_cmd(app_context: &model::ApplicationContext, options: &mut CmdOption
     if !app_context.get_root_config().shell_word_split {
         options.word_split = false;
     }
+    // "garden cmd" does not parse the options and arguments declared by commands.
+    // Their defaults are used unless values are provided using "garden -D name=value".
+    set_command_defaults(app_context, &options.commands);
     let params: CmdParams = options.clone().into();
-    let exit_status = cmd(app_context, &options.query, &params)?;
+    let mut summary = CmdSummary::default();
+    let exit_status = cmd(app_context, &options.query, &params, &mut summary)?;
+    summary.display(&params)?;
 
     cmd::result_from_exit_status(exit_status).map_err(|err| err.into())
 }
@@ -112,13 +147,27 @@ pub struct CmdParams {
     queries: Vec<String>,
     tree_pattern: glob::Pattern,
     breadth_first: bool,
+    changed_since: Option<String>,
     keep_going: bool,
+    #[derivative(Default(value = "1"))]
+    num_jobs: usize,
+    summary: bool,
+    summary_json: Option<String>,
     #[derivative(Default(value = "true"))]
     exit_on_error: bool,
     #[derivative(Default(value = "true"))]
     word_split: bool,
 }
 
+impl CmdParams {
+    /// Return true when "--changed-since" was specified and the tree has no changes.
+    fn is_unchanged(&self, tree: &model::Tree) -> bool {
+        self.changed_since
+            .as_ref()
+            .is_some_and(|reference| !git::tree_has_changes_since(tree, reference))
+    }
+}
+
 /// Build CmdParams from a CmdOptions struct.
 impl From<CmdOptions> for CmdParams {
     fn from(options: CmdOptions) -> Self {
@@ -126,8 +175,12 @@ impl From<CmdOptions> for CmdParams {
             commands: options.commands.clone(),
             arguments: options.arguments.clone(),
             breadth_first: options.breadth_first,
+            changed_since: options.changed_since.clone(),
             exit_on_error: options.exit_on_error,
             keep_going: options.keep_going,
+            num_jobs: options.num_jobs,
+            summary: options.summary,
+            summary_json: options.summary_json.clone(),
             tree_pattern: glob::Pattern::new(&options.trees).unwrap_or_default(),
             word_split: options.word_split,
             ..Default::default()
@@ -147,7 +200,11 @@ impl From<CustomOptions> for CmdParams {
             // use breadth-first because it retains the original implementation/behavior from before
             // --breadth-first was added to "garden cmd" and made opt-in.
             breadth_first: true,
+            changed_since: options.changed_since.clone(),
             keep_going: options.keep_going,
+            num_jobs: options.num_jobs,
+            summary: options.summary,
+            summary_json: options.summary_json.clone(),
             exit_on_error: options.exit_on_error,
             tree_pattern: glob::Pattern::new(&options.trees).unwrap_or_default(),
             word_split: options.word_split,
@@ -174,7 +231,22 @@ pub fn main_custom(app_context: &model::ApplicationContext, arguments: &Vec<Stri
     // Set the command name to "garden <custom>".
     let name = &arguments[0];
     let garden_custom = format!("garden {name}");
-    let cli = CustomOptions::command().bin_name(garden_custom);
+    let mut cli = CustomOptions::command().bin_name(garden_custom);
+    let spec = app_context.get_root_config().command_specs.get(name);
+    if let Some(spec) = spec {
+        if let Some((key, problem)) = command_spec_problems(name, spec).into_iter().next() {
+            let config = app_context.get_root_config();
+            let source = match config.describe_source(&key) {
+                Some(source) => format!(" ({source})"),
+                None => String::new(),
+            };
+            return Err(errors::GardenError::ConfigurationError(format!(
+                "{key}: {problem}{source}"
+            ))
+            .into());
+        }
+        cli = add_command_params(cli, spec);
+    }
     let matches = cli.get_matches_from(arguments);
 
     let mut options = <CustomOptions as FromArgMatches>::from_arg_matches(&matches)
@@ -191,6 +263,12 @@ pub fn main_custom(app_context: &model::ApplicationContext, arguments: &Vec<Stri
         debug!("queries: {:?}", options.queries);
         debug!("arguments: {:?}", options.arguments);
         debug!("trees: {:?}", options.trees);
+        debug!("changed since: {:?}", options.changed_since);
+        debug!("jobs: {}", options.num_jobs);
+    }
+
+    if let Some(spec) = spec {
+        set_command_variables(app_context, spec, &matches);
     }
 
     // Add the custom command name to the list of commands. cmds() operates on a vec of commands.
@@ -200,6 +278,175 @@ pub fn main_custom(app_context: &model::ApplicationContext, arguments: &Vec<Stri
     cmds(app_context, &params)
 }
 
+/// Add the description, options and arguments declared by a command to its clap command.
+/// Declared arguments are positional and precede the tree queries.
+pub(crate) fn add_command_params(
+    mut command: clap::Command,
+    spec: &model::CommandSpec,
+) -> clap::Command {
+    if !spec.description.is_empty() {
+        command = command.about(spec.description.clone());
+    }
+    for param in &spec.options {
+        let mut arg = command_param_arg(param).long(param.name.clone());
+        if let Some(short) = param.short {
+            arg = arg.short(short);
+        }
+        command = command.arg(arg);
+    }
+    for (index, param) in spec.args.iter().enumerate() {
+        command = command.arg(command_param_arg(param).index(index + 1));
+    }
+    let num_args = spec.args.len();
+
+    command
+        .mut_arg("queries", |arg| arg.index(num_args + 1))
+        .mut_arg("arguments", |arg| arg.index(num_args + 2))
+}
+
+/// Return the clap argument for a declared option or argument.
+fn command_param_arg(param: &model::CommandParam) -> clap::Arg {
+    let mut arg = clap::Arg::new(param.name.clone())
+        .help(param.description.clone())
+        .required(param.required);
+    if let Some(default) = &param.default {
+        arg = arg.default_value(default.clone());
+    }
+    if !param.choices.is_empty() {
+        arg = arg.value_parser(clap::builder::PossibleValuesParser::new(
+            param.choices.clone(),
+        ));
+    }
+
+    arg
+}
+
+/// Return the problems that prevent a command's options and arguments from being used.
+/// Problems are returned along with the key path of the option or argument.
+pub(crate) fn command_spec_problems(
+    name: &str,
+    spec: &model::CommandSpec,
+) -> Vec<(String, String)> {
+    let mut problems = Vec::new();
+    let builtin = CustomOptions::command();
+    let mut names: Vec<String> = builtin
+        .get_arguments()
+        .flat_map(|arg| {
+            [
+                Some(arg.get_id().to_string()),
+                arg.get_long().map(String::from),
+            ]
+        })
+        .flatten()
+        .chain([string!("help")])
+        .collect();
+    let mut shorts: Vec<char> = builtin
+        .get_arguments()
+        .filter_map(clap::Arg::get_short)
+        .chain(['h'])
+        .collect();
+    let params = spec
+        .options
+        .iter()
+        .map(|param| (constants::OPTIONS, param))
+        .chain(spec.args.iter().map(|param| (constants::ARGS, param)));
+    let mut has_optional_arg = false;
+    for (kind, param) in params {
+        let key = format!("{}.{name}.{kind}.{}", constants::COMMANDS, param.name);
+        if names.contains(&param.name) {
+            problems.push((key.clone(), string!("name is already in use")));
+        }
+        names.push(param.name.clone());
+        if let Some(short) = param.short.filter(|_| kind == constants::OPTIONS) {
+            if shorts.contains(&short) {
+                problems.push((
+                    key.clone(),
+                    format!("short option is already in use: -{short}"),
+                ));
+            }
+            shorts.push(short);
+        }
+        if let Some(default) = &param.default {
+            if !param.choices.is_empty() && !param.choices.contains(default) {
+                problems.push((
+                    key.clone(),
+                    format!("default is not one of the choices: {default}"),
+                ));
+            }
+        }
+        if kind == constants::ARGS {
+            if param.required && has_optional_arg {
+                problems.push((
+                    key.clone(),
+                    string!("required arguments must come before optional arguments"),
+                ));
+            }
+            has_optional_arg |= !param.required;
+        }
+    }
+
+    problems
+}
+
+/// Store the values parsed for a command's options and arguments as variables.
+/// Values that were defined using "garden -D name=value" are retained unless
+/// they are specified on the command-line.
+fn set_command_variables(
+    app_context: &model::ApplicationContext,
+    spec: &model::CommandSpec,
+    matches: &clap::ArgMatches,
+) {
+    let config = app_context.get_root_config_mut();
+    for param in spec.params() {
+        let value = matches
+            .get_one::<String>(&param.name)
+            .cloned()
+            .unwrap_or_default();
+        if app_context.options.debug_level(constants::DEBUG_LEVEL_CMD) > 0 {
+            debug!("parameter: {} = {}", param.name, value);
+        }
+        let variable = model::Variable::new(value.clone(), Some(value));
+        // Default values do not replace values defined using "garden -D name=value".
+        match matches.value_source(&param.name) {
+            Some(clap::parser::ValueSource::CommandLine) => {
+                config
+                    .override_variables
+                    .insert(param.name.clone(), variable);
+            }
+            _ => {
+                config
+                    .override_variables
+                    .entry(param.name.clone())
+                    .or_insert(variable);
+            }
+        }
+    }
+}
+
+/// Store the default values for the options and arguments declared by commands.
+/// Values that were defined using "garden -D name=value" are retained.
+fn set_command_defaults(app_context: &model::ApplicationContext, names: &[String]) {
+    let specs = &app_context.get_root_config().command_specs;
+    let defaults: Vec<(String, String)> = names
+        .iter()
+        .filter_map(|name| specs.get(name))
+        .flat_map(|spec| spec.params())
+        .map(|param| {
+            (
+                param.name.clone(),
+                param.default.clone().unwrap_or_default(),
+            )
+        })
+        .collect();
+    let config = app_context.get_root_config_mut();
+    for (name, value) in defaults {
+        config
+            .override_variables
+            .entry(name)
+            .or_insert_with(|| model::Variable::new(value.clone(), Some(value)));
+    }
+}
+
 /// Strategy: resolve the trees down to a set of tree indexes paired with
 /// an optional garden context.
 ///
@@ -209,14 +456,31 @@ pub fn main_custom(app_context: &model::ApplicationContext, arguments: &Vec<Stri
 ///
 /// If the names resolve to trees, each tree is processed independently
 /// with no garden context.
-
-fn cmd(app_context: &model::ApplicationContext, query: &str, params: &CmdParams) -> Result<i32> {
+///
+/// Trees are processed after the trees listed in their "depends" field.
+fn cmd(
+    app_context: &model::ApplicationContext,
+    query: &str,
+    params: &CmdParams,
+    summary: &mut CmdSummary,
+) -> Result<i32> {
     let config = app_context.get_root_config_mut();
-    let contexts = query::resolve_trees(app_context, config, None, query);
+    let contexts = query::resolve_trees(app_context, config, None, query)?;
+    let waves = query::dependency_waves(app_context, contexts)?;
+    if app_context.options.debug_level(constants::DEBUG_LEVEL_CMD) > 1 {
+        for (index, wave) in waves.iter().enumerate() {
+            let trees: Vec<&str> = wave.iter().map(|context| context.tree.as_str()).collect();
+            debug!("wave {index}: {}", trees.join(" "));
+        }
+    }
+    if params.num_jobs > 1 {
+        return run_cmd_parallel(app_context, &waves, params, summary);
+    }
+    let contexts = waves.concat();
     if params.breadth_first {
-        run_cmd_breadth_first(app_context, &contexts, params)
+        run_cmd_breadth_first(app_context, &contexts, params, summary)
     } else {
-        run_cmd_depth_first(app_context, &contexts, params)
+        run_cmd_depth_first(app_context, &contexts, params, summary)
     }
 }
 
@@ -235,6 +499,7 @@ fn run_cmd_breadth_first(
     app_context: &model::ApplicationContext,
     contexts: &[model::TreeContext],
     params: &CmdParams,
+    summary: &mut CmdSummary,
 ) -> Result<i32> {
     let mut exit_status: i32 = errors::EX_OK;
     let quiet = app_context.options.quiet;
@@ -243,11 +508,16 @@ fn run_cmd_breadth_first(
     let shell_params = ShellParams::new(shell, params.exit_on_error, params.word_split);
     // Loop over each command, evaluate the tree environment,
     // and run the command in each context.
-    for name in &params.commands {
+    for (index, name) in params.commands.iter().enumerate() {
+        // Skipped trees are only recorded in the summary once.
+        let record_skipped = index == 0;
         // One invocation runs multiple commands
         for context in contexts {
             // Skip filtered trees.
             if !params.tree_pattern.matches(&context.tree) {
+                if record_skipped {
+                    summary.skip(&context.tree, SKIPPED_FILTERED);
+                }
                 continue;
             }
             // Skip symlink trees.
@@ -260,39 +530,56 @@ fn run_cmd_breadth_first(
                 None => continue,
             };
             if tree.is_symlink {
+                if record_skipped {
+                    summary.skip(&context.tree, SKIPPED_SYMLINK);
+                }
+                continue;
+            }
+            if params.is_unchanged(tree) {
+                if record_skipped {
+                    summary.skip(&context.tree, SKIPPED_UNCHANGED);
+                }
                 continue;
             }
             // Evaluate the tree environment
-            let env = eval::environment(app_context, config, context);
+            let env = eval::environment(app_context, config, context)?;
 
             // Run each command in the tree's context
             let path = tree.path_as_ref()?.to_string();
             // Sparse gardens/missing trees are ok -> skip these entries.
             if !display::print_tree(tree, config.tree_branches, verbose, quiet) {
+                if record_skipped {
+                    summary.skip(&context.tree, SKIPPED_MISSING);
+                }
                 continue;
             }
 
             // Expand one named command to include its pre-commands and post-commands.
-            let command_names = cmd::expand_command_names(app_context, context, name);
+            let command_names = cmd::expand_command_names(app_context, context, name)?;
             for command_name in command_names.iter() {
                 // One command maps to multiple command sequences.
                 // When the scope is tree, only the tree's commands
                 // are included.  When the scope includes a gardens,
                 // its matching commands are appended to the end.
-                let cmd_seq_vec = eval::command(app_context, context, command_name);
+                let cmd_seq_vec =
+                    eval::command_sequences(app_context, context, command_name, &env)?;
                 app_context.get_root_config_mut().reset();
 
-                if let Err(cmd_status) = run_cmd_vec(
+                let start = std::time::Instant::now();
+                let result = run_cmd_vec(
                     &app_context.options,
                     &path,
                     &shell_params,
                     &env,
                     &cmd_seq_vec,
                     params,
-                ) {
-                    exit_status = cmd_status;
+                    None,
+                );
+                summary.record(&context.tree, command_name, start.elapsed(), &result);
+                if let Err(errors::CommandError::ExitStatus { status, .. }) = result {
+                    exit_status = status;
                     if !params.keep_going {
-                        return Ok(cmd_status);
+                        return Ok(status);
                     }
                 }
             }
@@ -317,12 +604,12 @@ impl ShellParams {
         let basename = if shell_command[0].contains('/') {
             shell_command[0]
                 .split('/')
-                .last()
+                .next_back()
                 .unwrap_or(&shell_command[0])
         } else if shell_command[0].contains('\\') {
             shell_command[0]
                 .split('\\')
-                .last()
+                .next_back()
                 .unwrap_or(&shell_command[0])
         } else {
             &shell_command[0]
@@ -376,6 +663,7 @@ fn run_cmd_depth_first(
     app_context: &model::ApplicationContext,
     contexts: &[model::TreeContext],
     params: &CmdParams,
+    summary: &mut CmdSummary,
 ) -> Result<i32> {
     let mut exit_status: i32 = errors::EX_OK;
     let quiet = app_context.options.quiet;
@@ -386,6 +674,7 @@ fn run_cmd_depth_first(
     for context in contexts {
         // Skip filtered trees.
         if !params.tree_pattern.matches(&context.tree) {
+            summary.skip(&context.tree, SKIPPED_FILTERED);
             continue;
         }
         // Skip symlink trees.
@@ -398,38 +687,49 @@ fn run_cmd_depth_first(
             None => continue,
         };
         if tree.is_symlink {
+            summary.skip(&context.tree, SKIPPED_SYMLINK);
+            continue;
+        }
+        if params.is_unchanged(tree) {
+            summary.skip(&context.tree, SKIPPED_UNCHANGED);
             continue;
         }
         // Evaluate the tree environment
-        let env = eval::environment(app_context, config, context);
+        let env = eval::environment(app_context, config, context)?;
         // Run each command in the tree's context
         let path = tree.path_as_ref()?.to_string();
         // Sparse gardens/missing trees are ok -> skip these entries.
         if !display::print_tree(tree, config.tree_branches, verbose, quiet) {
+            summary.skip(&context.tree, SKIPPED_MISSING);
             continue;
         }
         // One invocation runs multiple commands
         for name in &params.commands {
             // Expand one named command to include its pre-commands and post-commands.
-            let command_names = cmd::expand_command_names(app_context, context, name);
+            let command_names = cmd::expand_command_names(app_context, context, name)?;
             for command_name in command_names.iter() {
                 // One command maps to multiple command sequences.
                 // When the scope is tree, only the tree's commands
                 // are included.  When the scope includes a gardens,
                 // its matching commands are appended to the end.
-                let cmd_seq_vec = eval::command(app_context, context, command_name);
+                let cmd_seq_vec =
+                    eval::command_sequences(app_context, context, command_name, &env)?;
                 app_context.get_root_config_mut().reset();
-                if let Err(cmd_status) = run_cmd_vec(
+                let start = std::time::Instant::now();
+                let result = run_cmd_vec(
                     &app_context.options,
                     &path,
                     &shell_params,
                     &env,
                     &cmd_seq_vec,
                     params,
-                ) {
-                    exit_status = cmd_status;
+                    None,
+                );
+                summary.record(&context.tree, command_name, start.elapsed(), &result);
+                if let Err(errors::CommandError::ExitStatus { status, .. }) = result {
+                    exit_status = status;
                     if !params.keep_going {
-                        return Ok(cmd_status);
+                        return Ok(status);
                     }
                 }
             }
@@ -440,34 +740,367 @@ fn run_cmd_depth_first(
     Ok(exit_status)
 }
 
+/// Output captured from commands that run in parallel.
+#[derive(Debug, Default)]
+struct CapturedOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// The commands to run in a single tree when running in parallel.
+/// Tree environments and commands are evaluated up front on the main thread.
+struct TreeJob {
+    tree: String,
+    /// The tree header, or the missing tree message when the tree does not exist.
+    header: Option<String>,
+    /// Missing trees are displayed but their commands are not run.
+    exists: bool,
+    /// The reason that the tree is skipped, recorded in the summary.
+    skipped: Option<&'static str>,
+    path: String,
+    env: Vec<(String, String)>,
+    /// Command sequences paired with the name of the command that they were evaluated from.
+    cmd_seq_vecs: Vec<(String, Vec<model::CommandSequence>)>,
+}
+
+/// The result of running a TreeJob. Skipped jobs have no result.
+struct TreeJobResult {
+    output: CapturedOutput,
+    exit_status: i32,
+    summary: CmdSummary,
+}
+
+/// Run commands in multiple trees concurrently.
+/// Each wave of trees finishes before the trees in the next wave are started.
+/// When running breadth-first each command is run in all trees before running the next.
+fn run_cmd_parallel(
+    app_context: &model::ApplicationContext,
+    waves: &[Vec<model::TreeContext>],
+    params: &CmdParams,
+    summary: &mut CmdSummary,
+) -> Result<i32> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(params.num_jobs)
+        .build()?;
+    let shell = app_context.get_root_config().shell.as_str();
+    let shell_params = ShellParams::new(shell, params.exit_on_error, params.word_split);
+    let command_groups: Vec<&[String]> = if params.breadth_first {
+        params.commands.iter().map(std::slice::from_ref).collect()
+    } else {
+        vec![params.commands.as_slice()]
+    };
+    let mut exit_status: i32 = errors::EX_OK;
+    for (index, names) in command_groups.iter().enumerate() {
+        // Skipped trees are only recorded in the summary once.
+        let record_skipped = index == 0;
+        for contexts in waves {
+            let jobs = tree_jobs(app_context, contexts, params, names, record_skipped)?;
+            let status = run_tree_jobs(
+                &pool,
+                &app_context.options,
+                &shell_params,
+                &jobs,
+                params,
+                summary,
+            );
+            if status != errors::EX_OK {
+                exit_status = status;
+                if !params.keep_going {
+                    return Ok(status);
+                }
+            }
+        }
+    }
+
+    // Return the last non-zero exit status.
+    Ok(exit_status)
+}
+
+/// Evaluate the environment and command sequences for each tree.
+/// Skipped trees are included so that they are recorded in the summary in tree order.
+fn tree_jobs(
+    app_context: &model::ApplicationContext,
+    contexts: &[model::TreeContext],
+    params: &CmdParams,
+    names: &[String],
+    record_skipped: bool,
+) -> Result<Vec<TreeJob>> {
+    let quiet = app_context.options.quiet;
+    let verbose = app_context.options.verbose;
+    let mut jobs = Vec::with_capacity(contexts.len());
+    for context in contexts {
+        let mut job = TreeJob {
+            tree: context.tree.to_string(),
+            header: None,
+            exists: false,
+            skipped: None,
+            path: String::new(),
+            env: Vec::new(),
+            cmd_seq_vecs: Vec::new(),
+        };
+        // Skip filtered trees.
+        if !params.tree_pattern.matches(&context.tree) {
+            if record_skipped {
+                job.skipped = Some(SKIPPED_FILTERED);
+                jobs.push(job);
+            }
+            continue;
+        }
+        // Skip symlink trees.
+        let config = match context.config {
+            Some(config_id) => app_context.get_config(config_id),
+            None => app_context.get_root_config(),
+        };
+        let tree = match config.trees.get(&context.tree) {
+            Some(tree) => tree,
+            None => continue,
+        };
+        if tree.is_symlink {
+            if record_skipped {
+                job.skipped = Some(SKIPPED_SYMLINK);
+                jobs.push(job);
+            }
+            continue;
+        }
+        if params.is_unchanged(tree) {
+            if record_skipped {
+                job.skipped = Some(SKIPPED_UNCHANGED);
+                jobs.push(job);
+            }
+            continue;
+        }
+        job.path = tree.path_as_ref()?.to_string();
+        // Sparse gardens/missing trees are ok -> skip these entries.
+        match display::display_tree_entry(tree, config.tree_branches, verbose) {
+            Ok(header) => {
+                if !quiet {
+                    job.header = Some(header);
+                }
+                job.exists = true;
+            }
+            Err(missing) => {
+                if !quiet {
+                    job.header = Some(missing);
+                }
+                if record_skipped {
+                    job.skipped = Some(SKIPPED_MISSING);
+                }
+                jobs.push(job);
+                continue;
+            }
+        }
+        job.env = eval::environment(app_context, config, context)?;
+        for name in names {
+            // Expand one named command to include its pre-commands and post-commands.
+            let command_names = cmd::expand_command_names(app_context, context, name)?;
+            for command_name in command_names.iter() {
+                let cmd_seq_vec =
+                    eval::command_sequences(app_context, context, command_name, &job.env)?;
+                job.cmd_seq_vecs
+                    .push((command_name.to_string(), cmd_seq_vec));
+                app_context.get_root_config_mut().reset();
+            }
+        }
+        jobs.push(job);
+    }
+
+    Ok(jobs)
+}
+
+/// Run tree jobs in the thread pool and display their output in tree order.
+/// Returns the first non-zero exit status, or the last non-zero exit status when
+/// params.keep_going is enabled.
+fn run_tree_jobs(
+    pool: &rayon::ThreadPool,
+    options: &cli::MainOptions,
+    shell_params: &ShellParams,
+    jobs: &[TreeJob],
+    params: &CmdParams,
+    summary: &mut CmdSummary,
+) -> i32 {
+    // Jobs that have not started yet are skipped once a job fails without --keep-going.
+    let stop = std::sync::atomic::AtomicBool::new(false);
+    let (send_result, recv_result) = crossbeam::channel::unbounded();
+    let mut exit_status: i32 = errors::EX_OK;
+
+    pool.in_place_scope_fifo(|scope| {
+        for (index, job) in jobs.iter().enumerate() {
+            let send_result = send_result.clone();
+            let stop = &stop;
+            scope.spawn_fifo(move |_| {
+                if !job.exists || stop.load(std::sync::atomic::Ordering::SeqCst) {
+                    send_result.send((index, None)).unwrap_or(());
+                    return;
+                }
+                let mut output = CapturedOutput::default();
+                let mut job_status = errors::EX_OK;
+                let mut job_summary = CmdSummary::default();
+                for (command_name, cmd_seq_vec) in &job.cmd_seq_vecs {
+                    let start = std::time::Instant::now();
+                    let result = run_cmd_vec(
+                        options,
+                        &job.path,
+                        shell_params,
+                        &job.env,
+                        cmd_seq_vec,
+                        params,
+                        Some(&mut output),
+                    );
+                    job_summary.record(&job.tree, command_name, start.elapsed(), &result);
+                    if let Err(errors::CommandError::ExitStatus { status, .. }) = result {
+                        job_status = status;
+                        if !params.keep_going {
+                            stop.store(true, std::sync::atomic::Ordering::SeqCst);
+                            break;
+                        }
+                    }
+                }
+                let result = TreeJobResult {
+                    output,
+                    exit_status: job_status,
+                    summary: job_summary,
+                };
+                send_result.send((index, Some(result))).unwrap_or(());
+            });
+        }
+
+        // Display results in tree order as soon as all of the preceding trees have finished.
+        let mut results: Vec<Option<Option<TreeJobResult>>> =
+            (0..jobs.len()).map(|_| None).collect();
+        let mut next_index = 0;
+        while next_index < jobs.len() {
+            let (index, result) = match recv_result.recv() {
+                Ok(message) => message,
+                Err(_) => break,
+            };
+            results[index] = Some(result);
+            while next_index < jobs.len() {
+                let result = match results[next_index].take() {
+                    Some(result) => result,
+                    None => break,
+                };
+                let job = &jobs[next_index];
+                next_index += 1;
+                if let Some(reason) = job.skipped {
+                    summary.skip(&job.tree, reason);
+                }
+                // Trees that were skipped after an earlier failure display nothing.
+                if job.exists && result.is_none() {
+                    continue;
+                }
+                if let Some(header) = &job.header {
+                    eprintln!("{header}");
+                }
+                let mut result = match result {
+                    Some(result) => result,
+                    None => continue,
+                };
+                summary.results.append(&mut result.summary.results);
+                std::io::stdout()
+                    .write_all(&result.output.stdout)
+                    .unwrap_or(());
+                std::io::stdout().flush().unwrap_or(());
+                std::io::stderr()
+                    .write_all(&result.output.stderr)
+                    .unwrap_or(());
+                if result.exit_status != errors::EX_OK
+                    && (params.keep_going || exit_status == errors::EX_OK)
+                {
+                    exit_status = result.exit_status;
+                }
+            }
+        }
+    });
+
+    exit_status
+}
+
 /// Run a vector of custom commands using the configured shell.
 /// Parameters:
 /// - path: The current working directory for the command.
 /// - shell: The shell that will be used to run the command strings.
 /// - env: Environment variables to set.
-/// - cmd_seq_vec: Vector of vector of command strings to run.
+/// - cmd_seq_vec: Command sequences to run. Sequences can override the shell,
+///   the working directory and the environment.
 /// - arguments: Additional command line arguments available in $1, $2, $N.
+/// - output: Buffer that captures stdout and stderr instead of inheriting them.
+///
+/// Returns the command string that failed along with its exit status.
 fn run_cmd_vec(
     options: &cli::MainOptions,
     path: &str,
     shell_params: &ShellParams,
-    env: &Vec<(String, String)>,
-    cmd_seq_vec: &[Vec<String>],
+    env: &[(String, String)],
+    cmd_seq_vec: &[model::CommandSequence],
     params: &CmdParams,
-) -> Result<(), i32> {
+    mut output: Option<&mut CapturedOutput>,
+) -> Result<(), errors::CommandError> {
     // Get the current executable name
     let current_exe = cmd::current_exe();
     let mut exit_status = errors::EX_OK;
+    let mut failed_command = String::new();
     for cmd_seq in cmd_seq_vec {
-        for cmd_str in cmd_seq {
+        let cmd_shell_params;
+        let shell_params = match &cmd_seq.shell {
+            Some(shell) => {
+                cmd_shell_params = ShellParams::new(shell, params.exit_on_error, params.word_split);
+                &cmd_shell_params
+            }
+            None => shell_params,
+        };
+        // Relative directories are relative to the tree.
+        let cwd = match &cmd_seq.cwd {
+            Some(cwd) => std::path::Path::new(path).join(cwd),
+            None => std::path::PathBuf::from(path),
+        };
+        if cmd_seq.cwd.is_some() && !cwd.is_dir() {
+            let message = format!(
+                "error: {}: directory does not exist: {}",
+                cmd_seq.name,
+                cwd.display()
+            );
+            match output.as_deref_mut() {
+                Some(output) => {
+                    output.stderr.extend_from_slice(message.as_bytes());
+                    output.stderr.push(b'\n');
+                }
+                None => eprintln!("{message}"),
+            }
+            return Err(errors::CommandError::ExitStatus {
+                command: cmd_seq.name.to_string(),
+                status: errors::EX_CONFIG,
+            });
+        }
+        if options.verbose > 1 && cmd_seq.cwd.is_some() {
+            let cwd_display = format!(
+                "{} {}",
+                display::Color::cyan(":"),
+                display::Color::green(format!("cd {}", cwd.display())),
+            );
+            match output.as_deref_mut() {
+                Some(output) => {
+                    output.stdout.extend_from_slice(cwd_display.as_bytes());
+                    output.stdout.push(b'\n');
+                }
+                None => println!("{cwd_display}"),
+            }
+        }
+        for cmd_str in &cmd_seq.commands {
             if options.verbose > 1 {
-                println!(
+                let cmd_display = format!(
                     "{} {}",
                     display::Color::cyan(":"),
                     display::Color::green(&cmd_str),
                 );
+                match output.as_deref_mut() {
+                    Some(output) => {
+                        output.stdout.extend_from_slice(cmd_display.as_bytes());
+                        output.stdout.push(b'\n');
+                    }
+                    None => println!("{cmd_display}"),
+                }
             }
-            let mut exec = subprocess::Exec::cmd(&shell_params.shell_command[0]).cwd(path);
+            let mut exec = subprocess::Exec::cmd(&shell_params.shell_command[0]).cwd(&cwd);
             exec = exec.args(&shell_params.shell_command[1..]);
             exec = exec.arg(cmd_str);
             if shell_params.is_shell {
@@ -478,34 +1111,274 @@ fn run_cmd_vec(
             }
             exec = exec.args(&params.arguments);
             // Update the command environment
-            for (k, v) in env {
+            for (k, v) in env.iter().chain(&cmd_seq.environment) {
                 exec = exec.env(k, v);
             }
             // When a command list is used then the return code from the final command
             // is the one that is returned when --no-errexit is in effect.
-            let status = cmd::status(exec);
+            let start = std::time::Instant::now();
+            let status = match output.as_deref_mut() {
+                Some(output) => {
+                    let (status, stdout, stderr) = cmd::status_with_output(exec);
+                    output.stdout.extend(stdout);
+                    output.stderr.extend(stderr);
+                    status
+                }
+                None => cmd::status(exec),
+            };
+            if options.debug_level(constants::DEBUG_LEVEL_TIMING) > 0 {
+                debug!(
+                    "timing: command: {:.3?}: {}: {}",
+                    start.elapsed(),
+                    cwd.display(),
+                    display_command(cmd_str)
+                );
+            }
             if status != errors::EX_OK {
                 exit_status = status;
+                failed_command = cmd_str.to_string();
                 if params.exit_on_error {
-                    return Err(status);
+                    return Err(errors::CommandError::ExitStatus {
+                        command: failed_command,
+                        status,
+                    });
                 }
             } else {
                 exit_status = errors::EX_OK;
             }
         }
         if exit_status != errors::EX_OK {
-            return Err(exit_status);
+            return Err(errors::CommandError::ExitStatus {
+                command: failed_command,
+                status: exit_status,
+            });
         }
     }
 
     Ok(())
 }
 
+/// Format a command string for display on a single line.
+fn display_command(command: &str) -> String {
+    command.trim().lines().collect::<Vec<_>>().join("; ")
+}
+
+/// Trees that do not match the "--trees" pattern.
+const SKIPPED_FILTERED: &str = "filtered";
+/// Trees that have not been grown.
+const SKIPPED_MISSING: &str = "missing";
+/// Symlink trees do not run commands.
+const SKIPPED_SYMLINK: &str = "symlink";
+/// Trees without changes when using "--changed-since".
+const SKIPPED_UNCHANGED: &str = "unchanged";
+
+/// The outcome of running a command in a tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CmdState {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl CmdState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// The result of running a command in a tree. Skipped trees are recorded without a command.
+#[derive(Clone, Debug)]
+struct CmdResult {
+    tree: String,
+    command: Option<String>,
+    state: CmdState,
+    exit_status: i32,
+    duration: std::time::Duration,
+    /// The command string that returned a non-zero exit status.
+    failed_command: Option<String>,
+    /// The reason that a tree was skipped.
+    reason: Option<&'static str>,
+}
+
+/// Per-tree, per-command results displayed by "--summary" and "--summary-json".
+#[derive(Debug, Default)]
+struct CmdSummary {
+    results: Vec<CmdResult>,
+}
+
+impl CmdSummary {
+    /// Record a tree that was skipped.
+    fn skip(&mut self, tree: &str, reason: &'static str) {
+        self.results.push(CmdResult {
+            tree: tree.to_string(),
+            command: None,
+            state: CmdState::Skipped,
+            exit_status: errors::EX_OK,
+            duration: std::time::Duration::default(),
+            failed_command: None,
+            reason: Some(reason),
+        });
+    }
+
+    /// Record the result of running a command in a tree.
+    fn record(
+        &mut self,
+        tree: &str,
+        command: &str,
+        duration: std::time::Duration,
+        result: &Result<(), errors::CommandError>,
+    ) {
+        let (state, exit_status, failed_command) = match result {
+            Ok(()) => (CmdState::Passed, errors::EX_OK, None),
+            Err(errors::CommandError::ExitStatus { command, status }) => {
+                (CmdState::Failed, *status, Some(command.to_string()))
+            }
+        };
+        self.results.push(CmdResult {
+            tree: tree.to_string(),
+            command: Some(command.to_string()),
+            state,
+            exit_status,
+            duration,
+            failed_command,
+            reason: None,
+        });
+    }
+
+    /// Return the number of results with the specified state.
+    fn count(&self, state: CmdState) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.state == state)
+            .count()
+    }
+
+    /// Display the summary table and write the JSON summary when requested.
+    fn display(&self, params: &CmdParams) -> Result<()> {
+        if params.summary {
+            self.print_table();
+        }
+        if let Some(path) = &params.summary_json {
+            let mut json = serde_json::to_string_pretty(&self.to_json())?;
+            json.push('\n');
+            if path == "-" {
+                std::io::stdout().write_all(json.as_bytes())?;
+            } else {
+                std::fs::write(path, json).map_err(|err| {
+                    errors::GardenError::IOError(format!("unable to write {path}: {err}"))
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return the summary as a JSON value.
+    fn to_json(&self) -> serde_json::Value {
+        let results: Vec<serde_json::Value> = self
+            .results
+            .iter()
+            .map(|result| {
+                serde_json::json!({
+                    "tree": result.tree,
+                    "command": result.command,
+                    "status": result.state.as_str(),
+                    "exit_status": result.exit_status,
+                    "duration": result.duration.as_secs_f64(),
+                    "failed_command": result.failed_command,
+                    "reason": result.reason,
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "passed": self.count(CmdState::Passed),
+            "failed": self.count(CmdState::Failed),
+            "skipped": self.count(CmdState::Skipped),
+            "results": results,
+        })
+    }
+
+    /// Print the results as an aligned table on stderr.
+    fn print_table(&self) {
+        let headers = ["TREE", "COMMAND", "STATUS", "DURATION", "DETAILS"];
+        let rows: Vec<[String; 5]> = self
+            .results
+            .iter()
+            .map(|result| {
+                let duration = match result.state {
+                    CmdState::Skipped => "-".to_string(),
+                    _ => format!("{:.2}s", result.duration.as_secs_f64()),
+                };
+                let details = match (&result.failed_command, result.reason) {
+                    (Some(command), _) => {
+                        format!(
+                            "exit status {}: {}",
+                            result.exit_status,
+                            display_command(command)
+                        )
+                    }
+                    (None, Some(reason)) => reason.to_string(),
+                    (None, None) => String::new(),
+                };
+                [
+                    result.tree.to_string(),
+                    result.command.as_deref().unwrap_or("-").to_string(),
+                    result.state.as_str().to_string(),
+                    duration,
+                    details,
+                ]
+            })
+            .collect();
+        let mut widths = headers.map(str::len);
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.len());
+            }
+        }
+        let [tree_width, command_width, status_width, duration_width, _] = widths;
+
+        let header = format!(
+            "{:<tree_width$}  {:<command_width$}  {:<status_width$}  {:<duration_width$}  {}",
+            headers[0], headers[1], headers[2], headers[3], headers[4]
+        );
+        eprintln!("{}", display::Color::cyan(header.trim_end()).bold());
+        for (row, result) in rows.iter().zip(&self.results) {
+            let status = format!("{:<status_width$}", row[2]);
+            let status = match result.state {
+                CmdState::Passed => display::Color::green(status),
+                CmdState::Failed => display::Color::red(status),
+                CmdState::Skipped => display::Color::yellow(status),
+            };
+            let line = format!(
+                "{}  {:<command_width$}  {}  {:<duration_width$}  {}",
+                display::Color::blue(format!("{:<tree_width$}", row[0])).bold(),
+                row[1],
+                status,
+                row[3],
+                row[4]
+            );
+            eprintln!("{}", line.trim_end());
+        }
+        eprintln!(
+            "{} passed, {} failed, {} skipped",
+            self.count(CmdState::Passed),
+            self.count(CmdState::Failed),
+            self.count(CmdState::Skipped)
+        );
+    }
+}
+
 /// Run cmd() over a Vec of tree queries
 fn cmds(app: &model::ApplicationContext, params: &CmdParams) -> Result<()> {
     let mut exit_status = errors::EX_OK;
+    let mut summary = CmdSummary::default();
     for query in &params.queries {
-        let status = cmd(app, query, params).unwrap_or(errors::EX_IOERR);
+        let status = cmd(app, query, params, &mut summary)?;
         if status != errors::EX_OK {
             exit_status = status;
             if !params.keep_going {
@@ -513,6 +1386,7 @@ fn cmds(app: &model::ApplicationContext, params: &CmdParams) -> Result<()> {
             }
         }
     }
+    summary.display(params)?;
     // Return the last non-zero exit status.
     cmd::result_from_exit_status(exit_status).map_err(|err| err.into())
 }
@@ -3,7 +3,7 @@ use std::io::Write;
 use anyhow::Result;
 use clap::{value_parser, Arg, Command, CommandFactory, Parser};
 
-use crate::{cli, model};
+use crate::{cli, cmds, model};
 
 /// Generate shell completions
 #[derive(Parser, Clone, Debug)]
@@ -26,40 +26,45 @@ pub fn main(options: &cli::MainOptions, completion_options: &CompletionOptions)
         let app_context = model::ApplicationContext::from_options(options)?;
         let config = app_context.get_root_config();
         for name in config.commands.keys() {
-            cmd = cmd.subcommand(
-                Command::new(name)
-                    .about(format!("Custom {name} command"))
-                    .arg(
-                        Arg::new("keep_going")
-                            .help("Continue to the next tree when errors occur")
-                            .short('k')
-                            .long("keep-going"),
-                    )
-                    .arg(
-                        Arg::new("no-errexit")
-                            .help("Do not pass -e to the shell")
-                            .short('n')
-                            .long("no-errexit"),
-                    )
-                    .arg(
-                        Arg::new("no-wordsplit")
-                            .help("Do not pass -o shwordsplit to zsh")
-                            .short('z')
-                            .long("no-wordsplit"),
-                    )
-                    .arg(
-                        Arg::new("queries")
-                            // NOTE: value_terminator may not be needed in future versions of clap_complete.
-                            // https://github.com/clap-rs/clap/pull/4612
-                            .value_terminator("--")
-                            .help("Tree queries to find trees where commands will be run"),
-                    )
-                    .arg(
-                        Arg::new("arguments")
-                            .help("Arguments to forward to custom commands")
-                            .last(true),
-                    ),
-            );
+            let mut command = Command::new(name)
+                .about(format!("Custom {name} command"))
+                .arg(
+                    Arg::new("keep_going")
+                        .help("Continue to the next tree when errors occur")
+                        .short('k')
+                        .long("keep-going"),
+                )
+                .arg(
+                    Arg::new("no-errexit")
+                        .help("Do not pass -e to the shell")
+                        .short('n')
+                        .long("no-errexit"),
+                )
+                .arg(
+                    Arg::new("no-wordsplit")
+                        .help("Do not pass -o shwordsplit to zsh")
+                        .short('z')
+                        .long("no-wordsplit"),
+                )
+                .arg(
+                    Arg::new("queries")
+                        // NOTE: value_terminator may not be needed in future versions of clap_complete.
+                        // https://github.com/clap-rs/clap/pull/4612
+                        .value_terminator("--")
+                        .help("Tree queries to find trees where commands will be run"),
+                )
+                .arg(
+                    Arg::new("arguments")
+                        .help("Arguments to forward to custom commands")
+                        .last(true),
+                );
+            // Include the description, options and arguments declared by the command.
+            if let Some(spec) = config.command_specs.get(name) {
+                if cmds::cmd::command_spec_problems(name, spec).is_empty() {
+                    command = cmds::cmd::add_command_params(command, spec);
+                }
+            }
+            cmd = cmd.subcommand(command);
         }
     }
 
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{cmds, errors, eval, model};
+
+/// Print the environment for a tree
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct EnvOptions {
+    /// Output format [sh, bash, zsh, fish, dotenv, json]
+    #[arg(
+        long,
+        short,
+        default_value_t = model::EnvFormat::Sh,
+        value_parser = model::EnvFormat::parse_from_str,
+    )]
+    format: model::EnvFormat,
+    /// Query for trees to build an environment
+    #[arg(default_value = ".")]
+    query: String,
+    /// Tree within the query whose environment is printed
+    tree: Option<String>,
+}
+
+/// Main entry point for the "garden env" command
+pub fn main(app_context: &model::ApplicationContext, options: &EnvOptions) -> Result<()> {
+    let config = app_context.get_root_config();
+    let context =
+        cmds::shell::find_context(app_context, config, &options.query, options.tree.as_ref())?;
+    let env = environment(app_context, config, &context)?;
+    print!("{}", format_environment(&env, &options.format));
+
+    Ok(())
+}
+
+/// Evaluate the environment for a tree context. Variables that are updated
+/// several times are reported once, in the order they were first defined,
+/// using their final value.
+pub(crate) fn environment(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    context: &model::TreeContext,
+) -> Result<Vec<(String, String)>, errors::GardenError> {
+    let mut result: indexmap::IndexMap<String, String> = indexmap::IndexMap::new();
+    for (name, value) in eval::environment(app_context, config, context)? {
+        result.insert(name, value);
+    }
+
+    Ok(result.into_iter().collect())
+}
+
+/// Format environment variables using the syntax for the specified format.
+pub(crate) fn format_environment(env: &[(String, String)], format: &model::EnvFormat) -> String {
+    if *format == model::EnvFormat::Json {
+        let map: serde_json::Map<String, serde_json::Value> = env
+            .iter()
+            .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+            .collect();
+        let json = serde_json::Value::Object(map);
+        return format!(
+            "{}\n",
+            serde_json::to_string_pretty(&json).unwrap_or_default()
+        );
+    }
+    let mut output = String::new();
+    for (name, value) in env {
+        let line = match format {
+            model::EnvFormat::Sh | model::EnvFormat::Bash | model::EnvFormat::Zsh => {
+                format!("export {name}={}", sh_quote(value))
+            }
+            model::EnvFormat::Fish => format!("set -gx {name} {}", fish_quote(value)),
+            model::EnvFormat::Dotenv => format!("{name}={}", dotenv_quote(value)),
+            model::EnvFormat::Json => continue,
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Quote a value using POSIX shell single-quotes.
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quote a value using fish single-quotes.
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Quote a value using ".env" double-quotes.
+fn dotenv_quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '\\' | '"' | '$' | '`' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str(r"\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
@@ -22,7 +22,7 @@ pub fn main(app_context: &model::ApplicationContext, eval: &EvalOptions) -> Resu
             // Evaluate and print the expression in global scope. No trees or gardens
             // were provided so only the top-level variables are included.
             let config = app_context.get_root_config();
-            let value = eval::value(app_context, config, &eval.expr);
+            let value = eval::value(app_context, config, &eval.expr)?;
             println!("{value}");
         }
         Some(tree) => {
@@ -37,7 +37,7 @@ pub fn main(app_context: &model::ApplicationContext, eval: &EvalOptions) -> Resu
                 &eval.expr,
                 &ctx.tree,
                 ctx.garden.as_ref(),
-            );
+            )?;
             println!("{value}");
         }
     }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::{Parser, ValueHint};
 
-use crate::{cmd, constants, errors, model, query};
+use crate::{cmd, constants, errors, git, model, query};
 
 /// Evaluate garden expressions
 #[derive(Parser, Clone, Debug)]
@@ -10,6 +10,9 @@ pub struct ExecOptions {
     /// Filter trees by name post-query using a glob pattern
     #[arg(long, short, default_value = "*")]
     trees: String,
+    /// Only include trees with uncommitted changes or with commits that differ from REF
+    #[arg(long, value_name = "REF")]
+    changed_since: Option<String>,
     /// Perform a trial run without executing any commands
     #[arg(long, short = 'n')]
     dry_run: bool,
@@ -26,6 +29,7 @@ pub fn main(app_context: &model::ApplicationContext, exec_options: &ExecOptions)
     if app_context.options.debug_level(constants::DEBUG_LEVEL_EXEC) > 0 {
         debug!("query: {}", exec_options.query);
         debug!("command: {:?}", exec_options.command);
+        debug!("changed since: {:?}", exec_options.changed_since);
     }
 
     let config = app_context.get_root_config_mut();
@@ -55,7 +59,7 @@ fn exec(
     // with no garden context.
 
     // Resolve the tree query into a vector of tree contexts.
-    let contexts = query::resolve_trees(app_context, config, None, query);
+    let contexts = query::resolve_trees(app_context, config, None, query)?;
     let pattern = glob::Pattern::new(tree_pattern).unwrap_or_default();
     let mut exit_status: i32 = 0;
 
@@ -77,6 +81,11 @@ fn exec(
         if tree.is_symlink {
             continue;
         }
+        if let Some(reference) = &exec_options.changed_since {
+            if !git::tree_has_changes_since(tree, reference) {
+                continue;
+            }
+        }
         // Run the command in the current context.
         if let Err(errors::GardenError::ExitStatus(status)) = cmd::exec_in_context(
             app_context,
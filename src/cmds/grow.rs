@@ -1,9 +1,12 @@
 /// Grow garden worktrees
 use std::collections::{HashMap, HashSet};
+use std::io::prelude::*;
 
 use anyhow::Result;
 use clap::Parser;
+use rayon::prelude::*;
 
+use crate::cmds::lock;
 use crate::{cmd, constants, display, errors, eval, git, model, query};
 
 type GitConfigMap = HashMap<String, HashSet<String>>;
@@ -12,6 +15,16 @@ type GitConfigMap = HashMap<String, HashSet<String>>;
 #[derive(Parser, Clone, Debug)]
 #[command(author, about, long_about)]
 pub struct GrowOptions {
+    /// Print the commands that would be run without running them
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+    /// Clone repositories concurrently using the specified number of jobs.
+    /// Output is buffered per tree and displayed in tree order.
+    #[arg(long = "jobs", short = 'j', default_value_t = 1)]
+    num_jobs: usize,
+    /// Check out the commits recorded in "garden.lock"
+    #[arg(long)]
+    locked: bool,
     /// Filter trees by name post-query using a glob pattern
     #[arg(long, short, default_value = "*")]
     trees: String,
@@ -20,19 +33,54 @@ pub struct GrowOptions {
     queries: Vec<String>,
 }
 
+/// State that is shared across the trees being grown.
+#[derive(Default)]
+struct GrowState {
+    /// Repositories that have already been configured, keyed by their shared worktree path.
+    configured_worktrees: HashSet<String>,
+    /// Repositories that were cloned concurrently, keyed by tree path.
+    clones: HashMap<String, CloneResult>,
+    /// Commits read from "garden.lock" when "--locked" is used.
+    locked_commits: Option<lock::LockedCommits>,
+}
+
+/// A repository that will be cloned concurrently.
+struct CloneJob {
+    path: String,
+    command: Vec<String>,
+}
+
+/// The captured result from cloning a repository.
+struct CloneResult {
+    exit_status: i32,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
 /// Main entry point for the "garden grow" command
 pub fn main(app: &model::ApplicationContext, options: &GrowOptions) -> Result<()> {
     let quiet = app.options.quiet;
     let verbose = app.options.verbose;
+    if app.options.debug_level(constants::DEBUG_LEVEL_CMD) > 0 {
+        debug!("jobs: {}", options.num_jobs);
+    }
 
     let mut exit_status = errors::EX_OK;
-    let mut configured_worktrees: HashSet<String> = HashSet::new();
+    let mut state = GrowState::default();
+    if options.locked {
+        let path = lock::lockfile_path(app.get_root_config())?;
+        state.locked_commits = Some(lock::read_lockfile(&path)?);
+    }
+    if options.num_jobs > 1 && !options.dry_run {
+        state.clones = clone_trees(app, options)?;
+    }
     for query in &options.queries {
         let status = grow(
             app,
-            &mut configured_worktrees,
+            &mut state,
             quiet,
             verbose,
+            options.dry_run,
             query,
             &options.trees,
         )?;
@@ -45,22 +93,121 @@ pub fn main(app: &model::ApplicationContext, options: &GrowOptions) -> Result<()
     cmd::result_from_exit_status(exit_status).map_err(|err| err.into())
 }
 
+/// Clone missing repositories concurrently.
+/// Worktrees are not cloned. Their parent repositories are cloned instead so that
+/// "git worktree add" can be run once the parent repository is available.
+fn clone_trees(
+    app_context: &model::ApplicationContext,
+    options: &GrowOptions,
+) -> Result<HashMap<String, CloneResult>> {
+    let config = app_context.get_root_config();
+    let mut jobs: Vec<CloneJob> = Vec::new();
+    for query in &options.queries {
+        let contexts = query::resolve_and_filter_trees(app_context, config, query, &options.trees)?;
+        for context in &contexts {
+            let context = match worktree_parent_context(app_context, context)? {
+                Some(parent_context) => parent_context,
+                None => context.clone(),
+            };
+            let tree = match tree_from_context(app_context, &context) {
+                Some(tree) => tree,
+                None => continue,
+            };
+            if tree.is_symlink || tree.is_worktree {
+                continue;
+            }
+            let path = tree.path_as_ref()?.clone();
+            if std::path::Path::new(&path).exists() || jobs.iter().any(|job| job.path == path) {
+                continue;
+            }
+            if let Some((command, _)) = clone_command(app_context, &context, &path)? {
+                jobs.push(CloneJob { path, command });
+            }
+        }
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.num_jobs)
+        .build()?;
+    let clones = pool.install(|| {
+        jobs.par_iter()
+            .map(|job| {
+                let (exit_status, stdout, stderr) =
+                    cmd::status_with_output(cmd::exec_cmd(&job.command));
+                let result = CloneResult {
+                    exit_status,
+                    stdout,
+                    stderr,
+                };
+                (job.path.clone(), result)
+            })
+            .collect()
+    });
+
+    Ok(clones)
+}
+
+/// Return the tree configuration for a tree context.
+fn tree_from_context<'a>(
+    app_context: &'a model::ApplicationContext,
+    context: &model::TreeContext,
+) -> Option<&'a model::Tree> {
+    let config = match context.config {
+        Some(config_id) => app_context.get_config(config_id),
+        None => app_context.get_root_config(),
+    };
+    config.trees.get(&context.tree)
+}
+
+/// Return the context for the parent repository of a tree that is grown using "git worktree".
+fn worktree_parent_context(
+    app_context: &model::ApplicationContext,
+    context: &model::TreeContext,
+) -> Result<Option<model::TreeContext>, errors::GardenError> {
+    let tree = match tree_from_context(app_context, context) {
+        Some(tree) if tree.is_worktree => tree,
+        _ => return Ok(None),
+    };
+    let config = app_context.get_root_config();
+    let graft_config = context
+        .config
+        .map(|config_id| app_context.get_config(config_id));
+    let worktree = eval::tree_variable(
+        app_context,
+        config,
+        graft_config,
+        &context.tree,
+        context.garden.as_ref(),
+        &tree.worktree,
+    )?;
+
+    Ok(query::tree_from_name(
+        config,
+        &worktree,
+        context.garden.as_ref(),
+        context.group.as_ref(),
+    ))
+}
+
 /// Create/update trees in the evaluated tree query.
 fn grow(
     app_context: &model::ApplicationContext,
-    configured_worktrees: &mut HashSet<String>,
+    state: &mut GrowState,
     quiet: bool,
     verbose: u8,
+    dry_run: bool,
     query: &str,
     tree_pattern: &str,
 ) -> Result<i32> {
     let config = app_context.get_root_config();
-    let contexts = query::resolve_and_filter_trees(app_context, config, query, tree_pattern);
+    let contexts = query::resolve_and_filter_trees(app_context, config, query, tree_pattern)?;
     let mut exit_status = errors::EX_OK;
 
     for ctx in &contexts {
-        let status =
-            grow_tree_from_context(app_context, configured_worktrees, ctx, quiet, verbose)?;
+        let mut status = grow_tree_from_context(app_context, state, ctx, quiet, verbose, dry_run)?;
+        if status == errors::EX_OK {
+            status = checkout_locked_commit(app_context, state, ctx, verbose, dry_run)?;
+        }
         if status != errors::EX_OK {
             // Return the last non-zero exit status.
             exit_status = status;
@@ -72,12 +219,14 @@ fn grow(
 
 /// Grow the tree specified by the context into existence.
 /// Trees without remotes are silently ignored.
+/// Commands are printed instead of being run when dry_run is true.
 fn grow_tree_from_context(
     app_context: &model::ApplicationContext,
-    configured_worktrees: &mut HashSet<String>,
+    state: &mut GrowState,
     context: &model::TreeContext,
     quiet: bool,
     verbose: u8,
+    dry_run: bool,
 ) -> Result<i32> {
     let config = app_context.get_root_config();
     let graft_config = context
@@ -104,23 +253,40 @@ fn grow_tree_from_context(
     let parent = pathbuf.parent().ok_or_else(|| {
         errors::GardenError::AssertionError(format!("unable to get parent directory for {path}"))
     })?;
-    std::fs::create_dir_all(parent)
-        .map_err(|err| errors::GardenError::OSError(format!("unable to create {path}: {err}")))?;
-
-    if pathbuf.exists() {
+    if dry_run {
+        if !parent.exists() {
+            print_quoted_command(&["mkdir", "-p", &parent.to_string_lossy()]);
+        }
+    } else {
+        std::fs::create_dir_all(parent).map_err(|err| {
+            errors::GardenError::OSError(format!("unable to create {path}: {err}"))
+        })?;
+    }
+
+    // Trial runs do not create anything so we must remember which repositories
+    // would have been cloned by an earlier step.
+    let is_grown = dry_run
+        && !tree.is_worktree
+        && state
+            .configured_worktrees
+            .contains(&query::shared_worktree_path(app_context, config, context)?);
+    // Repositories that were cloned concurrently are configured as if they were cloned here.
+    let clone_result = state.clones.remove(&path);
+    if (pathbuf.exists() && clone_result.is_none()) || is_grown {
         return update_tree_from_context(
             app_context,
-            configured_worktrees,
+            &mut state.configured_worktrees,
             context,
             &pathbuf,
             None,
             quiet,
             verbose,
+            dry_run,
         );
     }
 
     if tree.is_symlink {
-        let status = grow_symlink(app_context, context).unwrap_or(errors::EX_IOERR);
+        let status = grow_symlink(app_context, context, dry_run).unwrap_or(errors::EX_IOERR);
         if status != errors::EX_OK {
             exit_status = status;
         }
@@ -130,40 +296,147 @@ fn grow_tree_from_context(
     if tree.is_worktree {
         return grow_tree_from_context_as_worktree(
             app_context,
-            configured_worktrees,
+            state,
             context,
             quiet,
             verbose,
+            dry_run,
         );
     }
 
-    // The "url" field maps to the default remote.
-    let url = match tree.remotes.get(&tree.default_remote) {
-        Some(remote) => eval::tree_variable(
-            app_context,
-            config,
-            graft_config,
-            &context.tree,
-            context.garden.as_ref(),
-            remote,
-        ),
+    let (command, branch) = match clone_command(app_context, context, &path)? {
+        Some(command_and_branch) => command_and_branch,
         None => return Ok(exit_status),
     };
+    let cmd: Vec<&str> = command.iter().map(String::as_str).collect();
+    if verbose > 1 || dry_run {
+        print_quoted_command(&cmd);
+    }
+    if let Some(result) = clone_result {
+        std::io::stdout().write_all(&result.stdout).unwrap_or(());
+        std::io::stdout().flush().unwrap_or(());
+        std::io::stderr().write_all(&result.stderr).unwrap_or(());
+        if result.exit_status != 0 {
+            exit_status = result.exit_status;
+        }
+    } else if !dry_run {
+        let exec = cmd::exec_cmd(&cmd);
+        let status = cmd::status(exec);
+        if status != 0 {
+            exit_status = status;
+        }
+    }
+
+    let status = update_tree_from_context(
+        app_context,
+        &mut state.configured_worktrees,
+        context,
+        &pathbuf,
+        Some(&branch),
+        quiet,
+        verbose,
+        dry_run,
+    )?;
+    if status != errors::EX_OK {
+        exit_status = status;
+    }
+    Ok(exit_status)
+}
+
+/// Check out the commit recorded for a tree in "garden.lock".
+/// Commits that are not present in existing trees are fetched from the default remote.
+fn checkout_locked_commit(
+    app_context: &model::ApplicationContext,
+    state: &GrowState,
+    context: &model::TreeContext,
+    verbose: u8,
+    dry_run: bool,
+) -> Result<i32> {
+    let commits = match &state.locked_commits {
+        Some(commits) => commits,
+        None => return Ok(errors::EX_OK),
+    };
+    let tree = match tree_from_context(app_context, context) {
+        Some(tree) => tree,
+        None => return Ok(errors::EX_OK),
+    };
+    if tree.is_symlink || tree.is_bare_repository {
+        return Ok(errors::EX_OK);
+    }
+    let name = lock::lock_name(app_context, context);
+    let commit = match commits.get(&name) {
+        Some(commit) => commit,
+        None => {
+            eprintln!("error: {name}: not found in {}", constants::GARDEN_LOCK);
+            return Ok(errors::EX_DATAERR);
+        }
+    };
+    let path = std::path::Path::new(tree.path_as_ref()?);
+    if !dry_run && !path.exists() {
+        return Ok(errors::EX_OK);
+    }
+
+    if !dry_run {
+        let object = format!("{commit}^{{commit}}");
+        let command = ["git", "cat-file", "-e", object.as_str()];
+        let (status, _, _) = cmd::status_with_output(cmd::exec_in_dir(&command, path));
+        if status != errors::EX_OK {
+            let command = ["git", "fetch", "--quiet", tree.default_remote.as_str()];
+            let status = run_verbose_command(&command, path, verbose, dry_run);
+            if status != errors::EX_OK {
+                return Ok(status);
+            }
+        }
+    }
+    let command = ["git", "checkout", "--quiet", "--detach", commit.as_str()];
+
+    Ok(run_verbose_command(&command, path, verbose, dry_run))
+}
+
+/// Return the "git clone" command for a tree and the branch that will be checked out.
+/// Returns None when the tree does not have a default remote.
+fn clone_command(
+    app_context: &model::ApplicationContext,
+    context: &model::TreeContext,
+    path: &str,
+) -> Result<Option<(Vec<String>, String)>, errors::GardenError> {
+    let config = app_context.get_root_config();
+    let graft_config = context
+        .config
+        .map(|config_id| app_context.get_config(config_id));
+    let tree = match tree_from_context(app_context, context) {
+        Some(tree) => tree,
+        None => return Ok(None),
+    };
+    let remote = match tree.remotes.get(&tree.default_remote) {
+        Some(remote) => remote,
+        None => return Ok(None),
+    };
+
+    // The "url" field maps to the default remote.
+    let url = eval::tree_variable(
+        app_context,
+        config,
+        graft_config,
+        &context.tree,
+        context.garden.as_ref(),
+        remote,
+    )?;
 
     // git clone [options] <url> <path>
-    let mut cmd: Vec<&str> = ["git", "clone"].to_vec();
+    let mut cmd: Vec<String> = vec!["git".into(), "clone".into()];
 
     // [options]
     //
     // "git clone --bare" clones bare repositories.
     if tree.is_bare_repository {
-        cmd.push("--bare");
+        cmd.push("--bare".into());
     }
 
     // "git clone --remote <name>" uses an alternatively-named remote instead of "origin".
     if tree.default_remote != constants::ORIGIN {
-        cmd.push("--origin");
-        cmd.push(&tree.default_remote);
+        cmd.push("--origin".into());
+        cmd.push(tree.default_remote.to_string());
     }
 
     // "git clone --branch=name" clones the named branch.
@@ -174,18 +447,14 @@ fn grow_tree_from_context(
         &context.tree,
         context.garden.as_ref(),
         &tree.branch,
-    );
-    let branch_opt;
+    )?;
     if !branch.is_empty() && !tree.branches.contains_key(&branch) {
-        branch_opt = format!("--branch={branch}");
-        cmd.push(&branch_opt);
+        cmd.push(format!("--branch={branch}"));
     }
     // "git clone --depth=N" creates shallow clones with truncated history.
     let clone_depth = tree.clone_depth;
-    let clone_depth_opt;
     if clone_depth > 0 {
-        clone_depth_opt = format!("--depth={clone_depth}");
-        cmd.push(&clone_depth_opt);
+        cmd.push(format!("--depth={clone_depth}"));
     }
     // "git clone --depth=N" clones a single branch by default.
     // We generally want all branches available in our clones so we default to
@@ -193,37 +462,16 @@ fn grow_tree_from_context(
     // all branches available by default.
     let is_single_branch = tree.is_single_branch;
     if is_single_branch {
-        cmd.push("--single-branch");
+        cmd.push("--single-branch".into());
     } else {
-        cmd.push("--no-single-branch");
+        cmd.push("--no-single-branch".into());
     }
 
     // <url> <path>
-    cmd.push(&url);
-    cmd.push(&path);
-    if verbose > 1 {
-        print_quoted_command(&cmd);
-    }
-
-    let exec = cmd::exec_cmd(&cmd);
-    let status = cmd::status(exec);
-    if status != 0 {
-        exit_status = status;
-    }
+    cmd.push(url);
+    cmd.push(path.to_string());
 
-    let status = update_tree_from_context(
-        app_context,
-        configured_worktrees,
-        context,
-        &pathbuf,
-        Some(&branch),
-        quiet,
-        verbose,
-    )?;
-    if status != errors::EX_OK {
-        exit_status = status;
-    }
-    Ok(exit_status)
+    Ok(Some((cmd, branch)))
 }
 
 /// Print a command from a list of arguments.
@@ -244,7 +492,34 @@ fn print_command_str(cmd: &str) {
     )
 }
 
+/// Run a command in the specified directory and return its exit status.
+/// The command is printed instead of being run when dry_run is true.
+fn run_command(command: &[&str], path: &std::path::Path, dry_run: bool) -> i32 {
+    if dry_run {
+        print_quoted_command(command);
+        return errors::EX_OK;
+    }
+    let exec = cmd::exec_in_dir(command, path);
+
+    cmd::status(exec)
+}
+
+/// Run a command using run_command() and print it beforehand when verbose > 1.
+fn run_verbose_command(
+    command: &[&str],
+    path: &std::path::Path,
+    verbose: u8,
+    dry_run: bool,
+) -> i32 {
+    if verbose > 1 && !dry_run {
+        print_quoted_command(command);
+    }
+
+    run_command(command, path, dry_run)
+}
+
 /// Add remotes that do not already exist and synchronize .git/config values.
+#[allow(clippy::too_many_arguments)]
 fn update_tree_from_context(
     app_context: &model::ApplicationContext,
     configured_worktrees: &mut HashSet<String>,
@@ -253,6 +528,7 @@ fn update_tree_from_context(
     branch: Option<&str>,
     _quiet: bool,
     verbose: u8,
+    dry_run: bool,
 ) -> Result<i32> {
     let config = app_context.get_root_config();
     let graft_config = ctx
@@ -280,14 +556,17 @@ fn update_tree_from_context(
     // Repositories created using "git worktree" share a common Git configuration
     // and only need to be configured once. Skip configuring the repository
     // if we've already processed it.
-    let shared_worktree_path = query::shared_worktree_path(app_context, config, ctx);
+    let shared_worktree_path = query::shared_worktree_path(app_context, config, ctx)?;
     if !configured_worktrees.insert(shared_worktree_path) {
         return Ok(exit_status);
     }
 
     // Gather existing remotes
     let mut existing_remotes = HashSet::new();
-    {
+    if dry_run && !path.exists() {
+        // "git clone" creates the default remote.
+        existing_remotes.insert(tree.default_remote.to_string());
+    } else {
         let command = ["git", "remote"];
         let exec = cmd::exec_in_dir(&command, path);
         if let Ok(output) = cmd::stdout_to_string(exec) {
@@ -299,7 +578,12 @@ fn update_tree_from_context(
 
     // The "default-remote" field is used to change the name of the default "origin" remote.
     if tree.default_remote != constants::ORIGIN {
-        set_gitconfig_value("checkout.defaultRemoteName", &tree.default_remote, path);
+        set_gitconfig_value(
+            "checkout.defaultRemoteName",
+            &tree.default_remote,
+            path,
+            dry_run,
+        );
     }
 
     // Loop over remotes and add/update the git remote configuration.
@@ -311,26 +595,18 @@ fn update_tree_from_context(
             &ctx.tree,
             ctx.garden.as_ref(),
             var,
-        );
+        )?;
 
         if existing_remotes.contains(remote) {
             let remote_key = format!("remote.{remote}.url");
             let command = ["git", "config", remote_key.as_ref(), url.as_ref()];
-            if verbose > 1 {
-                print_command_str(&command.join(" "));
-            }
-            let exec = cmd::exec_in_dir(&command, path);
-            let status = cmd::status(exec);
+            let status = run_verbose_command(&command, path, verbose, dry_run);
             if status != errors::EX_OK {
                 exit_status = status;
             }
         } else {
             let command = ["git", "remote", "add", remote.as_ref(), url.as_ref()];
-            if verbose > 1 {
-                print_command_str(&command.join(" "));
-            }
-            let exec = cmd::exec_in_dir(&command, path);
-            let status = cmd::status(exec);
+            let status = run_verbose_command(&command, path, verbose, dry_run);
             if status != errors::EX_OK {
                 exit_status = status;
             }
@@ -338,11 +614,7 @@ fn update_tree_from_context(
             // git config remote.<name>.tagopt --no-tags
             let key = format!("remote.{}.tagopt", remote);
             let command = ["git", "config", key.as_ref(), "--no-tags"];
-            if verbose > 1 {
-                print_command_str(&command.join(" "));
-            }
-            let exec = cmd::exec_in_dir(&command, path);
-            let status = cmd::status(exec);
+            let status = run_verbose_command(&command, path, verbose, dry_run);
             if status != errors::EX_OK {
                 exit_status = status;
             }
@@ -359,7 +631,7 @@ fn update_tree_from_context(
             var_name,
             &ctx.tree,
             ctx.garden.as_ref(),
-        );
+        )?;
         for var in variables {
             let value = match var.get_value() {
                 Some(precomputed_value) => precomputed_value.to_string(),
@@ -370,14 +642,14 @@ fn update_tree_from_context(
                     &ctx.tree,
                     ctx.garden.as_ref(),
                     var,
-                ),
+                )?,
             };
             let status = if variables.len() > 1 {
                 // Multiple values are set using "git config --add <name> <value>"
-                append_gitconfig_value(&name, &value, path, &mut gitconfig_cache)
+                append_gitconfig_value(&name, &value, path, &mut gitconfig_cache, dry_run)
             } else {
                 // Single values are set directly using "git config <name> <value>".
-                set_gitconfig_value(&name, &value, path)
+                set_gitconfig_value(&name, &value, path, dry_run)
             };
             if status != errors::EX_OK {
                 exit_status = status;
@@ -392,11 +664,10 @@ fn update_tree_from_context(
         // Create all configured tracking branches.
         for (branch, expr) in &tree.branches {
             if !branches.contains(branch) {
-                let remote_branch = eval::variable(app_context, config, expr);
+                let remote_branch = eval::variable(app_context, config, expr)?;
                 if !remote_branch.is_empty() {
                     let command = ["git", "branch", "--track", branch, remote_branch.as_str()];
-                    let exec = cmd::exec_in_dir(&command, path);
-                    let status = cmd::status(exec);
+                    let status = run_command(&command, path, dry_run);
                     if status != errors::EX_OK {
                         exit_status = status;
                     }
@@ -409,8 +680,7 @@ fn update_tree_from_context(
     if let Some(branch) = branch {
         if tree.branches.contains_key(branch) {
             let command = ["git", "checkout", branch];
-            let exec = cmd::exec_in_dir(&command, path);
-            let status = cmd::status(exec);
+            let status = run_command(&command, path, dry_run);
             if status != errors::EX_OK {
                 exit_status = status;
             }
@@ -426,6 +696,7 @@ fn append_gitconfig_value(
     value: &str,
     path: &std::path::Path,
     config_map: &mut GitConfigMap,
+    dry_run: bool,
 ) -> i32 {
     // If the config_map doesn't contain this variable then we need
     // to query git for the current values to avoid appending values
@@ -452,8 +723,7 @@ fn append_gitconfig_value(
         if !values.contains(value) {
             values.insert(value.to_string());
             let command = ["git", "config", "--add", name, value];
-            let exec = cmd::exec_in_dir(&command, path);
-            status = cmd::status(exec)
+            status = run_command(&command, path, dry_run);
         }
     }
 
@@ -461,21 +731,21 @@ fn append_gitconfig_value(
 }
 
 /// Set a simple gitconfig value.
-fn set_gitconfig_value(name: &str, value: &str, path: &std::path::Path) -> i32 {
+fn set_gitconfig_value(name: &str, value: &str, path: &std::path::Path, dry_run: bool) -> i32 {
     let command = ["git", "config", name, value];
-    let exec = cmd::exec_in_dir(&command, path);
 
-    cmd::status(exec)
+    run_command(&command, path, dry_run)
 }
 
 /// Use "git worktree" to create a worktree.
 /// Grow the parent worktree first and then create our worktree.
 fn grow_tree_from_context_as_worktree(
     app_context: &model::ApplicationContext,
-    configured_worktrees: &mut HashSet<String>,
+    state: &mut GrowState,
     ctx: &model::TreeContext,
     quiet: bool,
     verbose: u8,
+    dry_run: bool,
 ) -> Result<i32> {
     let config = app_context.get_root_config();
     let graft_config = ctx
@@ -502,7 +772,7 @@ fn grow_tree_from_context_as_worktree(
         &ctx.tree,
         ctx.garden.as_ref(),
         &tree.worktree,
-    );
+    )?;
     let branch = eval::tree_variable(
         app_context,
         config,
@@ -510,7 +780,7 @@ fn grow_tree_from_context_as_worktree(
         &ctx.tree,
         ctx.garden.as_ref(),
         &tree.branch,
-    );
+    )?;
 
     let parent_ctx =
         query::tree_from_name(config, &worktree, ctx.garden.as_ref(), ctx.group.as_ref())
@@ -519,13 +789,7 @@ fn grow_tree_from_context_as_worktree(
                 worktree: worktree.clone(),
             })?;
 
-    exit_status = grow_tree_from_context(
-        app_context,
-        configured_worktrees,
-        &parent_ctx,
-        quiet,
-        verbose,
-    )?;
+    exit_status = grow_tree_from_context(app_context, state, &parent_ctx, quiet, verbose, dry_run)?;
     if exit_status != 0 {
         return Err(errors::GardenError::WorktreeParentCreationError {
             tree: tree.get_name().into(),
@@ -569,7 +833,7 @@ fn grow_tree_from_context_as_worktree(
         // Read the upstream branch from tree.<tree>.branches.<branch> when configured.
         // Defaults to "<remote>/<branch>" when not configured.
         if let Some(expr) = tree.branches.get(&branch) {
-            remote_branch = eval::variable(app_context, config, expr);
+            remote_branch = eval::variable(app_context, config, expr)?;
         } else {
             // The "default-remote" field is used to change the name of the default "origin" remote.
             let default_remote = tree.default_remote.to_string();
@@ -580,11 +844,7 @@ fn grow_tree_from_context_as_worktree(
         }
     }
 
-    if verbose > 1 {
-        print_quoted_command(&cmd);
-    }
-    let exec = cmd::exec_in_dir(&cmd, parent_path);
-    exit_status = cmd::status(exec);
+    exit_status = run_verbose_command(&cmd, std::path::Path::new(parent_path), verbose, dry_run);
     if exit_status != 0 {
         return Err(errors::GardenError::WorktreeGitCheckoutError {
             tree: tree.get_name().clone(),
@@ -597,7 +857,11 @@ fn grow_tree_from_context_as_worktree(
 }
 
 /// Initialize a tree symlink entry.
-fn grow_symlink(app_context: &model::ApplicationContext, ctx: &model::TreeContext) -> Result<i32> {
+fn grow_symlink(
+    app_context: &model::ApplicationContext,
+    ctx: &model::TreeContext,
+    dry_run: bool,
+) -> Result<i32> {
     let config = match ctx.config {
         Some(config_id) => app_context.get_config(config_id),
         None => app_context.get_root_config(),
@@ -608,8 +872,13 @@ fn grow_symlink(app_context: &model::ApplicationContext, ctx: &model::TreeContex
     };
     // Invalid usage: non-symlink.
     if !tree.is_symlink || tree.path_as_ref()?.is_empty() || tree.symlink_as_ref()?.is_empty() {
+        let key = format!("{}.{}", constants::TREES, tree.get_name());
+        let source = match config.describe_source(&key) {
+            Some(source) => format!(" ({source})"),
+            None => String::new(),
+        };
         return Err(errors::GardenError::ConfigurationError(format!(
-            "invalid symlink: {}",
+            "invalid symlink: {}{source}",
             tree.get_name()
         ))
         .into());
@@ -638,6 +907,10 @@ fn grow_symlink(app_context: &model::ApplicationContext, ctx: &model::TreeContex
     }
     .to_string();
 
+    if dry_run {
+        print_quoted_command(&["ln", "-s", &target, path_str]);
+        return Ok(errors::EX_OK);
+    }
     let target_path = std::path::PathBuf::from(&target);
     std::os::unix::fs::symlink(target_path, &path)?;
 
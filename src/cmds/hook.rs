@@ -0,0 +1,265 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use clap::Parser;
+use strum::VariantNames;
+
+use crate::{cli, cmd, cmds, config, constants, model, path, query};
+
+/// Print a shell hook that loads tree environments when changing directories
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct HookOptions {
+    /// Print the environment changes for the current directory instead of the hook.
+    /// This is used by the hook and is not intended to be run directly.
+    #[arg(long, hide = true)]
+    export: bool,
+    /// Shell syntax to emit [bash, zsh, fish]
+    #[arg(value_parser = HookShell::parse_from_str)]
+    shell: HookShell,
+}
+
+/// Shells supported by "garden hook".
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    strum_macros::EnumString,
+    strum_macros::Display,
+    strum_macros::EnumVariantNames,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "kebab-case")]
+enum HookShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl HookShell {
+    /// Parse a shell name from a string using strum's from_str().
+    fn parse_from_str(string: &str) -> Result<HookShell, String> {
+        HookShell::from_str(string).map_err(|_| format!("choices are {:?}", Self::VARIANTS))
+    }
+
+    /// Return the "garden env" format used to export variables.
+    fn env_format(&self) -> model::EnvFormat {
+        match self {
+            HookShell::Bash => model::EnvFormat::Bash,
+            HookShell::Zsh => model::EnvFormat::Zsh,
+            HookShell::Fish => model::EnvFormat::Fish,
+        }
+    }
+
+    /// Return a statement that unsets a variable.
+    fn unset(&self, name: &str) -> String {
+        match self {
+            HookShell::Bash | HookShell::Zsh => format!("unset {name}\n"),
+            HookShell::Fish => format!("set -e {name}\n"),
+        }
+    }
+}
+
+/// Main entry point for the "garden hook" command
+pub fn main(options: &cli::MainOptions, hook_options: &HookOptions) -> Result<()> {
+    if hook_options.export {
+        print!("{}", export(options, &hook_options.shell));
+    } else {
+        print!("{}", hook_script(&hook_options.shell));
+    }
+
+    Ok(())
+}
+
+/// Return the hook script for a shell.
+fn hook_script(shell: &HookShell) -> String {
+    let garden = cmd::shell_quote(&cmd::current_exe());
+    match shell {
+        HookShell::Bash => format!(
+            r#"_garden_hook() {{
+    local previous_exit_status=$?
+    if [[ "${{_garden_hook_pwd-}}" != "$PWD" ]]; then
+        _garden_hook_pwd=$PWD
+        eval "$({garden} hook bash --export)"
+    fi
+    return $previous_exit_status
+}}
+if [[ ";${{PROMPT_COMMAND[*]:-}};" != *";_garden_hook;"* ]]; then
+    PROMPT_COMMAND="_garden_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi
+"#
+        ),
+        HookShell::Zsh => format!(
+            r#"_garden_hook() {{
+    if [[ "${{_garden_hook_pwd-}}" != "$PWD" ]]; then
+        _garden_hook_pwd=$PWD
+        eval "$({garden} hook zsh --export)"
+    fi
+}}
+typeset -ag precmd_functions
+if (( ! ${{precmd_functions[(I)_garden_hook]}} )); then
+    precmd_functions=(_garden_hook $precmd_functions)
+fi
+"#
+        ),
+        HookShell::Fish => format!(
+            r#"function _garden_hook --on-variable PWD --description 'Load garden tree environments'
+    {garden} hook fish --export | source
+end
+_garden_hook
+"#
+        ),
+    }
+}
+
+/// The tree whose environment was loaded and the values that it replaced.
+/// This is recorded in $GARDEN_HOOK_STATE as JSON.
+struct HookState {
+    config: String,
+    tree: String,
+    /// Previous values for the variables that were set. None when a variable was unset.
+    saved: Vec<(String, Option<String>)>,
+}
+
+impl HookState {
+    /// Read the state from the environment.
+    fn from_env() -> Option<Self> {
+        let string = std::env::var(constants::ENV_GARDEN_HOOK_STATE).ok()?;
+        let value: serde_json::Value = serde_json::from_str(&string).ok()?;
+        let saved = value["saved"]
+            .as_object()?
+            .iter()
+            .map(|(name, value)| (name.clone(), value.as_str().map(String::from)))
+            .collect();
+
+        Some(HookState {
+            config: value["config"].as_str()?.to_string(),
+            tree: value["tree"].as_str()?.to_string(),
+            saved,
+        })
+    }
+
+    /// Serialize the state to JSON.
+    fn to_json(&self) -> String {
+        let saved: serde_json::Map<String, serde_json::Value> = self
+            .saved
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    Some(value) => serde_json::Value::String(value.clone()),
+                    None => serde_json::Value::Null,
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        let value = serde_json::json!({
+            "config": self.config,
+            "tree": self.tree,
+            "saved": saved,
+        });
+
+        value.to_string()
+    }
+}
+
+/// Return the statements that restore the previous environment and load the
+/// environment for the tree that contains the current directory.
+fn export(options: &cli::MainOptions, shell: &HookShell) -> String {
+    let state = HookState::from_env();
+    // Restore the values that were replaced by the previous tree so that the
+    // configuration and the new environment are evaluated against the original values.
+    if let Some(state) = &state {
+        for (name, value) in &state.saved {
+            match value {
+                Some(value) => std::env::set_var(name, value),
+                None => std::env::remove_var(name),
+            }
+        }
+    }
+    // Errors are ignored so that directories outside of a garden are quiet.
+    let app_context = if config_exists(options) {
+        model::ApplicationContext::from_options(options).ok()
+    } else {
+        None
+    };
+    let current = app_context.as_ref().and_then(|app_context| {
+        let config = app_context.get_root_config();
+        let tree = enclosing_tree(config)?;
+        let config_path = config.path.as_ref()?.to_string_lossy().to_string();
+        Some((config_path, tree))
+    });
+    // Nothing needs to change when the current directory is in the same tree.
+    let unchanged = match (&state, &current) {
+        (Some(state), Some((config, tree))) => state.config == *config && state.tree == *tree,
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        return String::new();
+    }
+
+    let mut output = String::new();
+    if let Some(state) = state {
+        let mut restored = Vec::new();
+        for (name, value) in state.saved {
+            match value {
+                Some(value) => restored.push((name, value)),
+                None => output.push_str(&shell.unset(&name)),
+            }
+        }
+        output.push_str(&cmds::env::format_environment(
+            &restored,
+            &shell.env_format(),
+        ));
+    }
+
+    let (app_context, (config_path, tree)) = match (app_context, current) {
+        (Some(app_context), Some(current)) => (app_context, current),
+        _ => {
+            output.push_str(&shell.unset(constants::ENV_GARDEN_HOOK_STATE));
+            return output;
+        }
+    };
+    let config = app_context.get_root_config();
+    let context = model::TreeContext::new(&tree, config.get_id(), None, None);
+    // Evaluation errors are ignored so that the prompt is not interrupted.
+    let env = cmds::env::environment(&app_context, config, &context).unwrap_or_default();
+    let state = HookState {
+        config: config_path,
+        saved: env
+            .iter()
+            .map(|(name, _)| (name.clone(), std::env::var(name).ok()))
+            .collect(),
+        tree,
+    };
+    let mut env = env;
+    env.push((
+        constants::ENV_GARDEN_HOOK_STATE.to_string(),
+        state.to_json(),
+    ));
+    output.push_str(&cmds::env::format_environment(&env, &shell.env_format()));
+
+    output
+}
+
+/// Return true when a garden file can be found from the current directory.
+fn config_exists(options: &cli::MainOptions) -> bool {
+    let basename = match &options.config {
+        Some(config_path) if config_path.is_file() => return true,
+        Some(config_path) => config_path.clone(),
+        None => std::path::PathBuf::from(constants::GARDEN_CONFIG),
+    };
+
+    config::search_path()
+        .iter()
+        .any(|dir| dir.join(&basename).exists())
+}
+
+/// Return the name of the tree that contains the current directory.
+fn enclosing_tree(config: &model::Configuration) -> Option<String> {
+    let current_dir = path::current_dir();
+    let current_dir = current_dir.canonicalize().unwrap_or(current_dir);
+    current_dir
+        .ancestors()
+        .find_map(|dir| query::tree_name_from_abspath(config, dir))
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{constants, display, model, query};
+use crate::{constants, display, errors, eval, git, model, query};
 
 /// Query tree status
 #[derive(Parser, Clone, Debug)]
@@ -13,21 +13,45 @@ pub struct ListOptions {
     /// Do not list commands
     #[arg(long, short = 'c', default_value_t = false)]
     no_commands: bool,
+    /// Output format [text, json, yaml]
+    #[arg(
+        long,
+        short,
+        default_value_t = model::OutputFormat::Text,
+        value_parser = model::OutputFormat::parse_from_str,
+    )]
+    format: model::OutputFormat,
     /// Display worktrees
     #[arg(short, long, default_value_t = false)]
     worktrees: bool,
     /// Filter trees by name post-query using a glob pattern
     #[arg(long, short, default_value = "*")]
     trees: String,
+    /// Only include trees with uncommitted changes or with commits that differ from REF
+    #[arg(long, value_name = "REF")]
+    changed_since: Option<String>,
     /// Tree query for the gardens, groups or trees to display
     queries: Vec<String>,
 }
 
+impl ListOptions {
+    /// Return true when the tree should be listed according to "--changed-since".
+    fn is_changed(&self, tree: &model::Tree) -> bool {
+        match &self.changed_since {
+            Some(reference) => git::tree_has_changes_since(tree, reference),
+            None => true,
+        }
+    }
+}
+
 /// Main entry point for the "garden ls" command
 pub fn main(app_context: &model::ApplicationContext, options: &mut ListOptions) -> Result<()> {
     if options.queries.is_empty() {
         options.queries.push("@*".into());
     }
+    if options.format != model::OutputFormat::Text {
+        return list_structured(app_context, options);
+    }
     list(app_context, options)
 }
 
@@ -37,6 +61,8 @@ fn list(app_context: &model::ApplicationContext, options: &ListOptions) -> Resul
     let display_all = options.all;
     let display_worktrees = options.worktrees;
     let show_commands = !options.no_commands;
+    // Commands are described by the top-level commands block.
+    let specs = &config.command_specs;
     let verbose = app_context.options.verbose;
     let mut needs_newline = false;
 
@@ -46,7 +72,7 @@ fn list(app_context: &model::ApplicationContext, options: &ListOptions) -> Resul
 
     for query in &options.queries {
         // Resolve the tree query into a vector of tree contexts.
-        let contexts = query::resolve_and_filter_trees(app_context, config, query, &options.trees);
+        let contexts = query::resolve_and_filter_trees(app_context, config, query, &options.trees)?;
         // Loop over each context and display the tree.
         for (idx, context) in contexts.iter().enumerate() {
             let config = match context.config {
@@ -57,6 +83,9 @@ fn list(app_context: &model::ApplicationContext, options: &ListOptions) -> Resul
                 Some(tree) => tree,
                 None => continue,
             };
+            if !options.is_changed(tree) {
+                continue;
+            }
             let path = match tree.path_as_ref() {
                 Ok(path) => path,
                 Err(_) => continue,
@@ -73,12 +102,18 @@ fn list(app_context: &model::ApplicationContext, options: &ListOptions) -> Resul
                         context,
                         tree,
                         display_worktrees,
-                    );
+                    )?;
                     if show_commands && !tree.commands.is_empty() {
-                        display::print_commands(&tree.commands);
+                        display::print_commands(&tree.commands, specs);
                     }
                 }
-                needs_newline = display_all;
+                if verbose > 1 {
+                    display::print_sources(
+                        config,
+                        &format!("{}.{}", constants::TREES, context.tree),
+                    );
+                }
+                needs_newline = display_all || verbose > 1;
                 continue;
             }
 
@@ -95,9 +130,12 @@ fn list(app_context: &model::ApplicationContext, options: &ListOptions) -> Resul
                 println!();
             }
             display::print_tree(tree, config.tree_branches, verbose, false);
-            display::print_tree_extended_details(app_context, context, tree, display_worktrees);
+            display::print_tree_extended_details(app_context, context, tree, display_worktrees)?;
             if show_commands && !tree.commands.is_empty() {
-                display::print_commands(&tree.commands);
+                display::print_commands(&tree.commands, specs);
+            }
+            if verbose > 1 {
+                display::print_sources(config, &format!("{}.{}", constants::TREES, context.tree));
             }
             needs_newline = true;
         }
@@ -106,17 +144,168 @@ fn list(app_context: &model::ApplicationContext, options: &ListOptions) -> Resul
     if !config.groups.is_empty() {
         println!();
         display::print_groups(&config.groups);
+        if verbose > 1 {
+            display::print_sources(config, constants::GROUPS);
+        }
     }
 
     if !config.gardens.is_empty() {
         println!();
         display::print_gardens(&config.gardens);
+        if verbose > 1 {
+            display::print_sources(config, constants::GARDENS);
+        }
     }
 
     if show_commands && !config.commands.is_empty() {
         println!();
-        display::print_commands(&config.commands);
+        display::print_commands(&config.commands, specs);
+        if verbose > 1 {
+            display::print_sources(config, constants::COMMANDS);
+        }
+    }
+
+    Ok(())
+}
+
+/// List tree details as structured JSON or YAML data
+fn list_structured(app_context: &model::ApplicationContext, options: &ListOptions) -> Result<()> {
+    let config = app_context.get_root_config();
+    let mut trees = Vec::new();
+
+    if app_context.options.debug_level(constants::DEBUG_LEVEL_LIST) > 0 {
+        debug!("queries: {:?}", options.queries);
+        debug!("format: {}", options.format);
     }
 
+    // Resolve the members of each garden and group once so that each tree
+    // can report the gardens and groups that it belongs to.
+    let garden_members: Vec<(&model::GardenName, Vec<model::TreeContext>)> = config
+        .gardens
+        .iter()
+        .map(|(name, garden)| {
+            let members = query::trees_from_garden(app_context, config, None, garden);
+            (name, members)
+        })
+        .collect();
+    let group_members: Vec<(&model::GroupName, Vec<model::TreeContext>)> = config
+        .groups
+        .iter()
+        .map(|(name, group)| {
+            let members = query::trees_from_group(app_context, config, None, None, group);
+            (name, members)
+        })
+        .collect();
+
+    for query in &options.queries {
+        let contexts = query::resolve_and_filter_trees(app_context, config, query, &options.trees)?;
+        for context in &contexts {
+            let tree_config = match context.config {
+                Some(config_id) => app_context.get_config(config_id),
+                None => config,
+            };
+            let tree = match tree_config.trees.get(&context.tree) {
+                Some(tree) => tree,
+                None => continue,
+            };
+            if !options.is_changed(tree) {
+                continue;
+            }
+            let gardens = member_names(app_context, &garden_members, context);
+            let groups = member_names(app_context, &group_members, context);
+            trees.push(tree_details(
+                app_context,
+                tree_config,
+                context,
+                tree,
+                &gardens,
+                &groups,
+            )?);
+        }
+    }
+
+    display::print_structured(&serde_json::Value::Array(trees), &options.format);
+
     Ok(())
 }
+
+/// Return the names of the gardens or groups that contain the tree.
+fn member_names<'a>(
+    app_context: &model::ApplicationContext,
+    members: &[(&'a String, Vec<model::TreeContext>)],
+    context: &model::TreeContext,
+) -> Vec<&'a String> {
+    // Trees from the root configuration may or may not have a config id.
+    let root_id = app_context.get_root_id();
+    let config_id = context.config.unwrap_or(root_id);
+    members
+        .iter()
+        .filter(|(_, contexts)| {
+            contexts
+                .iter()
+                .any(|ctx| ctx.tree == context.tree && ctx.config.unwrap_or(root_id) == config_id)
+        })
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// Return the details for a tree as a JSON object.
+fn tree_details(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    context: &model::TreeContext,
+    tree: &model::Tree,
+    gardens: &[&String],
+    groups: &[&String],
+) -> Result<serde_json::Value, errors::GardenError> {
+    let evaluate = |var: &model::Variable| {
+        eval::tree_variable(
+            app_context,
+            config,
+            None,
+            &context.tree,
+            context.garden.as_ref(),
+            var,
+        )
+    };
+    let path = tree.path_as_ref().ok();
+    let exists = path.is_some_and(|path| std::path::Path::new(path).exists());
+    let branch = match tree.canonical_pathbuf() {
+        Some(pathbuf) if exists && !tree.is_symlink => git::branch(&pathbuf),
+        _ => None,
+    };
+    let remotes: serde_json::Map<String, serde_json::Value> = tree
+        .remotes
+        .iter()
+        .map(|(name, remote)| Ok((name.to_string(), evaluate(remote)?.into())))
+        .collect::<Result<_, errors::GardenError>>()?;
+    let links = tree
+        .links
+        .iter()
+        .map(evaluate)
+        .collect::<Result<Vec<String>, errors::GardenError>>()?;
+    let tags: Vec<&String> = tree.tags.iter().collect();
+    let worktree = match tree.is_worktree {
+        true => Some(evaluate(&tree.worktree)?),
+        false => None,
+    };
+    let graft = context
+        .config
+        .map(|config_id| app_context.get_graft_namespace(config_id))
+        .filter(|namespace| !namespace.is_empty());
+
+    Ok(serde_json::json!({
+        "name": context.tree,
+        "path": path,
+        "exists": exists,
+        "branch": branch,
+        "description": tree.description,
+        "tags": tags,
+        "remotes": remotes,
+        "links": links,
+        "gardens": gardens,
+        "groups": groups,
+        "graft": graft,
+        "worktree": worktree,
+    }))
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use clap::Parser;
+use indexmap::IndexMap;
+use yaml_rust::{yaml, Yaml};
+
+use crate::{cmd, config, constants, display, errors, model, query};
+
+/// Record the current commit of trees in "garden.lock"
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct LockOptions {
+    /// Filter trees by name post-query using a glob pattern
+    #[arg(long, short, default_value = "*")]
+    trees: String,
+    /// Tree query for the gardens, groups or trees to lock
+    #[arg(default_value = "@*")]
+    queries: Vec<String>,
+}
+
+/// Locked commits keyed by tree name. Grafted trees use "graft::tree" names.
+pub(crate) type LockedCommits = IndexMap<String, String>;
+
+/// Main entry point for the "garden lock" command
+pub fn main(app_context: &model::ApplicationContext, options: &LockOptions) -> Result<()> {
+    let config = app_context.get_root_config();
+    let quiet = app_context.options.quiet;
+    let verbose = app_context.options.verbose;
+    let path = lockfile_path(config)?;
+
+    // Entries for trees outside of the current query are retained.
+    let mut commits = if path.exists() {
+        read_lockfile(&path)?
+    } else {
+        LockedCommits::new()
+    };
+
+    let mut exit_status = errors::EX_OK;
+    for query in &options.queries {
+        let contexts = query::resolve_and_filter_trees(app_context, config, query, &options.trees)?;
+        for context in &contexts {
+            let tree_config = match context.config {
+                Some(config_id) => app_context.get_config(config_id),
+                None => config,
+            };
+            let tree = match tree_config.trees.get(&context.tree) {
+                Some(tree) => tree,
+                None => continue,
+            };
+            if tree.is_symlink {
+                continue;
+            }
+            let pathbuf = match tree.pathbuf() {
+                Some(pathbuf) if pathbuf.exists() => pathbuf,
+                _ => {
+                    if !quiet {
+                        display::print_missing_tree(tree, tree.path_as_ref()?, verbose);
+                    }
+                    continue;
+                }
+            };
+            let command = ["git", "rev-parse", "HEAD"];
+            let exec = cmd::exec_in_dir(&command, &pathbuf);
+            match cmd::stdout_to_string(exec) {
+                Ok(commit) if !commit.is_empty() => {
+                    commits.insert(lock_name(app_context, context), commit);
+                }
+                _ => {
+                    eprintln!("error: {}: unable to read HEAD", tree.get_name());
+                    exit_status = errors::EX_ERROR;
+                }
+            }
+        }
+    }
+
+    write_lockfile(&commits, &path)?;
+    if verbose > 0 {
+        eprintln!("wrote {}", path.display());
+    }
+
+    cmd::result_from_exit_status(exit_status).map_err(|err| err.into())
+}
+
+/// Return the path to the "garden.lock" file that sits next to the configuration.
+pub(crate) fn lockfile_path(
+    config: &model::Configuration,
+) -> Result<std::path::PathBuf, errors::GardenError> {
+    let dirname = config.dirname.as_ref().ok_or_else(|| {
+        errors::GardenError::ConfigurationError("unable to locate the configuration".into())
+    })?;
+
+    Ok(dirname.join(constants::GARDEN_LOCK))
+}
+
+/// Return the name used to record a tree in "garden.lock".
+pub(crate) fn lock_name(
+    app_context: &model::ApplicationContext,
+    context: &model::TreeContext,
+) -> String {
+    match context.config {
+        Some(config_id) => {
+            let namespace = app_context.get_graft_namespace(config_id);
+            if namespace.is_empty() {
+                context.tree.to_string()
+            } else {
+                format!("{namespace}::{}", context.tree)
+            }
+        }
+        None => context.tree.to_string(),
+    }
+}
+
+/// Read the locked commits from a "garden.lock" file.
+pub(crate) fn read_lockfile(path: &std::path::Path) -> Result<LockedCommits, errors::GardenError> {
+    let doc = config::reader::read_yaml(path)?;
+    let mut commits = LockedCommits::new();
+    if let Some(trees) = doc[constants::TREES].as_hash() {
+        for (name, entry) in trees {
+            if let (Some(name), Some(commit)) = (name.as_str(), entry[constants::COMMIT].as_str()) {
+                commits.insert(name.to_string(), commit.to_string());
+            }
+        }
+    }
+
+    Ok(commits)
+}
+
+/// Write the locked commits to a "garden.lock" file.
+fn write_lockfile(commits: &LockedCommits, path: &std::path::Path) -> Result<()> {
+    let mut trees = yaml::Hash::new();
+    for (name, commit) in commits {
+        let mut entry = yaml::Hash::new();
+        entry.insert(
+            Yaml::String(constants::COMMIT.to_string()),
+            Yaml::String(commit.to_string()),
+        );
+        trees.insert(Yaml::String(name.to_string()), Yaml::Hash(entry));
+    }
+    let mut doc = yaml::Hash::new();
+    doc.insert(
+        Yaml::String(constants::TREES.to_string()),
+        Yaml::Hash(trees),
+    );
+    config::writer::write_yaml(&Yaml::Hash(doc), path)?;
+
+    Ok(())
+}
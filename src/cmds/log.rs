@@ -0,0 +1,137 @@
+use std::io::prelude::*;
+
+use anyhow::Result;
+use clap::{Parser, ValueHint};
+
+use crate::{cmd, constants, display, errors, model, query};
+
+/// Show commit logs across trees
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct LogOptions {
+    /// Filter trees by name post-query using a glob pattern
+    #[arg(long, short, default_value = "*")]
+    trees: String,
+    /// Tree query for the gardens, groups or trees to inspect
+    #[arg(value_hint=ValueHint::Other)]
+    query: String,
+    /// Arguments to pass to "git log"
+    #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+    arguments: Vec<String>,
+}
+
+/// Show changes across trees
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct DiffOptions {
+    /// Filter trees by name post-query using a glob pattern
+    #[arg(long, short, default_value = "*")]
+    trees: String,
+    /// Tree query for the gardens, groups or trees to inspect
+    #[arg(value_hint=ValueHint::Other)]
+    query: String,
+    /// Arguments to pass to "git diff"
+    #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+    arguments: Vec<String>,
+}
+
+/// Main entry point for the "garden log" command
+pub fn main_log(app_context: &model::ApplicationContext, options: &LogOptions) -> Result<()> {
+    run_git(
+        app_context,
+        "log",
+        &options.query,
+        &options.trees,
+        &options.arguments,
+    )
+}
+
+/// Main entry point for the "garden diff" command
+pub fn main_diff(app_context: &model::ApplicationContext, options: &DiffOptions) -> Result<()> {
+    run_git(
+        app_context,
+        "diff",
+        &options.query,
+        &options.trees,
+        &options.arguments,
+    )
+}
+
+/// Run "git <command> <arguments>" in each tree and display the output grouped by tree.
+/// Trees that produce no output are skipped.
+fn run_git(
+    app_context: &model::ApplicationContext,
+    git_command: &str,
+    query: &str,
+    tree_pattern: &str,
+    arguments: &[String],
+) -> Result<()> {
+    if app_context.options.debug_level(constants::DEBUG_LEVEL_CMD) > 0 {
+        debug!("query: {query}");
+        debug!("git {git_command}: {arguments:?}");
+    }
+    let config = app_context.get_root_config();
+    let quiet = app_context.options.quiet;
+    let verbose = app_context.options.verbose;
+
+    // Output is captured so we must ask Git for colors explicitly.
+    let mut command: Vec<&str> = vec!["git"];
+    if yansi::Paint::is_enabled() {
+        command.push("-c");
+        command.push("color.ui=always");
+    }
+    command.push(git_command);
+    command.extend(arguments.iter().map(String::as_str));
+
+    let contexts = query::resolve_and_filter_trees(app_context, config, query, tree_pattern)?;
+    let mut exit_status = errors::EX_OK;
+    let mut num_trees = 0;
+    let mut num_trees_with_output = 0;
+    for context in &contexts {
+        let tree_config = match context.config {
+            Some(config_id) => app_context.get_config(config_id),
+            None => config,
+        };
+        let tree = match tree_config.trees.get(&context.tree) {
+            Some(tree) => tree,
+            None => continue,
+        };
+        if tree.is_symlink {
+            continue;
+        }
+        // Missing trees have no output and are skipped.
+        let path = match tree.pathbuf() {
+            Some(path) if path.exists() => path,
+            _ => continue,
+        };
+        num_trees += 1;
+
+        let exec = cmd::exec_in_dir(&command, &path);
+        let (status, stdout, stderr) = cmd::status_with_output(exec);
+        if status != errors::EX_OK {
+            exit_status = status;
+        }
+        if stdout.is_empty() && stderr.is_empty() {
+            continue;
+        }
+        num_trees_with_output += 1;
+
+        display::print_tree(tree, config.tree_branches, verbose, quiet);
+        std::io::stdout().write_all(&stdout).unwrap_or(());
+        std::io::stdout().flush().unwrap_or(());
+        std::io::stderr().write_all(&stderr).unwrap_or(());
+    }
+
+    if !quiet {
+        eprintln!(
+            "{} {} of {} trees with output from \"git {}\"",
+            display::Color::cyan("#"),
+            num_trees_with_output,
+            num_trees,
+            git_command
+        );
+    }
+
+    // Return the last non-zero exit status.
+    cmd::result_from_exit_status(exit_status).map_err(|err| err.into())
+}
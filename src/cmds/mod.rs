@@ -1,9 +1,18 @@
 /// Configuration-defined commands
 pub mod cmd;
 
+/// Cache command
+pub mod cache;
+
+/// Check command
+pub mod check;
+
 /// Completion command
 pub mod completion;
 
+/// Env command
+pub mod env;
+
 /// Exec command
 pub mod exec;
 
@@ -13,12 +22,21 @@ pub mod eval;
 /// Grow command
 pub mod grow;
 
+/// Hook command
+pub mod hook;
+
 /// Init command
 pub mod init;
 
 /// List command
 pub mod list;
 
+/// Lock command
+pub mod lock;
+
+/// Log and diff commands
+pub mod log;
+
 /// Plant command
 pub mod plant;
 
@@ -27,3 +45,12 @@ pub mod prune;
 
 /// Shell command
 pub mod shell;
+
+/// Status command
+pub mod status;
+
+/// Sync command
+pub mod sync;
+
+/// Vars command
+pub mod vars;
@@ -16,42 +16,7 @@ pub struct ShellOptions {
 
 pub fn main(app_context: &model::ApplicationContext, options: &ShellOptions) -> Result<()> {
     let config = app_context.get_root_config_mut();
-    let contexts = query::resolve_trees(app_context, config, None, &options.query);
-    if contexts.is_empty() {
-        return Err(errors::GardenError::EmptyTreeQueryResult(options.query.clone()).into());
-    }
-    let mut context = contexts[0].clone();
-
-    // If a tree's name in the returned contexts exactly matches the tree
-    // query that was used to find it then chdir into that tree.
-    // This makes it convenient to have gardens and trees with the same name.
-    for ctx in &contexts {
-        if ctx.tree == options.query {
-            context = ctx.clone();
-            break;
-        }
-    }
-
-    if let Some(tree) = &options.tree {
-        let mut found = false;
-        if let Some(ctx) = query::tree_from_name(config, tree, None, None) {
-            for query_ctx in &contexts {
-                if ctx.tree == query_ctx.tree {
-                    context = query_ctx.clone();
-                    found = true;
-                    break;
-                }
-            }
-        } else {
-            error!("unable to find '{}': No tree exists with that name", tree);
-        }
-        if !found {
-            error!(
-                "'{}' was not found in the tree query '{}'",
-                tree, options.query
-            );
-        }
-    }
+    let context = find_context(app_context, config, &options.query, options.tree.as_ref())?;
 
     // Evaluate garden.shell
     let graft_config = context.config.map(|id| app_context.get_config(id));
@@ -67,7 +32,7 @@ pub fn main(app_context: &model::ApplicationContext, options: &ShellOptions) ->
         shell_expr,
         &context.tree,
         context.garden.as_ref(),
-    );
+    )?;
 
     let verbose = app_context.options.verbose;
     let quiet = verbose == 0;
@@ -89,3 +54,48 @@ pub fn main(app_context: &model::ApplicationContext, options: &ShellOptions) ->
         .into())
     }
 }
+
+/// Resolve a tree query into the context used to build an environment.
+/// The tree whose name matches the query or the specified tree is used when present.
+pub(crate) fn find_context(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    query: &str,
+    tree: Option<&String>,
+) -> Result<model::TreeContext, errors::GardenError> {
+    let contexts = query::resolve_trees(app_context, config, None, query)?;
+    if contexts.is_empty() {
+        return Err(errors::GardenError::EmptyTreeQueryResult(query.to_string()));
+    }
+    let mut context = contexts[0].clone();
+
+    // If a tree's name in the returned contexts exactly matches the tree
+    // query that was used to find it then chdir into that tree.
+    // This makes it convenient to have gardens and trees with the same name.
+    for ctx in &contexts {
+        if ctx.tree == query {
+            context = ctx.clone();
+            break;
+        }
+    }
+
+    if let Some(tree) = tree {
+        let mut found = false;
+        if let Some(ctx) = query::tree_from_name(config, tree, None, None) {
+            for query_ctx in &contexts {
+                if ctx.tree == query_ctx.tree {
+                    context = query_ctx.clone();
+                    found = true;
+                    break;
+                }
+            }
+        } else {
+            error!("unable to find '{}': No tree exists with that name", tree);
+        }
+        if !found {
+            error!("'{}' was not found in the tree query '{}'", tree, query);
+        }
+    }
+
+    Ok(context)
+}
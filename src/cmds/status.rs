@@ -0,0 +1,173 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{display, git, model, query};
+
+/// Display the branch and status of trees
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct StatusOptions {
+    /// Filter trees by name post-query using a glob pattern
+    #[arg(long, short, default_value = "*")]
+    trees: String,
+    /// Tree queries for the gardens, groups or trees to display
+    queries: Vec<String>,
+}
+
+/// A row in the status table.
+struct StatusRow {
+    name: String,
+    branch: String,
+    /// None when the status is not available, e.g. for bare repositories.
+    status: Option<model::GitTreeStatus>,
+}
+
+/// Main entry point for the "garden status" command
+pub fn main(app_context: &model::ApplicationContext, options: &mut StatusOptions) -> Result<()> {
+    if options.queries.is_empty() {
+        options.queries.push("@*".into());
+    }
+    status(app_context, options)
+}
+
+/// Gather the status for each tree and display a table.
+fn status(app_context: &model::ApplicationContext, options: &StatusOptions) -> Result<()> {
+    let config = app_context.get_root_config();
+    let quiet = app_context.options.quiet;
+    let verbose = app_context.options.verbose;
+    let tree_branches = config.tree_branches;
+    let mut rows = Vec::new();
+
+    for query in &options.queries {
+        let contexts = query::resolve_and_filter_trees(app_context, config, query, &options.trees)?;
+        for context in &contexts {
+            let config = match context.config {
+                Some(config_id) => app_context.get_config(config_id),
+                None => config,
+            };
+            let tree = match config.trees.get(&context.tree) {
+                Some(tree) => tree,
+                None => continue,
+            };
+            if tree.is_symlink {
+                continue;
+            }
+            // Sparse gardens/missing trees are okay -> skip these entries.
+            if let Err(missing) = display::display_tree_entry(tree, false, verbose) {
+                if !quiet {
+                    eprintln!("{missing}");
+                }
+                continue;
+            }
+            let pathbuf = match tree.canonical_pathbuf() {
+                Some(pathbuf) => pathbuf,
+                None => continue,
+            };
+            let details = git::worktree_details(&pathbuf).ok();
+            let is_bare = details
+                .as_ref()
+                .is_some_and(|details| details.tree_type == model::GitTreeType::Bare);
+            let branch = match &details {
+                Some(details) if is_bare => details.branch.clone(),
+                _ => git::branch(&pathbuf).unwrap_or_default(),
+            };
+            let status = match is_bare {
+                true => None,
+                false => git::status(&pathbuf).ok(),
+            };
+            rows.push(StatusRow {
+                name: context.tree.clone(),
+                branch,
+                status,
+            });
+        }
+    }
+
+    print_status_table(&rows, tree_branches, quiet);
+
+    Ok(())
+}
+
+/// Format a count for display. Unavailable counts are displayed as "-".
+fn count_string(count: Option<usize>) -> String {
+    match count {
+        Some(count) => count.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// Print the status rows as an aligned table.
+fn print_status_table(rows: &[StatusRow], tree_branches: bool, quiet: bool) {
+    if rows.is_empty() {
+        return;
+    }
+    let headers = [
+        "TREE",
+        "BRANCH",
+        "AHEAD",
+        "BEHIND",
+        "STAGED",
+        "DIRTY",
+        "UNTRACKED",
+        "STASH",
+    ];
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.len())
+        .chain([headers[0].len()])
+        .max()
+        .unwrap_or_default();
+    let branch_width = rows
+        .iter()
+        .map(|row| row.branch.len())
+        .chain([headers[1].len()])
+        .max()
+        .unwrap_or_default();
+
+    if !quiet {
+        let mut header = format!("{:<name_width$}", headers[0]);
+        if tree_branches {
+            header.push_str(&format!("  {:<branch_width$}", headers[1]));
+        }
+        for title in &headers[2..] {
+            header.push_str(&format!("  {title}"));
+        }
+        println!("{}", display::Color::cyan(header.trim_end()).bold());
+    }
+
+    for row in rows {
+        let status = row.status.as_ref();
+        let ahead = status.and_then(|status| status.upstream.map(|(ahead, _)| ahead));
+        let behind = status.and_then(|status| status.upstream.map(|(_, behind)| behind));
+        let staged = status.map(|status| status.staged);
+        let dirty = status.map(|status| status.dirty);
+        let untracked = status.map(|status| status.untracked);
+        let stashes = status.map(|status| status.stashes);
+
+        let mut line = format!("{:<name_width$}", display::Color::blue(&row.name).bold());
+        if tree_branches {
+            line.push_str(&format!(
+                "  {:<branch_width$}",
+                display::Color::green(&row.branch)
+            ));
+        }
+        let columns = [
+            (ahead, headers[2].len(), yansi::Color::Yellow),
+            (behind, headers[3].len(), yansi::Color::Yellow),
+            (staged, headers[4].len(), yansi::Color::Green),
+            (dirty, headers[5].len(), yansi::Color::Red),
+            (untracked, headers[6].len(), yansi::Color::Red),
+            (stashes, headers[7].len(), yansi::Color::Yellow),
+        ];
+        for (count, width, color) in columns {
+            let value = count_string(count);
+            if count.unwrap_or(0) > 0 {
+                let value = display::Color::new(value).fg(color);
+                line.push_str(&format!("  {value:<width$}"));
+            } else {
+                line.push_str(&format!("  {value:<width$}"));
+            }
+        }
+        println!("{}", line.trim_end());
+    }
+}
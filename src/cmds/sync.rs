@@ -0,0 +1,307 @@
+use std::io::prelude::*;
+
+use anyhow::Result;
+use clap::Parser;
+use rayon::prelude::*;
+
+use crate::{cmd, constants, display, errors, git, model, query};
+
+/// Get the default number of sync jobs to run in parallel
+fn default_num_jobs() -> usize {
+    match std::thread::available_parallelism() {
+        Ok(value) => value.get(),
+        Err(_) => 4,
+    }
+}
+
+/// Fetch remotes and fast-forward branches
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct SyncOptions {
+    /// Number of parallel jobs
+    #[arg(short = 'j', long = "jobs", default_value_t = default_num_jobs())]
+    num_jobs: usize,
+    /// Filter trees by name post-query using a glob pattern
+    #[arg(long, short, default_value = "*")]
+    trees: String,
+    /// Tree query for the gardens, groups or trees to sync
+    #[arg(required = true)]
+    queries: Vec<String>,
+}
+
+/// A tree resolved by the tree queries.
+struct QueryTree<'a> {
+    tree: &'a model::Tree,
+    /// The "tree-branches" setting from the tree's configuration.
+    tree_branches: bool,
+}
+
+/// A tree that will be synchronized.
+struct SyncTree {
+    /// The position of the tree in the query results.
+    index: usize,
+    path: std::path::PathBuf,
+    is_bare: bool,
+}
+
+/// Trees that share a Git repository through "git worktree" are synchronized
+/// sequentially by a single job so that the repository is only fetched once.
+struct SyncJob {
+    /// The path to the shared repository.
+    path: std::path::PathBuf,
+    /// Remotes to fetch. All remotes are fetched when no remotes are configured.
+    remotes: Vec<String>,
+    trees: Vec<SyncTree>,
+}
+
+/// The outcome of synchronizing a tree.
+enum SyncState {
+    /// The branch is already up to date with its upstream branch.
+    UpToDate,
+    /// The branch has local commits that are not present upstream.
+    Ahead(usize),
+    /// The branch was fast-forwarded by the specified number of commits.
+    FastForward(usize),
+    /// The branch and its upstream branch have diverged (ahead, behind).
+    Diverged(usize, usize),
+    /// The branch is behind its upstream branch but the worktree has uncommitted changes.
+    Dirty(usize),
+    /// HEAD is detached.
+    Detached,
+    /// The branch does not have an upstream branch.
+    NoUpstream,
+    /// Bare repositories are fetched but have no worktree to update.
+    Bare,
+    /// "git fetch" failed for the specified remote.
+    FetchError(String),
+    /// "git status" or "git merge --ff-only" failed.
+    Error,
+}
+
+/// The result of synchronizing a tree.
+struct SyncResult {
+    index: usize,
+    state: SyncState,
+    exit_status: i32,
+    /// Output captured from the git commands that failed.
+    output: Vec<u8>,
+}
+
+/// Main entry point for the "garden sync" command
+pub fn main(app_context: &model::ApplicationContext, options: &SyncOptions) -> Result<()> {
+    if app_context.options.debug_level(constants::DEBUG_LEVEL_CMD) > 0 {
+        debug!("queries: {:?}", options.queries);
+        debug!("jobs: {}", options.num_jobs);
+    }
+    let (trees, jobs) = sync_jobs(app_context, options)?;
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.num_jobs.max(1))
+        .build()?;
+    let mut results: Vec<Option<SyncResult>> = trees.iter().map(|_| None).collect();
+    for result in pool.install(|| jobs.par_iter().flat_map(sync_job).collect::<Vec<_>>()) {
+        let index = result.index;
+        results[index] = Some(result);
+    }
+
+    let quiet = app_context.options.quiet;
+    let verbose = app_context.options.verbose;
+    let mut exit_status = errors::EX_OK;
+    for (query_tree, result) in trees.iter().zip(&results) {
+        let tree = query_tree.tree;
+        let tree_branches = query_tree.tree_branches;
+        // Sparse gardens/missing trees are okay -> display and skip these entries.
+        let result = match result {
+            Some(result) => result,
+            None => {
+                display::print_tree(tree, tree_branches, verbose, quiet);
+                continue;
+            }
+        };
+        if quiet && matches!(result.state, SyncState::UpToDate | SyncState::Bare) {
+            continue;
+        }
+        display::print_tree(tree, tree_branches, verbose, false);
+        print_sync_result(result);
+        if result.exit_status != errors::EX_OK {
+            exit_status = result.exit_status;
+        }
+    }
+
+    // Return the last non-zero exit status.
+    cmd::result_from_exit_status(exit_status).map_err(|err| err.into())
+}
+
+/// Resolve the tree queries and group the trees by their shared repository.
+/// The resolved trees are returned alongside the jobs and are indexed by `SyncTree::index`.
+fn sync_jobs<'a>(
+    app_context: &'a model::ApplicationContext,
+    options: &SyncOptions,
+) -> Result<(Vec<QueryTree<'a>>, Vec<SyncJob>), errors::GardenError> {
+    let config = app_context.get_root_config();
+    let mut trees: Vec<QueryTree> = Vec::new();
+    let mut jobs: Vec<SyncJob> = Vec::new();
+
+    for query in &options.queries {
+        let contexts = query::resolve_and_filter_trees(app_context, config, query, &options.trees)?;
+        for context in &contexts {
+            let tree_config = match context.config {
+                Some(config_id) => app_context.get_config(config_id),
+                None => config,
+            };
+            let tree = match tree_config.trees.get(&context.tree) {
+                Some(tree) => tree,
+                None => continue,
+            };
+            if tree.is_symlink {
+                continue;
+            }
+            let index = trees.len();
+            trees.push(QueryTree {
+                tree,
+                tree_branches: tree_config.tree_branches,
+            });
+            // Missing trees are displayed without being synchronized.
+            let path = match tree.canonical_pathbuf() {
+                Some(path) => path,
+                None => continue,
+            };
+            let shared_path = std::path::PathBuf::from(query::shared_worktree_path(
+                app_context,
+                config,
+                context,
+            )?);
+            let shared_path = shared_path.canonicalize().unwrap_or(shared_path);
+            let sync_tree = SyncTree {
+                index,
+                path,
+                is_bare: tree.is_bare_repository,
+            };
+
+            match jobs.iter_mut().find(|job| job.path == shared_path) {
+                Some(job) => {
+                    for remote in tree.remotes.keys() {
+                        if !job.remotes.contains(remote) {
+                            job.remotes.push(remote.to_string());
+                        }
+                    }
+                    job.trees.push(sync_tree);
+                }
+                None => jobs.push(SyncJob {
+                    path: shared_path,
+                    remotes: tree.remotes.keys().map(String::to_string).collect(),
+                    trees: vec![sync_tree],
+                }),
+            }
+        }
+    }
+
+    Ok((trees, jobs))
+}
+
+/// Fetch the remotes for a shared repository and fast-forward each of its trees.
+fn sync_job(job: &SyncJob) -> Vec<SyncResult> {
+    let mut fetch_error = None;
+    if job.remotes.is_empty() {
+        let command = ["git", "fetch", "--quiet", "--all"];
+        let (status, _, stderr) = cmd::status_with_output(cmd::exec_in_dir(&command, &job.path));
+        if status != errors::EX_OK {
+            fetch_error = Some((String::new(), status, stderr));
+        }
+    }
+    for remote in &job.remotes {
+        let command = ["git", "fetch", "--quiet", remote.as_str()];
+        let (status, _, stderr) = cmd::status_with_output(cmd::exec_in_dir(&command, &job.path));
+        if status != errors::EX_OK {
+            fetch_error = Some((remote.to_string(), status, stderr));
+            break;
+        }
+    }
+
+    job.trees
+        .iter()
+        .map(|tree| {
+            let mut result = SyncResult {
+                index: tree.index,
+                state: SyncState::Error,
+                exit_status: errors::EX_OK,
+                output: Vec::new(),
+            };
+            if let Some((remote, status, stderr)) = &fetch_error {
+                result.state = SyncState::FetchError(remote.to_string());
+                result.exit_status = *status;
+                result.output = stderr.clone();
+            } else if tree.is_bare {
+                result.state = SyncState::Bare;
+            } else {
+                fast_forward(&tree.path, &mut result);
+            }
+            result
+        })
+        .collect()
+}
+
+/// Fast-forward the current branch to its upstream branch when it is safe to do so.
+/// Diverged branches, dirty worktrees and detached HEADs are left as-is.
+fn fast_forward(path: &std::path::Path, result: &mut SyncResult) {
+    let status = match git::status(path) {
+        Ok(status) => status,
+        Err(errors::CommandError::ExitStatus { status, .. }) => {
+            result.state = SyncState::Error;
+            result.exit_status = status;
+            return;
+        }
+    };
+    result.state = match status.upstream {
+        _ if status.detached => SyncState::Detached,
+        None => SyncState::NoUpstream,
+        Some((0, 0)) => SyncState::UpToDate,
+        Some((ahead, 0)) => SyncState::Ahead(ahead),
+        Some((ahead, behind)) if ahead > 0 => SyncState::Diverged(ahead, behind),
+        Some((_, behind)) if status.staged > 0 || status.dirty > 0 => SyncState::Dirty(behind),
+        Some((_, behind)) => {
+            let command = ["git", "merge", "--ff-only", "--quiet", "@{upstream}"];
+            let (exit_status, stdout, stderr) =
+                cmd::status_with_output(cmd::exec_in_dir(&command, path));
+            if exit_status == errors::EX_OK {
+                SyncState::FastForward(behind)
+            } else {
+                result.exit_status = exit_status;
+                result.output = [stdout, stderr].concat();
+                SyncState::Error
+            }
+        }
+    };
+}
+
+/// Display the outcome of synchronizing a tree.
+fn print_sync_result(result: &SyncResult) {
+    let message = match &result.state {
+        SyncState::UpToDate => display::Color::green("up to date").to_string(),
+        SyncState::Ahead(ahead) => display::Color::green(format!("ahead {ahead}")).to_string(),
+        SyncState::FastForward(count) => {
+            display::Color::green(format!("fast-forwarded {count}")).to_string()
+        }
+        SyncState::Diverged(ahead, behind) => {
+            display::Color::red(format!("diverged: ahead {ahead}, behind {behind}")).to_string()
+        }
+        SyncState::Dirty(behind) => {
+            display::Color::yellow(format!("dirty: behind {behind}")).to_string()
+        }
+        SyncState::Detached => display::Color::yellow("detached HEAD").to_string(),
+        SyncState::NoUpstream => display::Color::yellow("no upstream branch").to_string(),
+        SyncState::Bare => display::Color::green("fetched").to_string(),
+        SyncState::FetchError(remote) if remote.is_empty() => {
+            display::Color::red("fetch failed").to_string()
+        }
+        SyncState::FetchError(remote) => {
+            display::Color::red(format!("fetch failed: {remote}")).to_string()
+        }
+        SyncState::Error => display::Color::red("error").to_string(),
+    };
+    println!("{message}");
+    // Flush stdout so that the output remains ordered with the tree headers on stderr.
+    std::io::stdout().flush().unwrap_or(());
+    if !result.output.is_empty() {
+        std::io::stderr().write_all(&result.output).unwrap_or(());
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{constants, display, eval, model, query};
+
+/// List variables and the scopes that define them
+#[derive(Parser, Clone, Debug)]
+#[command(author, about, long_about)]
+pub struct VarsOptions {
+    /// Display variables whose names match a glob pattern
+    #[arg(long, short, default_value = "*")]
+    name: String,
+    /// Tree within which to resolve variables
+    tree: Option<String>,
+    /// Garden within which to resolve variables
+    garden: Option<String>,
+}
+
+/// Variables whose values are provided by garden itself.
+const BUILTIN_VARIABLES: &[&str] = &[
+    constants::GARDEN_CMD_QUIET,
+    constants::GARDEN_CMD_VERBOSE,
+    constants::GARDEN_CONFIG_DIR,
+    constants::GARDEN_ROOT,
+    constants::TREE_NAME,
+    constants::TREE_PATH,
+];
+
+/// Main entry point for the "garden vars" command
+pub fn main(app_context: &model::ApplicationContext, options: &VarsOptions) -> Result<()> {
+    let pattern = glob::Pattern::new(&options.name)?;
+    let config = app_context.get_root_config();
+    match options.tree.as_ref() {
+        None => {
+            // Only the top-level variables are visible when no tree is specified.
+            let mut names: Vec<&String> = config.variables.keys().collect();
+            names.sort();
+            for name in names {
+                if !pattern.matches(name) {
+                    continue;
+                }
+                let definitions = eval::variable_definitions(app_context, config, name);
+                if let Some(definition) = definitions.first() {
+                    let value =
+                        eval::variable(app_context, definition.config, definition.variable)?;
+                    print_variable(name, &value, &definitions, None);
+                }
+            }
+        }
+        Some(tree) => {
+            let garden = options.garden.as_deref();
+            let ctx = query::find_tree(app_context, app_context.get_root_id(), tree, garden)?;
+            let graft_config = ctx.config.map(|graft_id| app_context.get_config(graft_id));
+            let garden_name = ctx.garden.as_ref();
+            for name in tree_variable_names(config, graft_config, &ctx.tree, garden_name) {
+                if !pattern.matches(&name) {
+                    continue;
+                }
+                let definitions = eval::tree_variable_definitions(
+                    config,
+                    graft_config,
+                    &ctx.tree,
+                    garden_name,
+                    &name,
+                );
+                if let Some(definition) = definitions.first() {
+                    let value = eval::tree_variable(
+                        app_context,
+                        config,
+                        graft_config,
+                        &ctx.tree,
+                        garden_name,
+                        definition.variable,
+                    )?;
+                    print_variable(&name, &value, &definitions, Some(&ctx.tree));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the sorted names of the variables that are visible in a tree context.
+fn tree_variable_names(
+    config: &model::Configuration,
+    graft_config: Option<&model::Configuration>,
+    tree_name: &str,
+    garden_name: Option<&model::GardenName>,
+) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for cfg in std::iter::once(config).chain(graft_config) {
+        names.extend(cfg.variables.keys().cloned());
+        if let Some(tree) = cfg.trees.get(tree_name) {
+            names.extend(tree.variables.keys().cloned());
+        }
+        if let Some(garden) = garden_name.and_then(|name| cfg.gardens.get(name)) {
+            names.extend(garden.variables.keys().cloned());
+        }
+    }
+    names.extend(config.override_variables.keys().cloned());
+    names.sort();
+    names.dedup();
+
+    names
+}
+
+/// Print a variable's value followed by the definition that was used and the
+/// definitions that it shadows.
+fn print_variable(
+    name: &str,
+    value: &str,
+    definitions: &[eval::VariableDefinition],
+    tree_name: Option<&str>,
+) {
+    println!(
+        "{} {} {}",
+        display::Color::blue(name).bold(),
+        display::Color::blue("="),
+        display::Color::green(value)
+    );
+    for (idx, definition) in definitions.iter().enumerate() {
+        let scope = if idx == 0 {
+            definition.scope.to_string()
+        } else {
+            format!("shadowed {}", definition.scope)
+        };
+        println!(
+            "  {} {} {}",
+            display::Color::blue(format!("{scope}:")),
+            display::Color::yellow(definition.variable.get_expr()),
+            display::Color::black(describe_definition(name, definition, tree_name)).bold()
+        );
+    }
+}
+
+/// Describe where a variable definition came from.
+fn describe_definition(
+    name: &str,
+    definition: &eval::VariableDefinition,
+    tree_name: Option<&str>,
+) -> String {
+    let config = definition.config;
+    if let Some(location) = config.get_sources().get(&definition.key) {
+        return format!("({} defined at {location})", definition.key);
+    }
+    if definition.scope == "override" {
+        return "(command-line)".to_string();
+    }
+    if BUILTIN_VARIABLES.contains(&name) {
+        return "(builtin)".to_string();
+    }
+    // Tree variables can be provided by templates.
+    let tree = tree_name.and_then(|tree_name| config.trees.get(tree_name));
+    if let Some(tree) = tree.filter(|_| definition.key.starts_with(constants::TREES)) {
+        for template in tree.templates.iter().rev() {
+            let key = format!(
+                "{}.{template}.{}.{name}",
+                constants::TEMPLATES,
+                constants::VARIABLES
+            );
+            if let Some(location) = config.get_sources().get(&key) {
+                return format!("({key} defined at {location})");
+            }
+        }
+    }
+    match config.describe_source(&definition.key) {
+        Some(source) => format!("({source})"),
+        None => String::new(),
+    }
+}
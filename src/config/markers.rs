@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use yaml_rust::parser::{MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+use yaml_rust::Event;
+
+/// Line numbers keyed by dotted YAML key paths, e.g. "trees.foo.url".
+/// Sequence entries use their index as the path component, e.g. "groups.core.0".
+pub(crate) type Markers = HashMap<String, usize>;
+
+/// Read the line numbers for every mapping key and sequence entry in a YAML document.
+/// Only the first document is read. Invalid YAML returns the markers read so far.
+pub(crate) fn read(string: &str) -> Markers {
+    let mut receiver = MarkerReceiver {
+        sequence_entries: true,
+        ..MarkerReceiver::default()
+    };
+    let mut parser = Parser::new(string.chars());
+    parser.load(&mut receiver, false).unwrap_or(());
+
+    receiver.markers
+}
+
+/// Read the line numbers for mapping keys only. Entries are returned in document order.
+pub(crate) fn read_keys(string: &str) -> Vec<(String, usize)> {
+    let mut receiver = MarkerReceiver::default();
+    let mut parser = Parser::new(string.chars());
+    parser.load(&mut receiver, false).unwrap_or(());
+
+    let mut keys: Vec<(String, usize)> = receiver.markers.into_iter().collect();
+    keys.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+
+    keys
+}
+
+/// Join a parent key path and a child key.
+pub(crate) fn join(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    }
+}
+
+/// Collection state tracked while receiving parser events.
+enum Frame {
+    /// The key for the next value. None when the next scalar is a key.
+    Mapping(Option<String>),
+    /// The index of the next entry.
+    Sequence(usize),
+}
+
+#[derive(Default)]
+struct MarkerReceiver {
+    markers: Markers,
+    frames: Vec<Frame>,
+    /// Key path components for the collections in "frames".
+    path: Vec<String>,
+    documents: usize,
+    /// Record sequence entries in addition to mapping keys.
+    sequence_entries: bool,
+}
+
+impl MarkerReceiver {
+    fn record(&mut self, key: &str, mark: Marker) {
+        let mut path = self.path.clone();
+        path.push(key.to_string());
+        self.markers.entry(path.join(".")).or_insert(mark.line());
+    }
+
+    /// Record the start of a value and return the path component for the value.
+    fn begin_value(&mut self, mark: Marker) -> Option<String> {
+        match self.frames.last_mut() {
+            Some(Frame::Mapping(key)) => key.take(),
+            Some(Frame::Sequence(index)) => {
+                let component = index.to_string();
+                *index += 1;
+                if self.sequence_entries {
+                    self.record(&component, mark);
+                }
+                Some(component)
+            }
+            None => None,
+        }
+    }
+}
+
+impl MarkedEventReceiver for MarkerReceiver {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        if self.documents > 1 {
+            return;
+        }
+        match event {
+            Event::DocumentStart => self.documents += 1,
+            Event::Scalar(value, ..) => {
+                if let Some(Frame::Mapping(None)) = self.frames.last() {
+                    self.record(&value, mark);
+                    self.frames.pop();
+                    self.frames.push(Frame::Mapping(Some(value)));
+                } else {
+                    self.begin_value(mark);
+                }
+            }
+            Event::Alias(_) => {
+                self.begin_value(mark);
+            }
+            Event::MappingStart(_) | Event::SequenceStart(_) => {
+                if let Some(component) = self.begin_value(mark) {
+                    self.path.push(component);
+                } else if !self.frames.is_empty() {
+                    // Complex mapping keys are not tracked.
+                    self.path.push(String::new());
+                }
+                match event {
+                    Event::MappingStart(_) => self.frames.push(Frame::Mapping(None)),
+                    _ => self.frames.push(Frame::Sequence(0)),
+                }
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.frames.pop();
+                if !self.frames.is_empty() {
+                    self.path.pop();
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use std::collections::HashSet;
 
+/// YAML source locations
+pub(crate) mod markers;
+
 /// YAML reader
 pub mod reader;
 
 /// YAML writer
 pub mod writer;
 
-use crate::{errors, model, path};
+use crate::{constants, errors, model, path};
 
 /// Search for configuration in the following locations:
 ///  .
@@ -20,7 +23,6 @@ use crate::{errors, model, path};
 ///  Traversal continues up file system until the root is reached.
 ///  GARDEN_CEILING_DIRS and GIT_CEILING_DIRS can be used to define
 ///  directories into which garden should not travrse.
-
 pub(crate) fn search_path() -> Vec<std::path::PathBuf> {
     // Result: Vec<PathBufs> in priority order
     let mut paths: Vec<std::path::PathBuf> = Vec::new();
@@ -110,7 +112,7 @@ pub fn parse(
 ) -> Result<(), errors::GardenError> {
     reader::parse(app_context, config_string, verbose, cfg)?;
     // Initialize the configuration now that the values have been read.
-    cfg.initialize(app_context);
+    cfg.initialize(app_context)?;
 
     Ok(())
 }
@@ -137,12 +139,17 @@ pub fn read_grafts_recursive(
     {
         let config = app.get_config(id); // Immutable borrow.
         for (graft_name, graft) in &config.grafts {
-            let path_str = config.eval_config_path(app, &graft.config);
+            let path_str = config.eval_config_path(app, &graft.config)?;
             let path = std::path::PathBuf::from(&path_str);
             if !path.exists() {
                 let config_path = config.get_path()?;
+                let key = format!("{}.{graft_name}", constants::GRAFTS);
+                let source = match config.describe_source(&key) {
+                    Some(source) => format!(" ({source})"),
+                    None => String::new(),
+                };
                 return Err(errors::GardenError::ConfigurationError(format!(
-                    "{}: invalid graft in {:?}",
+                    "{}: invalid graft in {:?}{source}",
                     graft.get_name(),
                     config_path
                 )));
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use indexmap::{IndexMap, IndexSet};
 use yaml_rust::{yaml, Yaml, YamlLoader};
 
+use crate::config::markers;
 use crate::{constants, errors, eval, model, syntax};
 
 // Apply YAML Configuration from a string.
@@ -22,14 +23,21 @@ fn parse_recursive(
     config: &mut model::Configuration,
     current_include: Option<&std::path::Path>,
 ) -> Result<(), errors::GardenError> {
+    // Errors in included files are reported against the included file.
     let docs =
         YamlLoader::load_from_str(string).map_err(|scan_err| errors::GardenError::ReadConfig {
             err: scan_err,
-            path: config.get_path_for_display(),
+            path: match current_include {
+                Some(path) => path.display().to_string(),
+                None => config.get_path_for_display(),
+            },
         })?;
     if docs.is_empty() {
         return Err(errors::GardenError::EmptyConfiguration {
-            path: config.get_path()?.into(),
+            path: match current_include {
+                Some(path) => path.into(),
+                None => config.get_path()?.into(),
+            },
         });
     }
     let doc = &docs[0];
@@ -114,6 +122,56 @@ fn parse_recursive(
         );
     }
 
+    // garden.env-dedupe
+    if get_bool(
+        &doc[constants::GARDEN][constants::ENV_DEDUPE],
+        &mut config.env_dedupe,
+    ) && config_verbose > 0
+    {
+        debug!(
+            "config: {} = {}",
+            constants::GARDEN_ENV_DEDUPE,
+            config.env_dedupe
+        );
+    }
+    // garden.env-separators
+    if let Yaml::Hash(hash) = &doc[constants::GARDEN][constants::ENV_SEPARATORS] {
+        for (name, separator) in hash {
+            let mut separator_str = String::new();
+            if let Some(name_str) = name.as_str() {
+                if get_str(separator, &mut separator_str) {
+                    if config_verbose > 0 {
+                        debug!(
+                            "config: {}.{} = {}",
+                            constants::GARDEN_ENV_SEPARATORS,
+                            name_str,
+                            separator_str
+                        );
+                    }
+                    config
+                        .env_separators
+                        .insert(name_str.to_string(), separator_str);
+                }
+            }
+        }
+    }
+
+    // garden.exec-timeout
+    let mut exec_timeout: i64 = 0;
+    if get_i64(
+        &doc[constants::GARDEN][constants::EXEC_TIMEOUT],
+        &mut exec_timeout,
+    ) {
+        config.exec_timeout = exec_timeout.max(0) as u64;
+        if config_verbose > 0 {
+            debug!(
+                "config: {} = {}",
+                constants::GARDEN_EXEC_TIMEOUT,
+                config.exec_timeout
+            );
+        }
+    }
+
     // GARDEN_ROOT and GARDEN_CONFIG_DIR are relative to the root configuration.
     // Referencing these variables from garden files included using garden.includes
     // resolves to the root config's location, not the included location.
@@ -175,7 +233,7 @@ fn parse_recursive(
                 app_context,
                 current_include,
                 garden_include.get_expr(),
-            ) {
+            )? {
                 Some(pathbuf) => pathbuf,
                 None => continue,
             };
@@ -225,11 +283,11 @@ fn parse_recursive(
     if config_verbose > 1 {
         debug!("config: commands");
     }
-    if !get_multivariables_hashmap(&doc[constants::COMMANDS], &mut config.commands)
-        && config_verbose > 1
-    {
+    if !get_commands(&doc[constants::COMMANDS], &mut config.commands) && config_verbose > 1 {
         debug!("config: no commands");
     }
+    get_command_specs(&doc[constants::COMMANDS], &mut config.command_specs);
+    get_command_settings(&doc[constants::COMMANDS], &mut config.command_settings);
 
     // templates
     if config_verbose > 1 {
@@ -248,7 +306,7 @@ fn parse_recursive(
     if config_verbose > 1 {
         debug!("config: trees");
     }
-    if !get_trees(app_context, config, &doc[constants::TREES]) && config_verbose > 1 {
+    if !get_trees(app_context, config, &doc[constants::TREES])? && config_verbose > 1 {
         debug!("config: no trees");
     }
 
@@ -268,9 +326,88 @@ fn parse_recursive(
         debug!("config: no gardens");
     }
 
+    // sources
+    if config_verbose > 1 {
+        debug!("config: sources");
+    }
+    get_sources(string, doc, current_include, config);
+
     Ok(())
 }
 
+/// Record the garden file so that the file and line where trees, templates,
+/// groups, gardens, grafts, variables and commands are defined can be read later.
+fn get_sources(
+    string: &str,
+    doc: &Yaml,
+    current_include: Option<&std::path::Path>,
+    config: &mut model::Configuration,
+) {
+    let path = match current_include {
+        Some(path) => path.to_path_buf(),
+        None => match &config.path {
+            Some(path) => path.clone(),
+            None => return,
+        },
+    };
+    let mut replaced_trees = Vec::new();
+    if let Yaml::Hash(trees) = &doc[constants::TREES] {
+        for (name, value) in trees {
+            if value[constants::REPLACE] != Yaml::Boolean(true) {
+                continue;
+            }
+            if let Some(name) = name.as_str() {
+                replaced_trees.push(name.to_string());
+            }
+        }
+    }
+    config.source_files.push(model::SourceFile {
+        path,
+        string: string.to_string(),
+        replaced_trees,
+    });
+}
+
+/// Read the source locations from the garden files in the order they were read.
+/// Entries from the current garden file take precedence over entries from included files.
+pub(crate) fn read_sources(
+    source_files: &[model::SourceFile],
+) -> IndexMap<String, model::SourceLocation> {
+    let sections = [
+        constants::COMMANDS,
+        constants::GARDENS,
+        constants::GRAFTS,
+        constants::GROUPS,
+        constants::TEMPLATES,
+        constants::TREES,
+        constants::VARIABLES,
+    ];
+    let mut sources = IndexMap::new();
+    for source_file in source_files {
+        // Trees that use "replace: true" discard the locations recorded from includes.
+        for name in &source_file.replaced_trees {
+            let key = format!("{}.{name}", constants::TREES);
+            let prefix = format!("{key}.");
+            sources.retain(|source_key: &String, _| {
+                source_key != &key && !source_key.starts_with(&prefix)
+            });
+        }
+        for (key, line) in markers::read_keys(&source_file.string) {
+            let section = key.split('.').next().unwrap_or_default();
+            if key.len() == section.len() || !sections.contains(&section) {
+                continue;
+            }
+            let location = model::SourceLocation {
+                path: source_file.path.clone(),
+                line,
+            };
+            sources.insert(key, location);
+        }
+    }
+
+    sources
+}
+
 fn print_indent(indent: usize) {
     for _ in 0..indent {
         print!("    ");
@@ -455,6 +592,9 @@ fn get_variables_hashmap(yaml: &Yaml, hashmap: &mut model::VariableHashMap) -> b
                             ),
                         );
                     }
+                    Yaml::Hash(_) => {
+                        hashmap.insert(key, get_variable_mapping(v));
+                    }
                     _ => {
                         dump_node(v, 1, "");
                         error!("invalid variables");
@@ -467,6 +607,45 @@ fn get_variables_hashmap(yaml: &Yaml, hashmap: &mut model::VariableHashMap) -> b
     }
 }
 
+/// Read a variable defined using a mapping with "value", "cache" and "watch" keys.
+fn get_variable_mapping(yaml: &Yaml) -> model::Variable {
+    let mut expr = String::new();
+    if !get_raw_str(&yaml[constants::VALUE], &mut expr) {
+        dump_node(yaml, 1, "");
+        error!(
+            "invalid variables: \"{}\" must be a string",
+            constants::VALUE
+        );
+    }
+    let mut variable = model::Variable::new(expr, None);
+    let duration = match &yaml[constants::CACHE] {
+        Yaml::BadValue | Yaml::Boolean(false) => return variable,
+        Yaml::Boolean(true) => None,
+        Yaml::Integer(seconds) => Some(std::time::Duration::from_secs(*seconds.max(&0) as u64)),
+        Yaml::String(value) => match syntax::parse_duration(value) {
+            Some(duration) => Some(duration),
+            None => {
+                error!(
+                    "invalid variables: \"{}\" is not a valid cache duration",
+                    value
+                );
+            }
+        },
+        value => {
+            dump_node(value, 1, "");
+            error!("invalid variables: invalid \"{}\" value", constants::CACHE);
+        }
+    };
+    let mut watch = IndexSet::new();
+    get_indexset_str(&yaml[constants::WATCH], &mut watch);
+    variable.set_cache(model::ExecCache {
+        duration,
+        watch: watch.into_iter().collect(),
+    });
+
+    variable
+}
+
 /// Read MultiVariable definitions (commands, environment)
 fn get_multivariables(yaml: &Yaml, vec: &mut Vec<model::MultiVariable>) -> bool {
     if let Yaml::Hash(hash) = yaml {
@@ -519,37 +698,64 @@ fn get_multivariables_hashmap(
                     Some(key_value) => key_value.to_string(),
                     None => continue,
                 };
-                match v {
-                    Yaml::String(yaml_str) => {
-                        let variables = vec![model::Variable::new(yaml_str.to_string(), None)];
-                        multivariables.insert(key, variables);
-                    }
-                    Yaml::Array(yaml_array) => {
+                multivariables.insert(key, get_multivariable_values(v));
+            }
+
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Read a string, list of strings, integer or boolean into a Vec of Variables
+fn get_multivariable_values(yaml: &Yaml) -> Vec<model::Variable> {
+    match yaml {
+        Yaml::String(yaml_str) => vec![model::Variable::new(yaml_str.to_string(), None)],
+        Yaml::Array(yaml_array) => {
+            let mut variables = Vec::new();
+            for value in yaml_array {
+                if let Yaml::String(yaml_str) = value {
+                    variables.push(model::Variable::new(yaml_str.clone(), None));
+                }
+            }
+            variables
+        }
+        Yaml::Integer(yaml_int) => {
+            // Integers are already resolved.
+            let value = yaml_int.to_string();
+            vec![model::Variable::new(value.clone(), Some(value))]
+        }
+        Yaml::Boolean(yaml_bool) => {
+            // Booleans are already resolved.
+            let value = syntax::bool_to_string(*yaml_bool);
+            vec![model::Variable::new(value.clone(), Some(value))]
+        }
+        _ => {
+            dump_node(yaml, 1, "");
+            error!("invalid variables");
+        }
+    }
+}
+
+/// Read commands into a MultiVariableHashMap.
+/// Commands defined using a mapping run the commands listed in "run".
+fn get_commands(yaml: &Yaml, commands: &mut model::MultiVariableHashMap) -> bool {
+    match yaml {
+        Yaml::Hash(hash) => {
+            for (k, v) in hash {
+                let key = match k.as_str() {
+                    Some(key_value) => key_value.to_string(),
+                    None => continue,
+                };
+                let variables = match v {
+                    Yaml::Hash(_) => {
                         let mut variables = Vec::new();
-                        for value in yaml_array {
-                            if let Yaml::String(yaml_str) = value {
-                                variables.push(model::Variable::new(yaml_str.clone(), None));
-                            }
-                        }
-                        multivariables.insert(key, variables);
+                        get_vec_variables(&v[constants::RUN], &mut variables);
+                        variables
                     }
-                    Yaml::Integer(yaml_int) => {
-                        // Integers are already resolved.
-                        let value = yaml_int.to_string();
-                        let variables = vec![model::Variable::new(value.clone(), Some(value))];
-                        multivariables.insert(key, variables);
-                    }
-                    Yaml::Boolean(yaml_bool) => {
-                        // Booleans are already resolved.
-                        let value = syntax::bool_to_string(*yaml_bool);
-                        let variables = vec![model::Variable::new(value.clone(), Some(value))];
-                        multivariables.insert(key, variables);
-                    }
-                    _ => {
-                        dump_node(v, 1, "");
-                        error!("invalid variables");
-                    }
-                }
+                    _ => get_multivariable_values(v),
+                };
+                commands.insert(key, variables);
             }
 
             true
@@ -558,6 +764,117 @@ fn get_multivariables_hashmap(
     }
 }
 
+/// Read the commands defined by trees, templates and gardens.
+/// Descriptions, options and arguments can only be declared by top-level commands.
+fn get_block_commands(yaml: &Yaml, commands: &mut model::MultiVariableHashMap) -> bool {
+    if let Yaml::Hash(hash) = yaml {
+        for (name, value) in hash {
+            for key in [constants::DESCRIPTION, constants::OPTIONS, constants::ARGS] {
+                if value[key] != Yaml::BadValue {
+                    dump_node(value, 1, "");
+                    error!(
+                        "invalid commands: {}: \"{}\" is only supported by top-level commands",
+                        name.as_str().unwrap_or_default(),
+                        key
+                    );
+                }
+            }
+        }
+    }
+
+    get_commands(yaml, commands)
+}
+
+/// Read the description, options and arguments for commands defined using a mapping.
+fn get_command_specs(yaml: &Yaml, specs: &mut IndexMap<String, model::CommandSpec>) {
+    let hash = match yaml {
+        Yaml::Hash(hash) => hash,
+        _ => return,
+    };
+    for (name, value) in hash {
+        let name = match name.as_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        // Commands that are redefined without a mapping no longer have a spec.
+        if !matches!(value, Yaml::Hash(_)) {
+            specs.shift_remove(&name);
+            continue;
+        }
+        let mut spec = model::CommandSpec::default();
+        get_str(&value[constants::DESCRIPTION], &mut spec.description);
+        get_command_params(&value[constants::OPTIONS], &mut spec.options);
+        get_command_params(&value[constants::ARGS], &mut spec.args);
+        specs.insert(name, spec);
+    }
+}
+
+/// Read the shell, cwd and environment settings for commands defined using a mapping.
+fn get_command_settings(yaml: &Yaml, settings: &mut model::CommandSettingsMap) {
+    let hash = match yaml {
+        Yaml::Hash(hash) => hash,
+        _ => return,
+    };
+    for (name, value) in hash {
+        let name = match name.as_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        if !matches!(value, Yaml::Hash(_)) {
+            settings.remove(&name);
+            continue;
+        }
+        let mut command_settings = model::CommandSettings::default();
+        get_str(&value[constants::SHELL], &mut command_settings.shell);
+        get_str(&value[constants::CWD], &mut command_settings.cwd);
+        get_multivariables(
+            &value[constants::ENVIRONMENT],
+            &mut command_settings.environment,
+        );
+        settings.insert(name, command_settings);
+    }
+}
+
+/// Read command options or arguments. A string value is shorthand for a description.
+fn get_command_params(yaml: &Yaml, params: &mut Vec<model::CommandParam>) {
+    let hash = match yaml {
+        Yaml::Hash(hash) => hash,
+        _ => return,
+    };
+    for (name, value) in hash {
+        let mut param = model::CommandParam {
+            name: match name.as_str() {
+                Some(name) => name.to_string(),
+                None => continue,
+            },
+            ..Default::default()
+        };
+        if !get_str(value, &mut param.description) {
+            get_str(&value[constants::DESCRIPTION], &mut param.description);
+            let mut short = String::new();
+            if get_str(&value[constants::SHORT], &mut short) {
+                param.short = short.chars().next();
+            }
+            param.default = get_scalar_str(&value[constants::DEFAULT]);
+            let mut choices = IndexSet::new();
+            get_indexset_str(&value[constants::CHOICES], &mut choices);
+            param.choices = choices.into_iter().collect();
+            get_bool(&value[constants::REQUIRED], &mut param.required);
+        }
+        params.push(param);
+    }
+}
+
+/// Yaml::String, Yaml::Integer or Yaml::Boolean -> String
+fn get_scalar_str(yaml: &Yaml) -> Option<String> {
+    match yaml {
+        Yaml::String(yaml_str) => Some(yaml_str.clone()),
+        Yaml::Integer(yaml_int) => Some(yaml_int.to_string()),
+        Yaml::Boolean(yaml_bool) => Some(syntax::bool_to_string(*yaml_bool)),
+        _ => None,
+    }
+}
+
 /// Read template definitions
 fn get_templates(
     yaml: &Yaml,
@@ -655,7 +972,7 @@ fn get_trees(
     app_context: &model::ApplicationContext,
     config: &mut model::Configuration,
     yaml: &Yaml,
-) -> bool {
+) -> Result<bool, errors::GardenError> {
     match yaml {
         Yaml::Hash(hash) => {
             for (name, value) in hash {
@@ -668,7 +985,7 @@ fn get_trees(
                         config.trees.insert(tree.get_name().to_string(), tree);
                     }
                 } else {
-                    let tree = get_tree(app_context, config, name, value, hash, true);
+                    let tree = get_tree(app_context, config, name, value, hash, true)?;
 
                     // Should we replace the current entry or sparsely override it?
                     // We sparsely override by default.
@@ -688,9 +1005,9 @@ fn get_trees(
                     }
                 }
             }
-            true
+            Ok(true)
         }
-        _ => false,
+        _ => Ok(false),
     }
 }
 
@@ -725,9 +1042,12 @@ fn get_tree_fields(value: &Yaml, tree: &mut model::Tree) {
     get_str_trimmed(&value[constants::DESCRIPTION], &mut tree.description);
     get_str_variables_hashmap(&value[constants::REMOTES], &mut tree.remotes);
     get_vec_variables(&value[constants::LINKS], &mut tree.links);
+    get_indexset_str(&value[constants::DEPENDS], &mut tree.depends);
+    get_indexset_str(&value[constants::TAGS], &mut tree.tags);
 
     get_multivariables(&value[constants::ENVIRONMENT], &mut tree.environment);
-    get_multivariables_hashmap(&value[constants::COMMANDS], &mut tree.commands);
+    get_block_commands(&value[constants::COMMANDS], &mut tree.commands);
+    get_command_settings(&value[constants::COMMANDS], &mut tree.command_settings);
 
     get_variable(&value[constants::BRANCH], &mut tree.branch);
     get_variables_hashmap(&value[constants::BRANCHES], &mut tree.branches);
@@ -760,7 +1080,7 @@ fn get_tree(
     value: &Yaml,
     trees: &yaml::Hash,
     variables: bool,
-) -> model::Tree {
+) -> Result<model::Tree, errors::GardenError> {
     // The tree that will be built and returned.
     let mut tree = model::Tree::default();
 
@@ -770,7 +1090,7 @@ fn get_tree(
         // Holds a base tree specified using "extend: <tree>".
         let tree_name = Yaml::String(extend.clone());
         if let Some(tree_values) = trees.get(&tree_name) {
-            let base_tree = get_tree(app_context, config, &tree_name, tree_values, trees, false);
+            let base_tree = get_tree(app_context, config, &tree_name, tree_values, trees, false)?;
             tree.clone_from_tree(&base_tree);
         } else {
             // Allow the referenced tree to be found from an earlier include.
@@ -784,11 +1104,11 @@ fn get_tree(
     // Load values from the parent tree when using "worktree: <parent>".
     let mut parent_expr = String::new();
     if get_str(&value[constants::WORKTREE], &mut parent_expr) {
-        let parent_name = eval::value(app_context, config, &parent_expr);
+        let parent_name = eval::value(app_context, config, &parent_expr)?;
         if !parent_expr.is_empty() {
             let tree_name = Yaml::String(parent_name);
             if let Some(tree_values) = trees.get(&tree_name) {
-                let base = get_tree(app_context, config, &tree_name, tree_values, trees, true);
+                let base = get_tree(app_context, config, &tree_name, tree_values, trees, true)?;
                 tree.clone_from_tree(&base);
             }
         }
@@ -828,7 +1148,7 @@ fn get_tree(
 
     get_tree_fields(value, &mut tree);
 
-    tree
+    Ok(tree)
 }
 
 /// Read simple string values into a garden::model::VariableHashMap.
@@ -875,7 +1195,8 @@ fn get_gardens(yaml: &Yaml, gardens: &mut IndexMap<String, model::Garden>) -> bo
                 get_multivariables_hashmap(&value[constants::GITCONFIG], &mut garden.gitconfig);
                 get_variables_hashmap(&value[constants::VARIABLES], &mut garden.variables);
                 get_multivariables(&value[constants::ENVIRONMENT], &mut garden.environment);
-                get_multivariables_hashmap(&value[constants::COMMANDS], &mut garden.commands);
+                get_block_commands(&value[constants::COMMANDS], &mut garden.commands);
+                get_command_settings(&value[constants::COMMANDS], &mut garden.command_settings);
                 gardens.insert(garden.get_name().to_string(), garden);
             }
             true
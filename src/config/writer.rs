@@ -4,13 +4,8 @@ use yaml_rust::{Yaml, YamlEmitter};
 
 use crate::errors;
 
-/// Write a Yaml object to a file
-
-pub fn write_yaml<P>(doc: &Yaml, path: P) -> Result<(), errors::GardenError>
-where
-    P: std::convert::AsRef<std::path::Path> + std::fmt::Debug,
-{
-    // Emit the YAML configuration into a string
+/// Emit a Yaml object into a string
+pub(crate) fn yaml_to_string(doc: &Yaml) -> String {
     let mut out_str = String::new();
     {
         let mut emitter = YamlEmitter::new(&mut out_str);
@@ -19,6 +14,17 @@ where
     }
     out_str += "\n";
 
+    out_str
+}
+
+/// Write a Yaml object to a file
+pub fn write_yaml<P>(doc: &Yaml, path: P) -> Result<(), errors::GardenError>
+where
+    P: std::convert::AsRef<std::path::Path> + std::fmt::Debug,
+{
+    // Emit the YAML configuration into a string
+    let out_str = yaml_to_string(doc);
+
     let mut file = std::fs::File::create(&path).map_err(|io_err| {
         errors::GardenError::CreateConfigurationError {
             path: path.as_ref().into(),
@@ -1,3 +1,6 @@
+/// The "args" key in a command mapping declares positional command-line arguments.
+pub const ARGS: &str = "args";
+
 /// The "bare" key in a tree block defines a bare repository.
 pub const BARE: &str = "bare";
 
@@ -7,36 +10,63 @@ pub const BRANCH: &str = "branch";
 /// The "branches" section in a tree block maps local to remote branches.
 pub const BRANCHES: &str = "branches";
 
+/// The "cache" key in a variable mapping caches the output of an exec expression.
+pub const CACHE: &str = "cache";
+
+/// The "choices" key in a command option or argument lists the accepted values.
+pub const CHOICES: &str = "choices";
+
 /// The "config" key in a graft definition defines the path to a garden config file.
 pub const CONFIG: &str = "config";
 
 /// The "commands" section in a configuration block defines custom commands.
 pub const COMMANDS: &str = "commands";
 
+/// The "commit" key in a "garden.lock" tree entry records the locked commit.
+pub const COMMIT: &str = "commit";
+
 /// The "cmd" debug level for "garden cmd".
 pub const DEBUG_LEVEL_CMD: &str = "cmd";
 
 /// The "config" debug level for configuration reading.
 pub const DEBUG_LEVEL_CONFIG: &str = "config";
 
-/// The "exec" debug level for "garden exec".
+/// The "exec" debug level for "garden exec" and "$ exec" expressions.
 pub const DEBUG_LEVEL_EXEC: &str = "exec";
 
 /// The "list" debug level for "garden ls".
 pub const DEBUG_LEVEL_LIST: &str = "list";
 
+/// The "timing" debug level reports the wall time spent reading configuration,
+/// evaluating exec expressions and environments, and running commands.
+pub const DEBUG_LEVEL_TIMING: &str = "timing";
+
+/// The "cwd" key in a command mapping defines the directory where the command runs.
+pub const CWD: &str = "cwd";
+
+/// The "default" key in a command option or argument provides its default value.
+pub const DEFAULT: &str = "default";
+
 /// The "default-remote" key in a tree block defines the default remote.
 pub const DEFAULT_REMOTE: &str = "default-remote";
 
+/// The "depends" key in a tree block lists the trees that must be processed
+/// before the tree when running commands.
+pub const DEPENDS: &str = "depends";
+
 /// The "depth" key in a tree block defines the git clone depth.
 pub const DEPTH: &str = "depth";
 
 /// The "description" key in a tree block describes the tree.
+/// Commands, command options and command arguments are also described using "description".
 pub const DESCRIPTION: &str = "description";
 
 /// The default "." tree query selects the tree in the current directory.
 pub(crate) const DOT: &str = ".";
 
+/// The "GARDEN_HOOK_STATE" environment variable records the environment loaded by "garden hook".
+pub(crate) const ENV_GARDEN_HOOK_STATE: &str = "GARDEN_HOOK_STATE";
+
 /// The "HOME" environment variable is used to resolve "~".
 pub(crate) const ENV_HOME: &str = "HOME";
 
@@ -50,6 +80,18 @@ pub(crate) const ENV_PWD: &str = "PWD";
 /// environment variables that are set in command environments.
 pub const ENVIRONMENT: &str = "environment";
 
+/// The "env-dedupe" key in the garden block removes duplicate entries from
+/// path-list environment variables.
+pub const ENV_DEDUPE: &str = "env-dedupe";
+
+/// The "env-separators" key in the garden block maps environment variable names
+/// to the separator used to join their entries.
+pub const ENV_SEPARATORS: &str = "env-separators";
+
+/// The "exec-timeout" key in the garden block limits the number of seconds
+/// that "$ exec" expressions are allowed to run.
+pub const EXEC_TIMEOUT: &str = "exec-timeout";
+
 /// The "extend" key in a tree or template block is used to extends an existing
 /// tree or template block.
 pub const EXTEND: &str = "extend";
@@ -77,10 +119,16 @@ pub const GARDEN_CONFIG_DIR: &str = "GARDEN_CONFIG_DIR";
 /// Variable expression for the "garden.yaml" configuration directory.
 pub const GARDEN_CONFIG_DIR_EXPR: &str = "${GARDEN_CONFIG_DIR}";
 
+/// The "garden.lock" file records the commit for each tree and sits next to the configuration.
+pub const GARDEN_LOCK: &str = "garden.lock";
+
 /// Builtin variable for the "garden.root" location where trees are grown.
 pub const GARDEN_ROOT: &str = "GARDEN_ROOT";
 
 /// Command-line defines for overriding configurable behavior.
+pub(crate) const GARDEN_ENV_DEDUPE: &str = "garden.env-dedupe";
+pub(crate) const GARDEN_ENV_SEPARATORS: &str = "garden.env-separators";
+pub(crate) const GARDEN_EXEC_TIMEOUT: &str = "garden.exec-timeout";
 pub(crate) const GARDEN_INTERACTIVE_SHELL: &str = "garden.interactive-shell";
 pub(crate) const GARDEN_SHELL: &str = "garden.shell";
 pub(crate) const GARDEN_SHELL_ERREXIT: &str = "garden.shell-errexit";
@@ -109,6 +157,9 @@ pub const INTERACTIVE_SHELL: &str = "interactive-shell";
 /// The "links" key in a tree block defines URLs displayed by "garden ls".
 pub const LINKS: &str = "links";
 
+/// The "options" key in a command mapping declares command-line options.
+pub const OPTIONS: &str = "options";
+
 /// The "origin" remote is the default Git remote name.
 pub(crate) const ORIGIN: &str = "origin";
 
@@ -127,9 +178,15 @@ pub const REMOTES: &str = "remotes";
 /// encountered.
 pub const REPLACE: &str = "replace";
 
+/// The "required" key in a command option or argument makes it mandatory.
+pub const REQUIRED: &str = "required";
+
 /// The "root" key in the garden block defines where trees are located and grown.
 pub const ROOT: &str = "root";
 
+/// The "run" key in a command mapping lists the commands to run.
+pub const RUN: &str = "run";
+
 /// The "shell" key in the garden block defines the shell to use for commands.
 pub const SHELL: &str = "shell";
 
@@ -170,6 +227,9 @@ pub(crate) const SHELL_RUBY: &str = "ruby";
 /// Extended version of the Bourne Shell with new features.
 pub(crate) const SHELL_ZSH: &str = "zsh";
 
+/// The "short" key in a command option defines its single-letter short option.
+pub const SHORT: &str = "short";
+
 /// The "single-branch" key in a tree block is used to make "garden grow"
 /// track only a single branch. Tracking branches for all remote branches
 /// are cloned and fetched by default.
@@ -178,6 +238,9 @@ pub const SINGLE_BRANCH: &str = "single-branch";
 /// The "symlink" key in a tree block creates a symlink.
 pub const SYMLINK: &str = "symlink";
 
+/// The "tags" key in a tree or template block labels trees for "#tag" tree queries.
+pub const TAGS: &str = "tags";
+
 /// The "templates" section defines tree templates that can be used when
 /// defining tree entries.
 pub const TEMPLATES: &str = "templates";
@@ -199,12 +262,19 @@ pub const TREES: &str = "trees";
 /// The "url" key in a tree block defines the "git clone" URL to clone.
 pub const URL: &str = "url";
 
+/// The "value" key in a variable mapping defines the variable's expression.
+pub const VALUE: &str = "value";
+
 /// The "variables" section in a configuration block defines expression
 /// variables that can be references using "${variable}" expressions in
 /// "environment", "commands" and "variables" blocks. Variables
 /// can use "$ exec" expressions to capture stdout from a command.
 pub const VARIABLES: &str = "variables";
 
+/// The "watch" key in a variable mapping lists files whose modification
+/// invalidates a cached exec expression.
+pub const WATCH: &str = "watch";
+
 /// The "worktree" key in a tree block is used to refer to a parent
 /// tree that will be used to grow the tree using "git worktree add".
 pub const WORKTREE: &str = "worktree";
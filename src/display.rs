@@ -1,4 +1,7 @@
-use crate::{eval, git, model};
+use indexmap::IndexMap;
+use yaml_rust::Yaml;
+
+use crate::{config, errors, eval, git, model};
 
 // Color is an alias for yansi::Paint.
 pub(crate) type Color<T> = yansi::Paint<T>;
@@ -73,6 +76,22 @@ pub(crate) fn display_tree(
     }
 }
 
+/// Return the header for a tree when it exists, otherwise return a missing tree message.
+pub(crate) fn display_tree_entry(
+    tree: &model::Tree,
+    tree_branches: bool,
+    verbose: u8,
+) -> Result<String, String> {
+    match tree.path_as_ref() {
+        // Sparse gardens/missing trees are expected. Skip these entries.
+        Ok(path) if !std::path::PathBuf::from(&path).exists() => {
+            Err(display_missing_tree(tree, path, verbose))
+        }
+        Ok(path) => Ok(display_tree(tree, path, tree_branches, verbose)),
+        Err(_) => Err(display_missing_tree(tree, "(invalid-path)", verbose)),
+    }
+}
+
 /// Print a tree if it exists, otherwise print a missing tree
 pub(crate) fn print_tree(
     tree: &model::Tree,
@@ -80,22 +99,20 @@ pub(crate) fn print_tree(
     verbose: u8,
     quiet: bool,
 ) -> bool {
-    if let Ok(path) = tree.path_as_ref() {
-        // Sparse gardens/missing trees are expected. Skip these entries.
-        if !std::path::PathBuf::from(&path).exists() {
+    match display_tree_entry(tree, tree_branches, verbose) {
+        Ok(header) => {
             if !quiet {
-                eprintln!("{}", display_missing_tree(tree, path, verbose));
+                eprintln!("{header}");
             }
-            return false;
+            true
+        }
+        Err(missing) => {
+            if !quiet {
+                eprintln!("{missing}");
+            }
+            false
         }
-
-        print_tree_details(tree, tree_branches, verbose, quiet);
-        return true;
-    } else if !quiet {
-        eprintln!("{}", display_missing_tree(tree, "(invalid-path)", verbose));
     }
-
-    false
 }
 
 /// Print a tree.
@@ -163,7 +180,7 @@ pub(crate) fn print_tree_extended_details(
     context: &model::TreeContext,
     tree: &model::Tree,
     display_worktrees: bool,
-) {
+) -> Result<(), errors::GardenError> {
     let config = match context.config {
         Some(config_id) => app_context.get_config(config_id),
         None => app_context.get_root_config(),
@@ -171,8 +188,12 @@ pub(crate) fn print_tree_extended_details(
     if !tree.description.is_empty() {
         println!("{}", Color::green(&tree.description));
     }
+    if !tree.tags.is_empty() {
+        let tags: Vec<&str> = tree.tags.iter().map(String::as_str).collect();
+        println!("{} {}", Color::blue("tags:"), Color::yellow(tags.join(" ")));
+    }
     if tree.is_worktree && !display_worktrees {
-        return;
+        return Ok(());
     }
     if !tree.remotes.is_empty() {
         println!("{}", Color::blue("remotes:"));
@@ -184,7 +205,7 @@ pub(crate) fn print_tree_extended_details(
                 &context.tree,
                 context.garden.as_ref(),
                 remote,
-            );
+            )?;
             println!(
                 "  {}{} {}",
                 Color::blue(name),
@@ -203,17 +224,54 @@ pub(crate) fn print_tree_extended_details(
                 &context.tree,
                 context.garden.as_ref(),
                 link,
-            );
+            )?;
             println!("  {} {}", Color::blue("-"), Color::yellow(value));
         }
     }
+
+    Ok(())
 }
 
 /// Print a list of commands
-pub(crate) fn print_commands(commands: &model::MultiVariableHashMap) {
+pub(crate) fn print_commands(
+    commands: &model::MultiVariableHashMap,
+    specs: &IndexMap<String, model::CommandSpec>,
+) {
     println!("{}", Color::blue("commands:"));
     for cmd in commands.keys() {
-        println!("  {} {}", Color::blue("-"), Color::yellow(cmd));
+        match specs.get(cmd).filter(|spec| !spec.description.is_empty()) {
+            Some(spec) => println!(
+                "  {} {}{} {}",
+                Color::blue("-"),
+                Color::yellow(cmd),
+                Color::blue(":"),
+                spec.description
+            ),
+            None => println!("  {} {}", Color::blue("-"), Color::yellow(cmd)),
+        }
+    }
+}
+
+/// Print the locations where an entry and its fields are defined, e.g. "trees.foo".
+pub(crate) fn print_sources(config: &model::Configuration, key: &str) {
+    let prefix = format!("{key}.");
+    let sources: Vec<_> = config
+        .get_sources()
+        .iter()
+        .filter(|(source_key, _)| *source_key == key || source_key.starts_with(&prefix))
+        .collect();
+    if sources.is_empty() {
+        return;
+    }
+    println!("{}", Color::blue("sources:"));
+    for (source_key, location) in sources {
+        println!(
+            "  {} {} {} {}",
+            Color::blue("-"),
+            Color::yellow(source_key),
+            Color::blue("defined at"),
+            Color::green(location)
+        );
     }
 }
 
@@ -245,3 +303,42 @@ pub(crate) fn print_command_string_vec(command: &[String]) {
     let str_vec: Vec<&str> = command.iter().map(String::as_str).collect();
     print_command_vec(&str_vec);
 }
+
+/// Convert a JSON value into a Yaml value.
+fn yaml_from_json(value: &serde_json::Value) -> Yaml {
+    match value {
+        serde_json::Value::Null => Yaml::Null,
+        serde_json::Value::Bool(value) => Yaml::Boolean(*value),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(value) => Yaml::Integer(value),
+            None => Yaml::Real(number.to_string()),
+        },
+        serde_json::Value::String(value) => Yaml::String(value.clone()),
+        serde_json::Value::Array(values) => {
+            Yaml::Array(values.iter().map(yaml_from_json).collect())
+        }
+        serde_json::Value::Object(map) => {
+            let mut hash = yaml_rust::yaml::Hash::new();
+            for (key, value) in map {
+                hash.insert(Yaml::String(key.clone()), yaml_from_json(value));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+/// Print structured data as JSON or YAML. Nothing is printed for the text format.
+pub(crate) fn print_structured(value: &serde_json::Value, format: &model::OutputFormat) {
+    match format {
+        model::OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(value).unwrap_or_default()
+            );
+        }
+        model::OutputFormat::Yaml => {
+            print!("{}", config::writer::yaml_to_string(&yaml_from_json(value)));
+        }
+        model::OutputFormat::Text => (),
+    }
+}
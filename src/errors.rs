@@ -24,6 +24,13 @@ pub enum GardenError {
     #[error("exit status {0}")]
     ExitStatus(i32),
 
+    #[error("{name}: \"$! {command}\" {message}")]
+    ExecError {
+        name: String,
+        command: String,
+        message: String,
+    },
+
     #[error("{0}")]
     FileExists(String),
 
@@ -48,6 +55,13 @@ pub enum GardenError {
     #[error("{0}")]
     OSError(String),
 
+    #[error("invalid tree query: {message}\n  {query}\n  {:>width$}", "^", width = .offset + 1)]
+    QuerySyntaxError {
+        query: String,
+        offset: usize,
+        message: String,
+    },
+
     #[error("unable to read {path:?}\nerror: {err}")]
     ReadConfig {
         err: yaml_rust::ScanError,
@@ -72,6 +86,9 @@ pub enum GardenError {
     #[error("invalid arguments: {0}")]
     Usage(String),
 
+    #[error("{name}: {message}")]
+    VariableError { name: String, message: String },
+
     #[error("error creating {tree:?}: 'git checkout' returned exit status {status:?}")]
     WorktreeGitCheckoutError { tree: String, status: i32 },
 
@@ -118,6 +135,7 @@ impl std::convert::From<GardenError> for i32 {
             GardenError::EmptyConfiguration { .. } => EX_CONFIG,
             GardenError::EmptyTreeQueryResult(_) => EX_DATAERR,
             GardenError::ExitStatus(status) => status, // Explicit exit code
+            GardenError::ExecError { .. } => EX_CONFIG,
             GardenError::FileExists(_) => EX_CANTCREAT,
             GardenError::FileNotFound => EX_IOERR,
             GardenError::GardenNotFound { .. } => EX_USAGE,
@@ -126,11 +144,13 @@ impl std::convert::From<GardenError> for i32 {
             GardenError::InvalidConfiguration { .. } => EX_CONFIG,
             GardenError::InvalidGardenArgument { .. } => EX_USAGE,
             GardenError::OSError(_) => EX_OSERR,
+            GardenError::QuerySyntaxError { .. } => EX_USAGE,
             GardenError::ReadConfig { .. } => EX_DATAERR,
             GardenError::ReadFile { .. } => EX_IOERR,
             GardenError::SyncConfigurationError { .. } => EX_IOERR,
             GardenError::TreeNotFound { .. } => EX_USAGE,
             GardenError::Usage(_) => EX_USAGE,
+            GardenError::VariableError { .. } => EX_CONFIG,
             GardenError::WorktreeGitCheckoutError { .. } => EX_CANTCREAT,
             GardenError::WorktreeParentCreationError { .. } => EX_CANTCREAT,
             GardenError::WorktreeParentNotPlantedError { .. } => EX_CONFIG,
@@ -1,6 +1,34 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
-use crate::{cmd, constants, model, path, query, syntax};
+use crate::{cache, cmd, constants, errors, model, path, query, syntax};
+
+thread_local! {
+    /// The names of the variables that are currently being evaluated.
+    static EVALUATING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Record a variable name for the duration of its evaluation so that
+/// exec expression errors can name the variable that failed.
+struct EvaluatingName;
+
+impl EvaluatingName {
+    fn new(name: &str) -> Self {
+        EVALUATING.with(|names| names.borrow_mut().push(name.to_string()));
+        EvaluatingName
+    }
+}
+
+impl Drop for EvaluatingName {
+    fn drop(&mut self) {
+        EVALUATING.with(|names| names.borrow_mut().pop());
+    }
+}
+
+/// Return the name of the innermost variable that is being evaluated.
+fn evaluating_name() -> Option<String> {
+    EVALUATING.with(|names| names.borrow().last().cloned())
+}
 
 /// Expand variables across all scopes (garden, tree, and global).
 /// - `app_context`: reference to the top-level ApplicationContext.
@@ -15,21 +43,23 @@ fn expand_tree_vars(
     tree_name: &str,
     garden_name: Option<&model::GardenName>,
     name: &str,
-) -> Option<String> {
+) -> Result<Option<String>, errors::GardenError> {
     // Special case $0, $1, .. $N so they can be used in commands.
     if syntax::is_digit(name) {
-        return Some(format!("${name}"));
+        return Ok(Some(format!("${name}")));
     }
+    let _evaluating = EvaluatingName::new(name);
     // Check for the variable in override scope defined by "garden -D name=value".
     if let Some(var) = config.override_variables.get(name) {
-        return Some(tree_variable(
+        return tree_variable(
             app_context,
             config,
             graft_config,
             tree_name,
             garden_name,
             var,
-        ));
+        )
+        .map(Some);
     }
 
     // Special-case evaluation of ${graft::values}.
@@ -80,14 +110,15 @@ fn expand_tree_vars(
             .and_then(|cfg| cfg.gardens.get(garden_name))
             .and_then(|garden| garden.variables.get(name))
         {
-            return Some(tree_variable(
+            return tree_variable(
                 app_context,
                 config,
                 graft_config,
                 tree_name,
                 Some(garden_name),
                 var,
-            ));
+            )
+            .map(Some);
         }
 
         // Check for the variable at the root garden scope.
@@ -96,14 +127,15 @@ fn expand_tree_vars(
             .get(garden_name)
             .and_then(|garden| garden.variables.get(name))
         {
-            return Some(tree_variable(
+            return tree_variable(
                 app_context,
                 config,
                 graft_config,
                 tree_name,
                 Some(garden_name),
                 var,
-            ));
+            )
+            .map(Some);
         }
     }
 
@@ -114,25 +146,27 @@ fn expand_tree_vars(
             .get(tree_name)
             .and_then(|tree| tree.variables.get(name))
         {
-            return Some(tree_variable(
+            return tree_variable(
                 app_context,
                 config,
                 graft_config,
                 tree_name,
                 garden_name,
                 var,
-            ));
+            )
+            .map(Some);
         }
         // Nothing was found. Check for the variable in global/config scope.
         if let Some(var) = graft_cfg.variables.get(name) {
-            return Some(tree_variable(
+            return tree_variable(
                 app_context,
                 config,
                 graft_config,
                 tree_name,
                 garden_name,
                 var,
-            ));
+            )
+            .map(Some);
         }
     }
 
@@ -142,29 +176,31 @@ fn expand_tree_vars(
         .get(tree_name)
         .and_then(|tree| tree.variables.get(name))
     {
-        return Some(tree_variable(
+        return tree_variable(
             app_context,
             config,
             graft_config,
             tree_name,
             garden_name,
             var,
-        ));
+        )
+        .map(Some);
     }
     if name == constants::TREE_NAME {
-        return Some(tree_name.to_string());
+        return Ok(Some(tree_name.to_string()));
     }
 
     // Nothing was found. Check for the variable in global/config scope.
     if let Some(var) = config.variables.get(name) {
-        return Some(tree_variable(
+        return tree_variable(
             app_context,
             config,
             graft_config,
             tree_name,
             garden_name,
             var,
-        ));
+        )
+        .map(Some);
     }
 
     // Nothing was found. Check for garden environment variables.
@@ -174,17 +210,133 @@ fn expand_tree_vars(
         garden_name.cloned(),
         None,
     );
-    if let Some(environ) = environment_value(app_context, config, graft_config, &context, name) {
-        return Some(environ);
+    if let Some(environ) = environment_value(app_context, config, graft_config, &context, name)? {
+        return Ok(Some(environ));
     }
 
     // If nothing was found then check for OS environment variables.
     if let Ok(env_value) = std::env::var(name) {
-        return Some(env_value);
+        return Ok(Some(env_value));
     }
 
     // Nothing was found -> empty value
-    Some(String::new())
+    Ok(Some(String::new()))
+}
+
+/// A variable definition that is visible when resolving a variable.
+pub struct VariableDefinition<'a> {
+    /// The scope that defines the variable, e.g. "tree" or "global".
+    pub scope: &'static str,
+    /// The configuration that defines the variable.
+    pub config: &'a model::Configuration,
+    /// The dotted key path for the definition, e.g. "trees.foo.variables.name".
+    pub key: String,
+    pub variable: &'a model::Variable,
+}
+
+/// Return the definitions for a variable in a tree context in priority order.
+/// The first definition is used and the remaining definitions are shadowed.
+/// The scopes are checked in the same order as expand_tree_vars().
+pub fn tree_variable_definitions<'a>(
+    config: &'a model::Configuration,
+    graft_config: Option<&'a model::Configuration>,
+    tree_name: &str,
+    garden_name: Option<&model::GardenName>,
+    name: &str,
+) -> Vec<VariableDefinition<'a>> {
+    let mut definitions = Vec::new();
+    let mut add = |scope, cfg: &'a model::Configuration, key: String, var| {
+        definitions.push(VariableDefinition {
+            scope,
+            config: cfg,
+            key,
+            variable: var,
+        });
+    };
+    if let Some(var) = config.override_variables.get(name) {
+        add("override", config, format!("-D {name}"), var);
+    }
+    if let Some(garden_name) = garden_name {
+        let key = format!(
+            "{}.{garden_name}.{}.{name}",
+            constants::GARDENS,
+            constants::VARIABLES
+        );
+        if let Some(graft_cfg) = graft_config {
+            if let Some(var) = graft_cfg
+                .gardens
+                .get(garden_name)
+                .and_then(|garden| garden.variables.get(name))
+            {
+                add("graft garden", graft_cfg, key.clone(), var);
+            }
+        }
+        if let Some(var) = config
+            .gardens
+            .get(garden_name)
+            .and_then(|garden| garden.variables.get(name))
+        {
+            add("garden", config, key, var);
+        }
+    }
+    let tree_key = format!(
+        "{}.{tree_name}.{}.{name}",
+        constants::TREES,
+        constants::VARIABLES
+    );
+    let global_key = format!("{}.{name}", constants::VARIABLES);
+    if let Some(graft_cfg) = graft_config {
+        if let Some(var) = graft_cfg
+            .trees
+            .get(tree_name)
+            .and_then(|tree| tree.variables.get(name))
+        {
+            add("graft tree", graft_cfg, tree_key.clone(), var);
+        }
+        if let Some(var) = graft_cfg.variables.get(name) {
+            add("graft global", graft_cfg, global_key.clone(), var);
+        }
+    }
+    if let Some(var) = config
+        .trees
+        .get(tree_name)
+        .and_then(|tree| tree.variables.get(name))
+    {
+        add("tree", config, tree_key, var);
+    }
+    if let Some(var) = config.variables.get(name) {
+        add("global", config, global_key, var);
+    }
+
+    definitions
+}
+
+/// Return the definitions for a variable in the global scope in priority order.
+/// The scopes are checked in the same order as expand_vars().
+pub fn variable_definitions<'a>(
+    app_context: &'a model::ApplicationContext,
+    config: &'a model::Configuration,
+    name: &str,
+) -> Vec<VariableDefinition<'a>> {
+    let mut definitions = Vec::new();
+    let key = format!("{}.{name}", constants::VARIABLES);
+    let mut current = Some(config);
+    let mut scope = "global";
+    while let Some(cfg) = current {
+        if let Some(var) = cfg.variables.get(name) {
+            definitions.push(VariableDefinition {
+                scope,
+                config: cfg,
+                key: key.clone(),
+                variable: var,
+            });
+        }
+        // Variables defined by graft parents are used when not defined by the graft.
+        current = cfg.parent_id.map(|id| app_context.get_config(id));
+        scope = "graft parent";
+    }
+
+    definitions
 }
 
 /// Expand variables at global scope only
@@ -192,23 +344,24 @@ fn expand_vars(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
     name: &str,
-) -> Option<String> {
+) -> Result<Option<String>, errors::GardenError> {
     // Special case $0, $1, .. $N so they can be used in commands.
     if syntax::is_digit(name) {
-        return Some(format!("${name}"));
+        return Ok(Some(format!("${name}")));
     }
+    let _evaluating = EvaluatingName::new(name);
 
     if syntax::is_graft(name) {
         let (graft_id, remainder) = match config.get_graft_id(name) {
             Ok((graft_id, remainder)) => (graft_id, remainder),
-            Err(_) => return Some(String::new()),
+            Err(_) => return Ok(Some(String::new())),
         };
         return expand_graft_vars(app_context, graft_id, remainder);
     }
 
     // Check for the variable in the current configuration's global scope.
     if let Some(var) = config.variables.get(name) {
-        return Some(variable(app_context, config, var));
+        return variable(app_context, config, var).map(Some);
     }
 
     // Walk up the parent hierarchy to resolve variables defined by graft parents.
@@ -219,11 +372,11 @@ fn expand_vars(
 
     // If nothing was found then check for environment variables.
     if let Ok(env_value) = std::env::var(name) {
-        return Some(env_value);
+        return Ok(Some(env_value));
     }
 
     // Nothing was found -> empty value
-    Some(String::new())
+    Ok(Some(String::new()))
 }
 
 /// Expand graft variables of the form "graft::name".
@@ -231,11 +384,11 @@ fn expand_graft_vars(
     app_context: &model::ApplicationContext,
     graft_id: model::ConfigId,
     name: &str,
-) -> Option<String> {
+) -> Result<Option<String>, errors::GardenError> {
     if syntax::is_graft(name) {
         let (graft_id, remainder) = match app_context.get_config(graft_id).get_graft_id(name) {
             Ok((graft_id, remainder)) => (graft_id, remainder),
-            Err(_) => return Some(String::new()),
+            Err(_) => return Ok(Some(String::new())),
         };
         return expand_graft_vars(app_context, graft_id, remainder);
     }
@@ -243,6 +396,209 @@ fn expand_graft_vars(
     expand_vars(app_context, app_context.get_config(graft_id), name)
 }
 
+/// Expand ${name<modifier>} expressions, e.g. ${name:-default}, ${name#prefix} and
+/// ${name|upper}. Plain ${name} expressions are left as-is for shellexpand and the
+/// values produced by modifiers are escaped so that shellexpand does not expand them again.
+fn expand_modifiers<'a, F>(
+    expr: &'a str,
+    lookup: &mut F,
+) -> Result<std::borrow::Cow<'a, str>, errors::GardenError>
+where
+    F: FnMut(&str) -> Result<Option<String>, errors::GardenError>,
+{
+    let mut result = String::new();
+    // Everything before "copied" has been copied into the result.
+    let mut copied = 0;
+    let mut index = 0;
+    while let Some(offset) = expr[index..].find('$') {
+        let start = index + offset;
+        let remainder = &expr[start..];
+        if remainder.starts_with("$$") {
+            index = start + 2;
+            continue;
+        }
+        if !remainder.starts_with("${") {
+            index = start + 1;
+            continue;
+        }
+        let end = match syntax::find_variable_end(expr, start) {
+            Some(end) => end,
+            None => break,
+        };
+        index = end + 1;
+        let (name, modifier) = syntax::split_variable_modifier(&expr[start + 2..end]);
+        if modifier.is_empty() {
+            continue;
+        }
+        let value = lookup(name)?.unwrap_or_default();
+        result.push_str(&expr[copied..start]);
+        result.push_str(&apply_modifier(name, &value, modifier, lookup)?);
+        copied = index;
+    }
+    if copied == 0 {
+        return Ok(std::borrow::Cow::Borrowed(expr));
+    }
+    result.push_str(&expr[copied..]);
+
+    Ok(std::borrow::Cow::Owned(result))
+}
+
+/// Apply a modifier to a variable's value. The result is escaped for shellexpand.
+fn apply_modifier<F>(
+    name: &str,
+    value: &str,
+    modifier: &str,
+    lookup: &mut F,
+) -> Result<String, errors::GardenError>
+where
+    F: FnMut(&str) -> Result<Option<String>, errors::GardenError>,
+{
+    let escape = |value: &str| value.replace('$', "$$");
+    if let Some(default) = modifier.strip_prefix(":-") {
+        // The default value is an expression that is expanded by the caller.
+        if value.is_empty() {
+            return Ok(expand_modifiers(default, lookup)?.to_string());
+        }
+        return Ok(escape(value));
+    }
+    if let Some(message) = modifier.strip_prefix(":?") {
+        if value.is_empty() {
+            let message = if message.is_empty() {
+                "parameter null or not set"
+            } else {
+                message
+            };
+            return Err(errors::GardenError::VariableError {
+                name: name.to_string(),
+                message: message.to_string(),
+            });
+        }
+        return Ok(escape(value));
+    }
+    if let Some(pattern) = modifier.strip_prefix("##") {
+        return Ok(escape(strip_prefix_pattern(value, pattern, true)));
+    }
+    if let Some(pattern) = modifier.strip_prefix('#') {
+        return Ok(escape(strip_prefix_pattern(value, pattern, false)));
+    }
+    if let Some(pattern) = modifier.strip_prefix("%%") {
+        return Ok(escape(strip_suffix_pattern(value, pattern, true)));
+    }
+    if let Some(pattern) = modifier.strip_prefix('%') {
+        return Ok(escape(strip_suffix_pattern(value, pattern, false)));
+    }
+    if let Some(replacement) = modifier.strip_prefix("//") {
+        let (pattern, replacement) = replacement.split_once('/').unwrap_or((replacement, ""));
+        return Ok(escape(&replace_pattern(value, pattern, replacement, true)));
+    }
+    if let Some(replacement) = modifier.strip_prefix('/') {
+        let (pattern, replacement) = replacement.split_once('/').unwrap_or((replacement, ""));
+        return Ok(escape(&replace_pattern(value, pattern, replacement, false)));
+    }
+    let mut result = value.to_string();
+    for filter in modifier.split('|').skip(1) {
+        result = match filter.trim() {
+            "upper" => result.to_uppercase(),
+            "lower" => result.to_lowercase(),
+            "basename" => std::path::Path::new(&result)
+                .file_name()
+                .map(|basename| basename.to_string_lossy().to_string())
+                .unwrap_or(result),
+            "dirname" => match std::path::Path::new(&result).parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => {
+                    parent.to_string_lossy().to_string()
+                }
+                Some(_) => constants::DOT.to_string(),
+                None => result,
+            },
+            _ => {
+                return Err(errors::GardenError::VariableError {
+                    name: name.to_string(),
+                    message: format!("unknown filter: {filter}"),
+                });
+            }
+        };
+    }
+
+    Ok(escape(&result))
+}
+
+/// Return a glob pattern. Invalid patterns are matched literally.
+fn glob_pattern(pattern: &str) -> glob::Pattern {
+    glob::Pattern::new(pattern)
+        .unwrap_or_else(|_| glob::Pattern::new(&glob::Pattern::escape(pattern)).unwrap_or_default())
+}
+
+/// Remove the shortest or longest prefix that matches a glob pattern.
+fn strip_prefix_pattern<'a>(value: &'a str, pattern: &str, longest: bool) -> &'a str {
+    let pattern = glob_pattern(pattern);
+    let mut ends: Vec<usize> = value.char_indices().map(|(idx, _)| idx).skip(1).collect();
+    ends.push(value.len());
+    if longest {
+        ends.reverse();
+    }
+    for end in ends {
+        if pattern.matches(&value[..end]) {
+            return &value[end..];
+        }
+    }
+
+    value
+}
+
+/// Remove the shortest or longest suffix that matches a glob pattern.
+fn strip_suffix_pattern<'a>(value: &'a str, pattern: &str, longest: bool) -> &'a str {
+    let pattern = glob_pattern(pattern);
+    let mut starts: Vec<usize> = value.char_indices().map(|(idx, _)| idx).collect();
+    if !longest {
+        starts.reverse();
+    }
+    for start in starts {
+        if pattern.matches(&value[start..]) {
+            return &value[..start];
+        }
+    }
+
+    value
+}
+
+/// Replace the first or every longest match of a glob pattern.
+fn replace_pattern(value: &str, pattern: &str, replacement: &str, all: bool) -> String {
+    if pattern.is_empty() {
+        return value.to_string();
+    }
+    let pattern = glob_pattern(pattern);
+    let mut boundaries: Vec<usize> = value.char_indices().map(|(idx, _)| idx).collect();
+    boundaries.push(value.len());
+
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < boundaries.len() {
+        let start = boundaries[idx];
+        let found = boundaries[idx + 1..]
+            .iter()
+            .rev()
+            .position(|end| pattern.matches(&value[start..*end]))
+            .map(|position| boundaries.len() - 1 - position);
+        match found {
+            Some(end_idx) => {
+                result.push_str(&value[copied..start]);
+                result.push_str(replacement);
+                copied = boundaries[end_idx];
+                if !all {
+                    break;
+                }
+                idx = end_idx;
+            }
+            None => idx += 1,
+        }
+    }
+    result.push_str(&value[copied..]);
+
+    result
+}
+
 /// Resolve ~ to the current user's home directory
 fn home_dir() -> Option<String> {
     // Honor $HOME when set in the environment.
@@ -260,7 +616,29 @@ pub fn tree_value(
     expr: &str,
     tree_name: &str,
     garden_name: Option<&model::GardenName>,
-) -> String {
+) -> Result<String, errors::GardenError> {
+    tree_expression(
+        app_context,
+        config,
+        graft_config,
+        expr,
+        tree_name,
+        garden_name,
+        None,
+    )
+}
+
+/// Resolve an expression in a garden/tree/global scope. Exec expressions
+/// are cached on disk when cache settings are provided.
+fn tree_expression(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    graft_config: Option<&model::Configuration>,
+    expr: &str,
+    tree_name: &str,
+    garden_name: Option<&model::GardenName>,
+    cache: Option<&model::ExecCache>,
+) -> Result<String, errors::GardenError> {
     let is_exec = syntax::is_exec(expr);
     let escaped_value;
     let escaped_expr = if is_exec {
@@ -269,10 +647,12 @@ pub fn tree_value(
     } else {
         expr
     };
-    let expanded = shellexpand::full_with_context_no_errors(escaped_expr, home_dir, |x| {
-        expand_tree_vars(app_context, config, graft_config, tree_name, garden_name, x)
-    })
-    .to_string();
+    let mut lookup =
+        |x: &str| expand_tree_vars(app_context, config, graft_config, tree_name, garden_name, x);
+    let modified_expr = expand_modifiers(escaped_expr, &mut lookup)?;
+    let expanded = shellexpand::full_with_context(modified_expr.as_ref(), home_dir, lookup)
+        .map_err(|err| err.cause)?
+        .to_string();
 
     // NOTE: an environment must not be calculated here otherwise any
     // exec expression will implicitly depend on the entire environment,
@@ -280,9 +660,9 @@ pub fn tree_value(
     // always use the default environment.
     if is_exec {
         let pathbuf = config.get_tree_pathbuf(tree_name);
-        exec_expression(&expanded, pathbuf)
+        exec_expression(app_context, config, &expanded, pathbuf, cache)
     } else {
-        expanded
+        Ok(expanded)
     }
 }
 
@@ -294,14 +674,15 @@ fn tree_value_for_shell(
     expr: &str,
     tree_name: &model::TreeName,
     garden_name: Option<&model::GardenName>,
-) -> String {
+) -> Result<String, errors::GardenError> {
     let is_exec = syntax::is_exec(expr);
-    let expanded = shellexpand::full_with_context_no_errors(
-        &syntax::escape_shell_variables(expr),
-        home_dir,
-        |x| expand_tree_vars(app_context, config, None, tree_name, garden_name, x),
-    )
-    .to_string();
+    let mut lookup =
+        |x: &str| expand_tree_vars(app_context, config, None, tree_name, garden_name, x);
+    let escaped_expr = syntax::escape_shell_variables(expr);
+    let modified_expr = expand_modifiers(&escaped_expr, &mut lookup)?;
+    let expanded = shellexpand::full_with_context(modified_expr.as_ref(), home_dir, lookup)
+        .map_err(|err| err.cause)?
+        .to_string();
 
     // NOTE: an environment must not be calculated here otherwise any
     // exec expression will implicitly depend on the entire environment,
@@ -309,9 +690,9 @@ fn tree_value_for_shell(
     // always use the default environment.
     if is_exec {
         let pathbuf = config.get_tree_pathbuf(tree_name);
-        exec_expression(&expanded, pathbuf)
+        exec_expression(app_context, config, &expanded, pathbuf, None)
     } else {
-        expanded
+        Ok(expanded)
     }
 }
 
@@ -320,7 +701,18 @@ pub fn value(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
     expr: &str,
-) -> String {
+) -> Result<String, errors::GardenError> {
+    expression(app_context, config, expr, None)
+}
+
+/// Resolve an expression in configuration/global scope. Exec expressions
+/// are cached on disk when cache settings are provided.
+fn expression(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    expr: &str,
+    cache: Option<&model::ExecCache>,
+) -> Result<String, errors::GardenError> {
     let is_exec = syntax::is_exec(expr);
     let escaped_value;
     let escaped_expr = if is_exec {
@@ -329,22 +721,45 @@ pub fn value(
     } else {
         expr
     };
-    let expanded = shellexpand::full_with_context_no_errors(escaped_expr, home_dir, |x| {
-        expand_vars(app_context, config, x)
-    })
-    .to_string();
+    let mut lookup = |x: &str| expand_vars(app_context, config, x);
+    let modified_expr = expand_modifiers(escaped_expr, &mut lookup)?;
+    let expanded = shellexpand::full_with_context(modified_expr.as_ref(), home_dir, lookup)
+        .map_err(|err| err.cause)?
+        .to_string();
 
     if is_exec {
-        exec_expression(&expanded, None)
+        exec_expression(app_context, config, &expanded, None, cache)
     } else {
-        expanded
+        Ok(expanded)
     }
 }
 
 /// Evaluate `$ <command>` command strings, AKA "exec expressions".
 /// The result of the expression is the stdout output from the command.
-fn exec_expression(string: &str, pathbuf: Option<std::path::PathBuf>) -> String {
+/// Failing `$! <command>` expressions return an `ExecError`.
+fn exec_expression(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    string: &str,
+    pathbuf: Option<std::path::PathBuf>,
+    cache: Option<&model::ExecCache>,
+) -> Result<String, errors::GardenError> {
+    let start = std::time::Instant::now();
     let cmd = syntax::trim_exec(string);
+    let exec_verbose = app_context.options.debug_level(constants::DEBUG_LEVEL_EXEC);
+    // Cached values are keyed by the command, the directory it runs in and the watched files.
+    let cache_key = cache.map(|cache| {
+        let dir = pathbuf.clone().unwrap_or_else(path::current_dir);
+        cache::exec_key(cmd, &dir, &cache.watch)
+    });
+    if let (Some(cache), Some(key)) = (cache, cache_key.as_ref()) {
+        if let Some(output) = cache::read(key, cache.duration) {
+            if exec_verbose > 0 {
+                debug!("exec: $ {}: cached", cmd);
+            }
+            return Ok(output);
+        }
+    }
     let mut proc = subprocess::Exec::shell(cmd);
     // Run the exec expression inside the tree's directory when specified.
     if let Some(pathbuf) = pathbuf {
@@ -353,8 +768,69 @@ fn exec_expression(string: &str, pathbuf: Option<std::path::PathBuf>) -> String
         // Set $PWD to ensure that commands that are sensitive to it see the right value.
         proc = proc.env(constants::ENV_PWD, pathbuf.to_str().unwrap_or(&current_dir));
     }
+    let timeout = match config.exec_timeout {
+        0 => None,
+        seconds => Some(std::time::Duration::from_secs(seconds)),
+    };
+
+    let (output, failure) = match cmd::capture_with_timeout(proc, timeout) {
+        Ok(capture) => {
+            let outcome = match capture.status {
+                Some(status) => format!("exited with status {status}"),
+                None => format!("timed out after {}s", config.exec_timeout),
+            };
+            if exec_verbose > 0 {
+                debug!("exec: $ {}: {}", cmd, outcome);
+            }
+            if exec_verbose > 1 {
+                for line in capture.stderr.lines() {
+                    debug!("exec: stderr: {}", line);
+                }
+            }
+            if capture.status == Some(errors::EX_OK) {
+                (capture.stdout, None)
+            } else if capture.stderr.is_empty() {
+                (String::new(), Some(outcome))
+            } else {
+                (
+                    String::new(),
+                    Some(format!("{outcome}:\n{}", capture.stderr)),
+                )
+            }
+        }
+        Err(err) => {
+            if exec_verbose > 0 {
+                debug!("exec: $ {}: {}", cmd, err);
+            }
+            (String::new(), Some(format!("could not be run: {err}")))
+        }
+    };
+    if app_context
+        .options
+        .debug_level(constants::DEBUG_LEVEL_TIMING)
+        > 0
+    {
+        debug!("timing: exec: {:.3?}: $ {}", start.elapsed(), cmd);
+    }
+
+    match failure {
+        Some(message) if syntax::is_required_exec(string) => {
+            return Err(errors::GardenError::ExecError {
+                name: evaluating_name().unwrap_or_else(|| string!("exec expression")),
+                command: cmd.to_string(),
+                message,
+            });
+        }
+        Some(_) => (),
+        // Only successful results are cached.
+        None => {
+            if let Some(key) = cache_key {
+                cache::write(&key, &output);
+            }
+        }
+    }
 
-    cmd::stdout_to_string(proc).unwrap_or_default()
+    Ok(output)
 }
 
 /// Evaluate a variable in the given context
@@ -364,7 +840,7 @@ pub fn multi_variable(
     graft_config: Option<&model::Configuration>,
     multi_var: &mut model::MultiVariable,
     context: &model::TreeContext,
-) -> Vec<String> {
+) -> Result<Vec<String>, errors::GardenError> {
     let mut result = Vec::new();
     for var in multi_var.iter() {
         let value = tree_variable(
@@ -374,11 +850,11 @@ pub fn multi_variable(
             &context.tree,
             context.garden.as_ref(),
             var,
-        );
+        )?;
         result.push(value.to_string());
     }
 
-    result
+    Ok(result)
 }
 
 /// Evaluate a variable in the given context for execution in a shell
@@ -387,7 +863,7 @@ pub(crate) fn variables_for_shell(
     config: &model::Configuration,
     variables: &mut Vec<model::Variable>,
     context: &model::TreeContext,
-) -> Vec<String> {
+) -> Result<Vec<String>, errors::GardenError> {
     let mut result = Vec::new();
 
     for var in variables {
@@ -401,13 +877,13 @@ pub(crate) fn variables_for_shell(
             var.get_expr(),
             &context.tree,
             context.garden.as_ref(),
-        );
+        )?;
         result.push(value.clone());
 
         var.set_value(value);
     }
 
-    result
+    Ok(result)
 }
 
 /// Evaluate environments
@@ -415,8 +891,8 @@ pub fn environment(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
     context: &model::TreeContext,
-) -> Vec<(String, String)> {
-    let mut result = Vec::new();
+) -> Result<Vec<(String, String)>, errors::GardenError> {
+    let start = std::time::Instant::now();
     let mut vars = Vec::new();
 
     // Evaluate environment variables defined at global scope.
@@ -444,7 +920,7 @@ pub fn environment(
             }
 
             for var in &garden.environment {
-                vars.push((context.clone(), &var));
+                vars.push((context.clone(), var));
             }
             ready = true;
         }
@@ -480,11 +956,38 @@ pub fn environment(
         }
     }
 
+    let mut values: HashMap<String, String> = HashMap::new();
+    let result = evaluate_environment(app_context, config, &vars, &mut values)?;
+
+    if app_context
+        .options
+        .debug_level(constants::DEBUG_LEVEL_TIMING)
+        > 0
+    {
+        debug!(
+            "timing: environment: {:.3?}: {}",
+            start.elapsed(),
+            context.tree
+        );
+    }
+
+    Ok(result)
+}
+
+/// Evaluate environment variables and apply their operations on top of the
+/// current values. Returns the (name, value) pairs in evaluation order.
+fn evaluate_environment(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    vars: &[(model::TreeContext, &model::MultiVariable)],
+    values: &mut HashMap<String, String>,
+) -> Result<Vec<(String, String)>, errors::GardenError> {
+    let mut result = Vec::new();
     let mut var_values = Vec::new();
-    for (ctx, var) in vars.iter_mut() {
-        let mut cloned_var = var.clone();
+    for (ctx, var) in vars {
+        let mut cloned_var = (*var).clone();
         let graft_config = ctx.config.map(|id| app_context.get_config(id));
-        let values = multi_variable(app_context, config, graft_config, &mut cloned_var, ctx);
+        let values = multi_variable(app_context, config, graft_config, &mut cloned_var, ctx)?;
         var_values.push((
             tree_value(
                 app_context,
@@ -493,7 +996,7 @@ pub fn environment(
                 var.get_name(),
                 ctx.tree.as_str(),
                 ctx.garden.as_ref(),
-            ),
+            )?,
             values,
         ));
     }
@@ -504,83 +1007,96 @@ pub fn environment(
     // in values; if not, check the environment and bootstrap values.
     // If still nothing, initialize it with the value and update the
     // values hashmap.
-    let mut values: HashMap<String, String> = HashMap::new();
-
     for (var_name, env_values) in &var_values {
         let mut name = var_name.clone();
-        let mut is_assign = false;
-        let mut is_append = false;
-
-        if syntax::is_replace_op(&name) {
-            is_assign = true;
-        }
-
-        if syntax::is_append_op(&name) {
-            is_append = true;
-        }
-
-        if is_assign || is_append {
-            syntax::trim_op_inplace(&mut name);
-        }
+        let op = EnvironmentOp::from_name(&mut name);
 
         for value in env_values {
-            let mut current = String::new();
-            let mut exists = false;
-            if let Some(map_value) = values.get(&name) {
-                // Use the existing value
-                current = map_value.clone();
-                exists = true;
+            // Use the existing value or bootstrap it from the environment.
+            // Empty values are treated as not existing to prevent ":foo" or
+            // "foo:" in the final result.
+            let current = values
+                .get(&name)
+                .cloned()
+                .or_else(|| std::env::var(&name).ok().filter(|env| !env.is_empty()));
+            if let Some(path_value) =
+                apply_environment_op(config, &name, op, current.as_deref(), value)
+            {
+                values.insert(name.clone(), path_value.clone());
+                result.push((name.clone(), path_value));
             }
-            if !exists {
-                // Not found, try to get the current value from the environment
-                let mut has_env = false;
-                if let Ok(env_value) = std::env::var(&name) {
-                    let env_str: String = env_value;
-                    // Empty values are treated as not existing to prevent ":foo" or
-                    // "foo:" in the final result.
-                    if !env_str.is_empty() {
-                        current = env_str;
-                        has_env = true;
-                    }
-                }
+        }
+    }
 
-                if has_env && !is_assign {
-                    values.insert(name.clone(), current.clone());
-                } else {
-                    // Either no environment value or an assignment will
-                    // create the value if it's never been seen.
-                    values.insert(name.clone(), value.clone());
-                    result.push((name.clone(), value.clone()));
-                    continue;
-                }
-            }
+    Ok(result)
+}
 
-            // If it's an assignment, replace the value.
-            if is_assign {
-                values.insert(name.clone(), value.clone());
-                result.push((name.clone(), value.clone()));
-                continue;
-            }
+/// Operations performed by the names used in "environment" blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EnvironmentOp {
+    /// "NAME" and "NAME^" prepend an entry.
+    Prepend,
+    /// "NAME+" appends an entry.
+    Append,
+    /// "NAME-" removes an entry.
+    Remove,
+    /// "NAME=" replaces the value.
+    Replace,
+}
 
-            // Append/prepend the value.
-            let mut path_values: Vec<String> = Vec::new();
-            if !is_append {
-                path_values.push(value.clone());
-            }
-            for path in current.split(':') {
-                path_values.push(path.into());
-            }
-            if is_append {
-                path_values.push(value.clone());
-            }
+impl EnvironmentOp {
+    /// Return the operation for an environment name and trim its suffix in-place.
+    fn from_name(name: &mut String) -> Self {
+        let op = if syntax::is_replace_op(name) {
+            EnvironmentOp::Replace
+        } else if syntax::is_append_op(name) {
+            EnvironmentOp::Append
+        } else if syntax::is_prepend_op(name) {
+            EnvironmentOp::Prepend
+        } else if syntax::is_remove_op(name) {
+            EnvironmentOp::Remove
+        } else {
+            return EnvironmentOp::Prepend;
+        };
+        syntax::trim_op_inplace(name);
 
-            let path_value = path_values.join(":");
-            values.insert(name.clone(), path_value.clone());
-            result.push((name.clone(), path_value));
-        }
+        op
     }
+}
 
-    result
+/// Apply an environment operation to the current value of a variable.
+/// Returns None when removing an entry from a variable that does not exist.
+fn apply_environment_op(
+    config: &model::Configuration,
+    name: &str,
+    op: EnvironmentOp,
+    current: Option<&str>,
+    value: &str,
+) -> Option<String> {
+    let current = match (op, current) {
+        (EnvironmentOp::Replace, _) => return Some(value.to_string()),
+        (EnvironmentOp::Remove, None) => return None,
+        (_, None) => return Some(value.to_string()),
+        (_, Some(current)) => current,
+    };
+    let separator = config.env_separator(name);
+    let mut entries: Vec<&str> = current
+        .split(separator)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    match op {
+        EnvironmentOp::Prepend => entries.insert(0, value),
+        EnvironmentOp::Append => entries.push(value),
+        EnvironmentOp::Remove => entries.retain(|entry| *entry != value),
+        EnvironmentOp::Replace => (),
+    }
+    // Keep the first occurrence of each entry.
+    if config.env_dedupe {
+        let mut seen = HashSet::new();
+        entries.retain(|entry| seen.insert(*entry));
+    }
+
+    Some(entries.join(separator))
 }
 
 /// Return a vector of references to variables that reference the specified names.
@@ -591,7 +1107,7 @@ fn environment_value_vars<'a>(
     context: &model::TreeContext,
     names: &[String],
     variables: &'a Vec<model::MultiVariable>,
-) -> Vec<(model::TreeContext, String, &'a model::MultiVariable)> {
+) -> Result<Vec<(model::TreeContext, String, &'a model::MultiVariable)>, errors::GardenError> {
     let mut vars = Vec::with_capacity(variables.len());
     for var in variables {
         if names.contains(var.get_name()) {
@@ -606,14 +1122,14 @@ fn environment_value_vars<'a>(
                 var.get_name(),
                 &context.tree,
                 context.garden.as_ref(),
-            );
+            )?;
             if names.contains(&name_value) {
                 vars.push((context.clone(), name_value, var));
             }
         }
     }
 
-    vars
+    Ok(vars)
 }
 
 /// Evaluate a single environment variable value.
@@ -623,12 +1139,15 @@ fn environment_value(
     graft_config: Option<&model::Configuration>,
     context: &model::TreeContext,
     name: &str,
-) -> Option<String> {
+) -> Result<Option<String>, errors::GardenError> {
     let mut vars = Vec::new();
-    let name_prepend = name.to_string();
-    let name_append = format!("{name}+");
-    let name_replace = format!("{name}=");
-    let names = vec![name_prepend, name_append, name_replace];
+    let names = vec![
+        name.to_string(),
+        format!("{name}^"),
+        format!("{name}+"),
+        format!("{name}-"),
+        format!("{name}="),
+    ];
 
     // Evaluate environment variables defined at global scope.
     vars.append(&mut environment_value_vars(
@@ -638,7 +1157,7 @@ fn environment_value(
         context,
         &names,
         &config.environment,
-    ));
+    )?);
 
     if let Some(graft_cfg) = graft_config {
         vars.append(&mut environment_value_vars(
@@ -648,7 +1167,7 @@ fn environment_value(
             context,
             &names,
             &graft_cfg.environment,
-        ));
+        )?);
     }
 
     // Evaluate garden environments.
@@ -672,7 +1191,7 @@ fn environment_value(
                         &ctx,
                         &names,
                         &tree.environment,
-                    ));
+                    )?);
                 } else if let Some(tree) = config.trees.get(&ctx.tree) {
                     vars.append(&mut environment_value_vars(
                         app_context,
@@ -681,7 +1200,7 @@ fn environment_value(
                         &ctx,
                         &names,
                         &tree.environment,
-                    ));
+                    )?);
                 }
             }
             // Garden environment variables prepend over tree environment variables.
@@ -692,7 +1211,7 @@ fn environment_value(
                 context,
                 &names,
                 &garden.environment,
-            ));
+            )?);
             ready = true;
         }
     } else if let Some(group_name) = context.group.as_ref() {
@@ -710,7 +1229,7 @@ fn environment_value(
                             &ctx,
                             &names,
                             &tree.environment,
-                        ));
+                        )?);
                         ready = true;
                     }
                 } else if let Some(tree) = config.trees.get(&ctx.tree) {
@@ -721,7 +1240,7 @@ fn environment_value(
                         &ctx,
                         &names,
                         &tree.environment,
-                    ));
+                    )?);
                     ready = true;
                 }
             }
@@ -741,7 +1260,7 @@ fn environment_value(
                     context,
                     &names,
                     &single_tree.environment,
-                ));
+                )?);
             }
         } else if let Some(tree) = config.trees.get(&context.tree) {
             single_tree = tree;
@@ -752,7 +1271,7 @@ fn environment_value(
                 context,
                 &names,
                 &single_tree.environment,
-            ));
+            )?);
         }
     }
 
@@ -765,7 +1284,7 @@ fn environment_value(
             graft_config.or(ctx.config.map(|id| app_context.get_config(id))),
             &mut cloned_var,
             ctx,
-        );
+        )?;
         var_values.push((name_value, values));
     }
 
@@ -779,80 +1298,22 @@ fn environment_value(
 
     for (var_name, env_values) in var_values {
         let mut real_name = var_name.clone();
-        let mut is_assign = false;
-        let mut is_append = false;
-
-        if syntax::is_replace_op(var_name) {
-            is_assign = true;
-        }
-
-        if syntax::is_append_op(var_name) {
-            is_append = true;
-        }
-
-        if is_assign || is_append {
-            syntax::trim_op_inplace(&mut real_name);
-        }
+        let op = EnvironmentOp::from_name(&mut real_name);
 
         for value in env_values {
-            let mut current = String::new();
-            let exists = if let Some(final_string_value) = final_value {
-                // Use the existing value
-                current = final_string_value.clone();
-                true
-            } else {
-                false
-            };
-            if !exists {
-                // Not found, try to get the current value from the environment
-                let mut has_env = false;
-                if let Ok(env_value) = std::env::var(&real_name) {
-                    let env_str: String = env_value;
-                    // Empty values are treated as not existing to prevent ":foo" or
-                    // "foo:" in the final result.
-                    if !env_str.is_empty() {
-                        current = env_str;
-                        has_env = true;
-                    }
-                }
-
-                #[allow(unused_assignments)]
-                if has_env && !is_assign {
-                    final_value = Some(current.clone());
-                } else {
-                    // Either no environment value or an assignment will
-                    // create the value if it's never been seen.
-                    final_value = Some(value.clone());
-                    continue;
-                }
+            // Not found, try to get the current value from the environment.
+            let current = final_value
+                .clone()
+                .or_else(|| std::env::var(&real_name).ok().filter(|env| !env.is_empty()));
+            if let Some(path_value) =
+                apply_environment_op(config, &real_name, op, current.as_deref(), &value)
+            {
+                final_value = Some(path_value);
             }
-
-            // If it's an assignment, replace the value.
-            if is_assign {
-                final_value = Some(value.clone());
-                continue;
-            }
-
-            // Append/prepend the value.
-            let mut path_values: Vec<String> = Vec::new();
-            if !is_append {
-                path_values.push(value.clone());
-            }
-            for path in current.split(':') {
-                if !path.is_empty() {
-                    path_values.push(path.to_string());
-                }
-            }
-            if is_append {
-                path_values.push(value.clone());
-            }
-
-            let path_value = path_values.join(":");
-            final_value = Some(path_value.clone());
         }
     }
 
-    final_value
+    Ok(final_value)
 }
 
 /// Evaluate commands
@@ -860,7 +1321,24 @@ pub fn command(
     app_context: &model::ApplicationContext,
     context: &model::TreeContext,
     name: &str,
-) -> Vec<Vec<String>> {
+) -> Result<Vec<Vec<String>>, errors::GardenError> {
+    let sequences = command_sequences(app_context, context, name, &[])?;
+
+    Ok(sequences
+        .into_iter()
+        .map(|sequence| sequence.commands)
+        .collect())
+}
+
+/// Evaluate commands along with the shell, directory and environment settings
+/// of the command entries that define them. The command environment is applied
+/// on top of the tree environment in "env".
+pub fn command_sequences(
+    app_context: &model::ApplicationContext,
+    context: &model::TreeContext,
+    name: &str,
+    env: &[(String, String)],
+) -> Result<Vec<model::CommandSequence>, errors::GardenError> {
     let mut vec_variables = Vec::new();
     let mut result = Vec::new();
     let config = match context.config {
@@ -870,13 +1348,13 @@ pub fn command(
 
     let pattern = match glob::Pattern::new(name) {
         Ok(value) => value,
-        Err(_) => return result,
+        Err(_) => return Ok(result),
     };
 
     // Global commands
     for (var_name, var) in &config.commands {
         if pattern.matches(var_name) {
-            vec_variables.push(var.clone());
+            vec_variables.push((var_name, var.clone(), config.command_settings.get(var_name)));
         }
     }
 
@@ -884,7 +1362,7 @@ pub fn command(
     if let Some(tree) = config.trees.get(&context.tree) {
         for (var_name, var) in &tree.commands {
             if pattern.matches(var_name) {
-                vec_variables.push(var.clone());
+                vec_variables.push((var_name, var.clone(), tree.command_settings.get(var_name)));
             }
         }
     }
@@ -894,17 +1372,65 @@ pub fn command(
         if let Some(garden) = &config.gardens.get(garden_name) {
             for (var_name, var) in &garden.commands {
                 if pattern.matches(var_name) {
-                    vec_variables.push(var.clone());
+                    vec_variables.push((
+                        var_name,
+                        var.clone(),
+                        garden.command_settings.get(var_name),
+                    ));
                 }
             }
         }
     }
 
-    for variables in vec_variables.iter_mut() {
-        result.push(variables_for_shell(app_context, config, variables, context));
+    for (var_name, variables, settings) in vec_variables.iter_mut() {
+        let mut sequence = model::CommandSequence {
+            name: var_name.to_string(),
+            commands: variables_for_shell(app_context, config, variables, context)?,
+            ..Default::default()
+        };
+        if let Some(settings) = settings {
+            command_settings(app_context, config, context, settings, env, &mut sequence)?;
+        }
+        result.push(sequence);
     }
 
-    result
+    Ok(result)
+}
+
+/// Evaluate the shell, directory and environment settings for a command sequence.
+fn command_settings(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    context: &model::TreeContext,
+    settings: &model::CommandSettings,
+    env: &[(String, String)],
+    sequence: &mut model::CommandSequence,
+) -> Result<(), errors::GardenError> {
+    let graft_config = context.config.map(|id| app_context.get_config(id));
+    if !settings.shell.is_empty() {
+        sequence.shell = Some(settings.shell.clone());
+    }
+    if !settings.cwd.is_empty() {
+        sequence.cwd = Some(tree_value(
+            app_context,
+            config,
+            graft_config,
+            &settings.cwd,
+            &context.tree,
+            context.garden.as_ref(),
+        )?);
+    }
+    if !settings.environment.is_empty() {
+        let vars: Vec<_> = settings
+            .environment
+            .iter()
+            .map(|var| (context.clone(), var))
+            .collect();
+        let mut values: HashMap<String, String> = env.iter().cloned().collect();
+        sequence.environment = evaluate_environment(app_context, config, &vars, &mut values)?;
+    }
+
+    Ok(())
 }
 
 /// Evaluate a variable with a tree context if it has not already been evaluated.
@@ -915,27 +1441,29 @@ pub(crate) fn tree_variable(
     tree_name: &str,
     garden_name: Option<&model::GardenName>,
     var: &model::Variable,
-) -> String {
+) -> Result<String, errors::GardenError> {
     if let Some(var_value) = var.get_value() {
-        return var_value.to_string();
+        return Ok(var_value.to_string());
     }
     if var.is_evaluating() {
-        return String::new();
+        return Ok(String::new());
     }
     var.set_evaluating(true);
     let expr = var.get_expr();
-    let result = tree_value(
+    let result = tree_expression(
         app_context,
         config,
         graft_config,
         expr,
         tree_name,
         garden_name,
+        var.get_cache(),
     );
     var.set_evaluating(false);
+    let result = result?;
     var.set_value(result.to_string());
 
-    result
+    Ok(result)
 }
 
 /// Evaluate a variable if it has not already been evaluated.
@@ -943,18 +1471,19 @@ pub(crate) fn variable(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
     var: &model::Variable,
-) -> String {
+) -> Result<String, errors::GardenError> {
     if let Some(var_value) = var.get_value() {
-        return var_value.to_string();
+        return Ok(var_value.to_string());
     }
     if var.is_evaluating() {
-        return String::new();
+        return Ok(String::new());
     }
     var.set_evaluating(true);
     let expr = var.get_expr();
-    let result = value(app_context, config, expr);
+    let result = expression(app_context, config, expr, var.get_cache());
     var.set_evaluating(false);
+    let result = result?;
     var.set_value(result.to_string());
 
-    result
+    Ok(result)
 }
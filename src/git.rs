@@ -104,6 +104,83 @@ pub(crate) fn branch(path: &std::path::Path) -> Option<String> {
     None
 }
 
+/// Return the status of the worktree at the specified repository path.
+pub fn status(path: &std::path::Path) -> Result<model::GitTreeStatus, errors::CommandError> {
+    let cmd = ["git", "status", "--porcelain=v2", "--branch"];
+    let exec = cmd::exec_in_dir(&cmd, &path);
+    let output = cmd::stdout_to_string(exec)?;
+    let ahead_behind_token = "# branch.ab ";
+    let detached_token = "# branch.head (detached)";
+    let mut status = model::GitTreeStatus::default();
+
+    for line in output.lines() {
+        if line == detached_token {
+            status.detached = true;
+        } else if let Some(ahead_behind) = line.strip_prefix(ahead_behind_token) {
+            // "# branch.ab +<ahead> -<behind>"
+            let mut counts = ahead_behind.split_whitespace().map(|count| {
+                count
+                    .trim_start_matches(['+', '-'])
+                    .parse::<usize>()
+                    .unwrap_or(0)
+            });
+            let ahead = counts.next().unwrap_or(0);
+            let behind = counts.next().unwrap_or(0);
+            status.upstream = Some((ahead, behind));
+        } else if line.starts_with("1 ") || line.starts_with("2 ") {
+            // Ordinary and renamed entries: "1 <XY> ..." where X is the staged state
+            // and Y is the worktree state. "." means unmodified.
+            let mut xy = line[2..].chars();
+            if xy.next().is_some_and(|x| x != '.') {
+                status.staged += 1;
+            }
+            if xy.next().is_some_and(|y| y != '.') {
+                status.dirty += 1;
+            }
+        } else if line.starts_with("u ") {
+            status.dirty += 1;
+        } else if line.starts_with("? ") {
+            status.untracked += 1;
+        }
+    }
+
+    let cmd = ["git", "rev-list", "--walk-reflogs", "--count", "refs/stash"];
+    let exec = cmd::exec_in_dir(&cmd, &path);
+    if let Ok(output) = cmd::stdout_to_string(exec) {
+        status.stashes = output.parse::<usize>().unwrap_or(0);
+    }
+
+    Ok(status)
+}
+
+/// Return true when the repository at the specified path has uncommitted changes,
+/// including untracked files, or when HEAD contains changes relative to its merge base
+/// with the specified reference. Repositories where the reference cannot be resolved
+/// are considered to be changed.
+pub(crate) fn has_changes_since(path: &std::path::Path, reference: &str) -> bool {
+    match status(path) {
+        Ok(status) if status.staged + status.dirty + status.untracked > 0 => return true,
+        Ok(_) => (),
+        Err(_) => return true,
+    }
+    let range = format!("{reference}...HEAD");
+    let cmd = ["git", "diff", "--quiet", range.as_str(), "--"];
+    let exec = cmd::exec_in_dir(&cmd, &path);
+    let (exit_status, _, _) = cmd::status_with_output(exec);
+
+    exit_status != errors::EX_OK
+}
+
+/// Return true when a tree has changes since the specified reference.
+/// Trees that have not been grown are considered changed so that commands
+/// report them as missing.
+pub(crate) fn tree_has_changes_since(tree: &model::Tree, reference: &str) -> bool {
+    match tree.pathbuf() {
+        Some(pathbuf) if pathbuf.exists() => has_changes_since(&pathbuf, reference),
+        _ => true,
+    }
+}
+
 /// Return the root of the current repository when inside a Git repository.
 pub(crate) fn current_worktree_path(
     path: &std::path::Path,
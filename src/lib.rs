@@ -2,6 +2,9 @@
 #[macro_use]
 pub mod macros;
 
+/// Exec expression cache
+pub mod cache;
+
 /// Command-line interface.
 pub mod cli;
 
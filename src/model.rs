@@ -41,11 +41,23 @@ pub type ConfigId = NodeId;
 /// string expression.  An exec expression is denoted by using a "$ "
 /// (dollar-sign followed by space) before the value.  For example,
 /// using "$ echo foo" will place the value "foo" in the variable.
+/// Exec expressions denoted by "$! " are required to succeed.
 #[derive(Clone, Debug, Default)]
 pub struct Variable {
     expr: String,
     value: RefCell<Option<String>>,
     evaluating: RefCell<bool>,
+    cache: Option<ExecCache>,
+}
+
+/// Exec expressions in variables with a "cache" setting store their output on disk.
+#[derive(Clone, Debug, Default)]
+pub struct ExecCache {
+    /// How long cached values remain valid. Values without a duration are
+    /// kept until the watched files change or the cache is cleared.
+    pub duration: Option<std::time::Duration>,
+    /// Files whose modification times are part of the cache key.
+    pub watch: Vec<String>,
 }
 
 impl_display_brief!(Variable);
@@ -56,9 +68,20 @@ impl Variable {
             expr,
             value: RefCell::new(value),
             evaluating: RefCell::new(false),
+            cache: None,
         }
     }
 
+    /// Return the cache settings for exec expressions.
+    pub fn get_cache(&self) -> Option<&ExecCache> {
+        self.cache.as_ref()
+    }
+
+    /// Cache the output of exec expressions.
+    pub fn set_cache(&mut self, cache: ExecCache) {
+        self.cache = Some(cache);
+    }
+
     /// Does this variable have a value?
     pub fn is_empty(&self) -> bool {
         self.expr.is_empty()
@@ -122,6 +145,9 @@ fn reset_hashmap_variables(vec_variables: &MultiVariableHashMap) {
 /// An unordered mapping of name to Variable.
 pub type VariableHashMap = HashMap<String, Variable>;
 
+/// Command settings keyed by command name.
+pub type CommandSettingsMap = HashMap<String, CommandSettings>;
+
 // Named variables with a single value
 #[derive(Clone, Debug)]
 pub struct NamedVariable {
@@ -200,20 +226,74 @@ impl MultiVariable {
         }
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Variable> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Variable> {
         self.variables.iter()
     }
 }
 
+/// Custom commands defined using a mapping declare a description along with
+/// the command-line options and arguments that "garden <command>" accepts.
+#[derive(Clone, Debug, Default)]
+pub struct CommandSpec {
+    pub description: String,
+    pub options: Vec<CommandParam>,
+    pub args: Vec<CommandParam>,
+}
+
+/// A named option or positional argument declared by a custom command.
+/// Parsed values are available to the command as ${name} variables.
+#[derive(Clone, Debug, Default)]
+pub struct CommandParam {
+    pub name: String,
+    pub description: String,
+    pub short: Option<char>,
+    pub default: Option<String>,
+    pub choices: Vec<String>,
+    pub required: bool,
+}
+
+impl CommandSpec {
+    /// Return the options followed by the arguments.
+    pub(crate) fn params(&self) -> impl Iterator<Item = &CommandParam> {
+        self.options.iter().chain(self.args.iter())
+    }
+}
+
+/// Commands defined using a mapping can override the shell, the working directory
+/// and the environment that their "run" commands use.
+#[derive(Clone, Debug, Default)]
+pub struct CommandSettings {
+    /// Shell used instead of "garden.shell".
+    pub shell: String,
+    /// Directory where the commands run. Relative paths are relative to the tree.
+    pub cwd: String,
+    /// Environment variables that are applied on top of the tree environment.
+    pub environment: Vec<MultiVariable>,
+}
+
+/// Commands that have been evaluated for a tree along with their settings.
+#[derive(Clone, Debug, Default)]
+pub struct CommandSequence {
+    /// The name of the command that defines the sequence.
+    pub name: String,
+    pub commands: Vec<String>,
+    pub shell: Option<String>,
+    pub cwd: Option<String>,
+    pub environment: Vec<(String, String)>,
+}
+
 /// Trees represent a single worktree
 #[derive(Clone, Debug, Derivative)]
 #[derivative(Default)]
 pub struct Tree {
     pub commands: MultiVariableHashMap,
+    pub command_settings: CommandSettingsMap,
     pub environment: Vec<MultiVariable>,
     pub gitconfig: MultiVariableHashMap,
     pub remotes: VariableHashMap,
     pub(crate) symlink: Variable,
+    pub depends: IndexSet<String>,
+    pub tags: IndexSet<String>,
     pub templates: IndexSet<String>,
     pub variables: VariableHashMap,
     pub branch: Variable,
@@ -332,9 +412,12 @@ impl Tree {
     /// Copy the guts of another tree into the current tree.
     pub(crate) fn clone_from_tree(&mut self, tree: &Tree) {
         collections::append_hashmap(&mut self.commands, &tree.commands);
+        collections::append_hashmap(&mut self.command_settings, &tree.command_settings);
         collections::append_hashmap(&mut self.gitconfig, &tree.gitconfig);
         collections::append_hashmap(&mut self.variables, &tree.variables);
         collections::append_hashmap(&mut self.remotes, &tree.remotes);
+        collections::append_indexset(&mut self.depends, &tree.depends);
+        collections::append_indexset(&mut self.tags, &tree.tags);
         collections::append_indexset(&mut self.templates, &tree.templates);
 
         // "environment" follow last-set-wins semantics.
@@ -442,6 +525,7 @@ impl Template {
 #[derive(Clone, Debug, Default)]
 pub struct Garden {
     pub commands: MultiVariableHashMap,
+    pub command_settings: CommandSettingsMap,
     pub environment: Vec<MultiVariable>,
     pub gitconfig: MultiVariableHashMap,
     pub groups: IndexSet<String>,
@@ -479,10 +563,36 @@ fn get_default_shell() -> String {
     .to_string()
 }
 
+/// SourceLocation records the garden file and line where an entry is defined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: std::path::PathBuf,
+    pub line: usize,
+}
+
+impl std::fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", path::display_relative(&self.path), self.line)
+    }
+}
+
+/// SourceFile holds the contents of a garden file so that source locations
+/// can be read when they are needed.
+#[derive(Clone, Debug)]
+pub(crate) struct SourceFile {
+    pub(crate) path: std::path::PathBuf,
+    pub(crate) string: String,
+    /// Trees that use "replace: true" discard the locations recorded from earlier files.
+    pub(crate) replaced_trees: Vec<String>,
+}
+
 /// Configuration represents an instantiated garden configuration
 #[derive(Clone, Debug, Default)]
 pub struct Configuration {
     pub commands: MultiVariableHashMap,
+    /// Descriptions, options and arguments for commands defined using a mapping.
+    pub command_specs: IndexMap<String, CommandSpec>,
+    pub command_settings: CommandSettingsMap,
     pub debug: HashMap<String, u8>,
     pub environment: Vec<MultiVariable>,
     pub gardens: GardenMap,
@@ -502,9 +612,21 @@ pub struct Configuration {
     /// Variables defined on the command-line using "-D name=value" have the
     /// highest precedence and override variables defined by any configuration or tree.
     pub override_variables: VariableHashMap,
+    /// Garden files in the order that they were read.
+    pub(crate) source_files: Vec<SourceFile>,
+    /// Source locations for trees, templates, groups, gardens, grafts, variables
+    /// and commands keyed by their dotted key path, e.g. "trees.foo.url".
+    /// Locations are read from the source files on first use.
+    sources: std::sync::OnceLock<IndexMap<String, SourceLocation>>,
     pub config_verbose: u8,
     pub quiet: bool,
     pub verbose: u8,
+    /// Remove duplicate entries when prepending or appending to environment variables.
+    pub(crate) env_dedupe: bool,
+    /// Separators for path-list environment variables keyed by variable name.
+    pub(crate) env_separators: HashMap<String, String>,
+    /// The number of seconds that exec expressions may run. Zero disables the timeout.
+    pub(crate) exec_timeout: u64,
     pub(crate) shell_exit_on_error: bool,
     pub(crate) shell_word_split: bool,
     pub(crate) tree_branches: bool,
@@ -528,10 +650,13 @@ impl Configuration {
         }
     }
 
-    pub(crate) fn initialize(&mut self, app_context: &ApplicationContext) {
+    pub(crate) fn initialize(
+        &mut self,
+        app_context: &ApplicationContext,
+    ) -> Result<(), errors::GardenError> {
         // Evaluate garden.root
         let expr = self.root.get_expr().to_string();
-        let mut value = eval::value(app_context, self, &expr);
+        let mut value = eval::value(app_context, self, &expr)?;
         if expr.is_empty() {
             if self.root_is_dynamic {
                 // Default to the current directory when garden.root is configured to
@@ -563,10 +688,12 @@ impl Configuration {
             }
             self.root.set_value(value);
         }
-        self.update_tree_paths(app_context); // Resolve tree paths
+        self.update_tree_paths(app_context)?; // Resolve tree paths
         self.synthesize_default_tree(); // Synthesize a tree if no trees exist.
                                         // Reset variables
         self.reset();
+
+        Ok(())
     }
 
     /// Return Some(&NodeId) when the configuration is a graft and None otherwise.
@@ -675,6 +802,12 @@ impl Configuration {
             }
             // Allow overridding garden.<value> using "garden -D garden.<value>=false".
             match name.as_str() {
+                constants::GARDEN_ENV_DEDUPE => {
+                    set_bool(name.as_str(), &expr, &mut self.env_dedupe);
+                }
+                constants::GARDEN_EXEC_TIMEOUT => {
+                    set_seconds(name.as_str(), &expr, &mut self.exec_timeout);
+                }
                 constants::GARDEN_INTERACTIVE_SHELL => {
                     self.interactive_shell = expr;
                 }
@@ -739,7 +872,10 @@ impl Configuration {
     // Calculate the "path" field for each tree.
     // If specified as a relative path, it will be relative to garden.root.
     // If specified as an asbolute path, it will be left as-is.
-    fn update_tree_paths(&mut self, app_context: &ApplicationContext) {
+    fn update_tree_paths(
+        &mut self,
+        app_context: &ApplicationContext,
+    ) -> Result<(), errors::GardenError> {
         // Gather path and symlink expressions.
         let mut path_values = Vec::new();
         let mut symlink_values = Vec::new();
@@ -753,7 +889,7 @@ impl Configuration {
 
         // Evaluate the "path" expression.
         for (name, value) in &path_values {
-            let result = self.eval_tree_path(app_context, value);
+            let result = self.eval_tree_path(app_context, value)?;
             if let Some(tree) = self.trees.get_mut(name) {
                 tree.path.set_value(result);
             }
@@ -761,11 +897,13 @@ impl Configuration {
 
         // Evaluate the "symlink" expression.
         for (name, value) in &symlink_values {
-            let result = self.eval_tree_path(app_context, value);
+            let result = self.eval_tree_path(app_context, value)?;
             if let Some(tree) = self.trees.get_mut(name) {
                 tree.symlink.set_value(result);
             }
         }
+
+        Ok(())
     }
 
     /// Create an implicit "." tree when no trees exist.
@@ -816,9 +954,13 @@ impl Configuration {
     }
 
     /// Evaluate and return a path string relative to the garden root.
-    fn eval_tree_path(&mut self, app_context: &ApplicationContext, path: &str) -> String {
-        let value = eval::value(app_context, self, path);
-        self.tree_path(&value)
+    fn eval_tree_path(
+        &mut self,
+        app_context: &ApplicationContext,
+        path: &str,
+    ) -> Result<String, errors::GardenError> {
+        let value = eval::value(app_context, self, path)?;
+        Ok(self.tree_path(&value))
     }
 
     /// Resolve a pathbuf relative to the config directory.
@@ -886,9 +1028,13 @@ impl Configuration {
     }
 
     /// Evaluate and resolve a path string and relative to the config directory.
-    pub(crate) fn eval_config_path(&self, app_context: &ApplicationContext, path: &str) -> String {
-        let value = eval::value(app_context, self, path);
-        self.config_path(&value)
+    pub(crate) fn eval_config_path(
+        &self,
+        app_context: &ApplicationContext,
+        path: &str,
+    ) -> Result<String, errors::GardenError> {
+        let value = eval::value(app_context, self, path)?;
+        Ok(self.config_path(&value))
     }
 
     /// Evaluate and resolve a pathbuf relative to the config directory for "includes".
@@ -897,15 +1043,16 @@ impl Configuration {
         app_context: &ApplicationContext,
         include_path: Option<&std::path::Path>,
         path: &str,
-    ) -> Option<std::path::PathBuf> {
-        let value = eval::value(app_context, self, path);
-
-        if let Some(include_path) = include_path {
+    ) -> Result<Option<std::path::PathBuf>, errors::GardenError> {
+        let value = eval::value(app_context, self, path)?;
+        let pathbuf = if let Some(include_path) = include_path {
             self.config_pathbuf_from_include(include_path, &value)
         } else {
             self.config_pathbuf(&value)
         }
-        .or_else(|| Some(std::path::PathBuf::from(&value)))
+        .or_else(|| Some(std::path::PathBuf::from(&value)));
+
+        Ok(pathbuf)
     }
 
     /// Reset resolved variables
@@ -965,6 +1112,40 @@ impl Configuration {
             .to_string()
     }
 
+    /// Return the separator used to join the entries of a path-list environment variable.
+    pub(crate) fn env_separator(&self, name: &str) -> &str {
+        self.env_separators
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or(":")
+    }
+
+    /// Return the source locations keyed by their dotted key path.
+    pub fn get_sources(&self) -> &IndexMap<String, SourceLocation> {
+        self.sources
+            .get_or_init(|| config::reader::read_sources(&self.source_files))
+    }
+
+    /// Return the location where an entry is defined using its dotted key path.
+    /// The location of the nearest parent entry is returned when the key was not
+    /// recorded, e.g. "trees.foo.url" falls back to "trees.foo".
+    pub fn get_source(&self, key: &str) -> Option<&SourceLocation> {
+        let sources = self.get_sources();
+        let mut key = key;
+        loop {
+            if let Some(location) = sources.get(key) {
+                return Some(location);
+            }
+            key = &key[..key.rfind('.')?];
+        }
+    }
+
+    /// Return a "<key> defined at <file>:<line>" description for error messages.
+    pub(crate) fn describe_source(&self, key: &str) -> Option<String> {
+        self.get_source(key)
+            .map(|location| format!("{key} defined at {location}"))
+    }
+
     /// Return true if the configuration contains the named graft.
     pub(crate) fn contains_graft(&self, name: &str) -> bool {
         let graft_name = syntax::trim(name);
@@ -1026,6 +1207,18 @@ fn set_bool(name: &str, expr: &str, output: &mut bool) {
     }
 }
 
+/// Parse a number of seconds from a string.
+fn set_seconds(name: &str, expr: &str, output: &mut u64) {
+    if let Ok(value) = expr.parse::<u64>() {
+        *output = value;
+    } else {
+        error!(
+            "'{}' is not a valid value for \"{}\". Must be a number of seconds",
+            expr, name
+        );
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Graft {
     id: Option<ConfigId>,
@@ -1121,6 +1314,7 @@ pub struct TreeQuery {
     pub is_default: bool,
     pub is_garden: bool,
     pub is_group: bool,
+    pub is_tag: bool,
     pub is_tree: bool,
     pub include_gardens: bool,
     pub include_groups: bool,
@@ -1135,6 +1329,7 @@ impl TreeQuery {
         let mut is_tree = false;
         let mut is_garden = false;
         let mut is_group = false;
+        let mut is_tag = false;
         let mut include_gardens = true;
         let mut include_groups = true;
         let mut include_trees = true;
@@ -1151,6 +1346,11 @@ impl TreeQuery {
             is_tree = true;
             include_gardens = false;
             include_groups = false;
+        } else if syntax::is_tag(query) {
+            is_tag = true;
+            include_gardens = false;
+            include_groups = false;
+            include_trees = false;
         } else {
             is_default = true;
         }
@@ -1162,6 +1362,7 @@ impl TreeQuery {
             is_default,
             is_garden,
             is_group,
+            is_tag,
             is_tree,
             include_gardens,
             include_groups,
@@ -1269,6 +1470,7 @@ impl ApplicationContext {
 
     /// Initialize an ApplicationContext and Configuration from cli::MainOptions.
     pub fn from_options(options: &cli::MainOptions) -> Result<Self, errors::GardenError> {
+        let start = std::time::Instant::now();
         let app_context = Self::new(options.clone());
         let config_verbose = options.debug_level(constants::DEBUG_LEVEL_CONFIG);
 
@@ -1281,6 +1483,9 @@ impl ApplicationContext {
         )?;
         app_context.get_root_config_mut().update_options(options)?;
         config::read_grafts(&app_context)?;
+        if options.debug_level(constants::DEBUG_LEVEL_TIMING) > 0 {
+            debug!("timing: config: {:.3?}", start.elapsed());
+        }
 
         Ok(app_context)
     }
@@ -1362,6 +1567,27 @@ impl ApplicationContext {
         graft_id
     }
 
+    /// Return the "::"-separated graft namespace for a configuration.
+    /// The root configuration has an empty namespace.
+    pub(crate) fn get_graft_namespace(&self, id: ConfigId) -> String {
+        let mut names = Vec::new();
+        let mut config = self.get_config(id);
+        while let Some(parent_id) = config.parent_id {
+            let parent = self.get_config(parent_id);
+            if let Some(graft) = parent
+                .grafts
+                .values()
+                .find(|graft| graft.get_id() == config.get_id())
+            {
+                names.push(graft.get_name().as_str());
+            }
+            config = parent;
+        }
+        names.reverse();
+
+        names.join("::")
+    }
+
     /// Attach a graft to the configuration specified by ConfigId.
     pub(crate) fn add_graft_config(
         &self,
@@ -1375,6 +1601,9 @@ impl ApplicationContext {
         let mut graft_config = Configuration::new();
         // Propagate the current config's settings onto child grafts.
         graft_config.tree_branches = self.get_config(config_id).tree_branches;
+        graft_config.env_dedupe = self.get_config(config_id).env_dedupe;
+        graft_config.env_separators = self.get_config(config_id).env_separators.clone();
+        graft_config.exec_timeout = self.get_config(config_id).exec_timeout;
         graft_config.shell_exit_on_error = self.get_config(config_id).shell_exit_on_error;
         graft_config.shell_word_split = self.get_config(config_id).shell_word_split;
         // Parse the config file for the graft.
@@ -1393,6 +1622,70 @@ impl ApplicationContext {
     }
 }
 
+/// Output formats for commands that emit structured data.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    strum_macros::EnumString,
+    strum_macros::Display,
+    strum_macros::EnumVariantNames,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// JSON
+    Json,
+    /// YAML
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Parse an output format from a string using strum's from_str().
+    pub fn parse_from_str(string: &str) -> Result<OutputFormat, String> {
+        OutputFormat::from_str(string).map_err(|_| format!("choices are {:?}", Self::VARIANTS))
+    }
+}
+
+/// Output formats for "garden env".
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    strum_macros::EnumString,
+    strum_macros::Display,
+    strum_macros::EnumVariantNames,
+)]
+#[strum(ascii_case_insensitive, serialize_all = "kebab-case")]
+pub enum EnvFormat {
+    /// POSIX shell "export" statements
+    #[default]
+    Sh,
+    /// Bash "export" statements
+    Bash,
+    /// Zsh "export" statements
+    Zsh,
+    /// Fish "set -gx" statements
+    Fish,
+    /// ".env" files
+    Dotenv,
+    /// JSON
+    Json,
+}
+
+impl EnvFormat {
+    /// Parse an environment format from a string using strum's from_str().
+    pub fn parse_from_str(string: &str) -> Result<EnvFormat, String> {
+        EnvFormat::from_str(string).map_err(|_| format!("choices are {:?}", Self::VARIANTS))
+    }
+}
+
 /// Represent the different types of Git worktree.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum GitTreeType {
@@ -1416,3 +1709,23 @@ pub struct GitTreeDetails {
 }
 
 impl_display!(GitTreeDetails);
+
+/// Represent "git status" details queried from Git.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitTreeStatus {
+    /// The number of commits (ahead, behind) relative to the upstream branch.
+    /// None when the current branch does not have an upstream branch.
+    pub upstream: Option<(usize, usize)>,
+    /// The number of files with staged changes.
+    pub staged: usize,
+    /// The number of files with unstaged changes, including unmerged files.
+    pub dirty: usize,
+    /// The number of untracked files.
+    pub untracked: usize,
+    /// The number of stash entries.
+    pub stashes: usize,
+    /// Is HEAD detached?
+    pub detached: bool,
+}
+
+impl_display!(GitTreeStatus);
@@ -22,6 +22,15 @@ pub fn abspath(path: &std::path::Path) -> std::path::PathBuf {
         .unwrap_or_else(|_| path.to_path_buf())
 }
 
+/// Return a path for display. Paths are displayed relative to the current directory when possible.
+pub(crate) fn display_relative(path: &std::path::Path) -> String {
+    let current_dir = current_dir();
+    path.strip_prefix(&current_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
 /// Strip a prefix from a path.
 pub(crate) fn strip_prefix(
     root: &std::path::Path,
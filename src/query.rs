@@ -2,26 +2,278 @@ use crate::{constants, errors, eval, model, path, query, syntax};
 
 /// Resolve a tree query into a `Vec<garden::model::TreeContext>`.
 ///
+/// Queries can combine `:garden`, `%group`, `@tree` and glob patterns using the
+/// `|` (union), `&` (intersection) and `-` (exclusion) operators and parentheses.
+///
 /// Parameters:
 /// - `config`: `&garden::model::Configuration`.
 /// - `query`: Tree query `&str`.
+///
 /// Returns:
-/// - `Vec<garden::model::TreeContext>`
-
+/// - `Vec<garden::model::TreeContext>`, or a `QuerySyntaxError` for invalid queries.
 pub fn resolve_trees(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
     graft_config: Option<&model::Configuration>,
     query: &str,
-) -> Vec<model::TreeContext> {
+) -> Result<Vec<model::TreeContext>, errors::GardenError> {
+    let expr = parse_query(query)?;
+    resolve_expression(app_context, config, graft_config, &expr)
+}
+
+/// A parsed tree query expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryExpression {
+    /// A single `:garden`, `%group`, `@tree` or glob pattern query.
+    Query(String),
+    /// Trees matched by either side: `a | b`.
+    Union(Box<QueryExpression>, Box<QueryExpression>),
+    /// Trees matched by both sides: `a & b`.
+    Intersection(Box<QueryExpression>, Box<QueryExpression>),
+    /// Trees matched by the left side but not the right side: `a - b`.
+    Difference(Box<QueryExpression>, Box<QueryExpression>),
+}
+
+/// Tokens produced when splitting a tree query expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum QueryToken<'a> {
+    Word(&'a str),
+    Union,
+    Intersection,
+    Difference,
+    Open,
+    Close,
+}
+
+/// Split a tree query into tokens and their byte offsets.
+/// "&" and "|" are always tokens. "-" is only an operator when it is surrounded by
+/// whitespace so that tree names can contain dashes. Parentheses are only operators
+/// when they are not attached to a word: "(" must follow whitespace, an operator or
+/// the start of the query, and ")" must precede whitespace, an operator or the end of
+/// the query. Parentheses that are opened within a word are closed within the same
+/// word so that paths such as "dir(1)" can be used as queries.
+fn tokenize_query(query: &str) -> Vec<(usize, QueryToken<'_>)> {
+    let word_token = |word| match word {
+        "-" => QueryToken::Difference,
+        _ => QueryToken::Word(word),
+    };
+    let is_boundary = |value: Option<char>| match value {
+        None => true,
+        Some(value) => value.is_whitespace() || matches!(value, '&' | '|' | ')'),
+    };
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+    // Parentheses that were opened within the current word.
+    let mut word_depth = 0;
+    let mut chars = query.char_indices().peekable();
+    while let Some((offset, value)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        let token = match value {
+            '(' if start.is_none() => Some(QueryToken::Open),
+            ')' if start.is_none() || (word_depth == 0 && is_boundary(next)) => {
+                Some(QueryToken::Close)
+            }
+            '&' => Some(QueryToken::Intersection),
+            '|' => Some(QueryToken::Union),
+            _ => None,
+        };
+        if token.is_some() || value.is_whitespace() {
+            if let Some(begin) = start.take() {
+                tokens.push((begin, word_token(&query[begin..offset])));
+            }
+            if let Some(token) = token {
+                tokens.push((offset, token));
+            }
+            continue;
+        }
+        if start.is_none() {
+            start = Some(offset);
+            word_depth = 0;
+        }
+        match value {
+            '(' => word_depth += 1,
+            ')' => word_depth -= 1,
+            _ => (),
+        }
+    }
+    if let Some(begin) = start {
+        tokens.push((begin, word_token(&query[begin..])));
+    }
+
+    tokens
+}
+
+/// Parse a tree query into a `QueryExpression`.
+///
+/// Queries without operators or parentheses are returned as a single
+/// `QueryExpression::Query` so that they are resolved exactly as written.
+/// `&` binds more tightly than `|` and `-`, which are evaluated left to right.
+pub fn parse_query(query: &str) -> Result<QueryExpression, errors::GardenError> {
+    let tokens = tokenize_query(query);
+    let is_expression = tokens
+        .iter()
+        .any(|(_, token)| !matches!(token, QueryToken::Word(_)));
+    if !is_expression {
+        return Ok(QueryExpression::Query(query.to_string()));
+    }
+    let mut parser = QueryParser {
+        query,
+        tokens,
+        position: 0,
+    };
+    let expr = parser.parse_expression()?;
+    if let Some((offset, token)) = parser.peek() {
+        let message = match token {
+            QueryToken::Close => "unmatched ')'",
+            _ => "expected an operator",
+        };
+        return Err(parser.error(offset, message));
+    }
+
+    Ok(expr)
+}
+
+/// Recursive descent parser for tree query expressions.
+struct QueryParser<'a> {
+    query: &'a str,
+    tokens: Vec<(usize, QueryToken<'a>)>,
+    position: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn peek(&self) -> Option<(usize, QueryToken<'a>)> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn error(&self, offset: usize, message: &str) -> errors::GardenError {
+        errors::GardenError::QuerySyntaxError {
+            query: self.query.to_string(),
+            offset,
+            message: message.to_string(),
+        }
+    }
+
+    /// expression := term (("|" | "-") term)*
+    fn parse_expression(&mut self) -> Result<QueryExpression, errors::GardenError> {
+        let mut expr = self.parse_term()?;
+        while let Some((_, token)) = self.peek() {
+            let is_union = match token {
+                QueryToken::Union => true,
+                QueryToken::Difference => false,
+                _ => break,
+            };
+            self.position += 1;
+            let rhs = Box::new(self.parse_term()?);
+            expr = if is_union {
+                QueryExpression::Union(Box::new(expr), rhs)
+            } else {
+                QueryExpression::Difference(Box::new(expr), rhs)
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// term := atom ("&" atom)*
+    fn parse_term(&mut self) -> Result<QueryExpression, errors::GardenError> {
+        let mut expr = self.parse_atom()?;
+        while let Some((_, QueryToken::Intersection)) = self.peek() {
+            self.position += 1;
+            let rhs = self.parse_atom()?;
+            expr = QueryExpression::Intersection(Box::new(expr), Box::new(rhs));
+        }
+
+        Ok(expr)
+    }
+
+    /// atom := query | "(" expression ")"
+    fn parse_atom(&mut self) -> Result<QueryExpression, errors::GardenError> {
+        match self.peek() {
+            Some((_, QueryToken::Word(word))) => {
+                self.position += 1;
+                Ok(QueryExpression::Query(word.to_string()))
+            }
+            Some((offset, QueryToken::Open)) => {
+                self.position += 1;
+                let expr = self.parse_expression()?;
+                match self.peek() {
+                    Some((_, QueryToken::Close)) => {
+                        self.position += 1;
+                        Ok(expr)
+                    }
+                    Some((next_offset, _)) => Err(self.error(next_offset, "expected ')'")),
+                    None => Err(self.error(
+                        self.query.len(),
+                        &format!("unclosed '(' at offset {offset}"),
+                    )),
+                }
+            }
+            Some((offset, _)) => Err(self.error(offset, "expected a tree query")),
+            None => Err(self.error(self.query.len(), "expected a tree query")),
+        }
+    }
+}
+
+/// Resolve a parsed query expression into a `Vec<garden::model::TreeContext>`.
+/// Trees are compared by name and configuration when combining results.
+/// Trees from the root configuration may or may not record its ConfigId.
+fn resolve_expression(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    graft_config: Option<&model::Configuration>,
+    expr: &QueryExpression,
+) -> Result<Vec<model::TreeContext>, errors::GardenError> {
+    let root_id = Some(app_context.get_root_id());
+    let is_same = |a: &model::TreeContext, b: &model::TreeContext| {
+        a.tree == b.tree && a.config.or(root_id) == b.config.or(root_id)
+    };
+    let result = match expr {
+        QueryExpression::Query(query) => resolve_query(app_context, config, graft_config, query)?,
+        QueryExpression::Union(lhs, rhs) => {
+            let mut result = resolve_expression(app_context, config, graft_config, lhs)?;
+            for context in resolve_expression(app_context, config, graft_config, rhs)? {
+                if !result.iter().any(|existing| is_same(existing, &context)) {
+                    result.push(context);
+                }
+            }
+            result
+        }
+        QueryExpression::Intersection(lhs, rhs) => {
+            let rhs = resolve_expression(app_context, config, graft_config, rhs)?;
+            let mut result = resolve_expression(app_context, config, graft_config, lhs)?;
+            result.retain(|context| rhs.iter().any(|other| is_same(context, other)));
+            result
+        }
+        QueryExpression::Difference(lhs, rhs) => {
+            let rhs = resolve_expression(app_context, config, graft_config, rhs)?;
+            let mut result = resolve_expression(app_context, config, graft_config, lhs)?;
+            result.retain(|context| !rhs.iter().any(|other| is_same(context, other)));
+            result
+        }
+    };
+
+    Ok(result)
+}
+
+/// Resolve a single `:garden`, `%group`, `@tree` or glob pattern query.
+fn resolve_query(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    graft_config: Option<&model::Configuration>,
+    query: &str,
+) -> Result<Vec<model::TreeContext>, errors::GardenError> {
     let mut result = Vec::new();
     let tree_query = model::TreeQuery::new(query);
     let pattern = &tree_query.pattern;
 
+    if tree_query.is_tag {
+        return Ok(tagged_trees(graft_config.unwrap_or(config), pattern));
+    }
+
     if tree_query.include_gardens {
         result = garden_trees(app_context, config, graft_config, pattern);
         if !result.is_empty() {
-            return result;
+            return Ok(result);
         }
     }
 
@@ -60,7 +312,7 @@ pub fn resolve_trees(
                 ));
             }
             if !result.is_empty() {
-                return result;
+                return Ok(result);
             }
         }
     }
@@ -75,7 +327,7 @@ pub fn resolve_trees(
                     config,
                     Some(app_context.get_config(graft_id)),
                     remainder,
-                ));
+                )?);
             }
         } else if let Some(graft_cfg) = graft_config {
             result.append(&mut trees(graft_cfg, pattern));
@@ -83,7 +335,7 @@ pub fn resolve_trees(
             result.append(&mut trees(config, pattern));
         }
         if !result.is_empty() {
-            return result;
+            return Ok(result);
         }
     }
 
@@ -97,7 +349,7 @@ pub fn resolve_trees(
         }
     }
 
-    result
+    Ok(result)
 }
 
 /// Resolve a tree query into a filtered `Vec<garden::model::TreeContext>`.
@@ -106,15 +358,16 @@ pub fn resolve_trees(
 /// - `config`: `&garden::model::Configuration`.
 /// - `query`: Tree query `&str`.
 /// - `pattern`: Tree name glob pattern used to filter the results.
+///
 /// Returns:
-/// - `Vec<garden::model::TreeContext>`
+/// - `Vec<garden::model::TreeContext>`, or a `QuerySyntaxError` for invalid queries.
 pub(crate) fn resolve_and_filter_trees(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
     query: &str,
     pattern: &str,
-) -> Vec<model::TreeContext> {
-    let contexts = resolve_trees(app_context, config, None, query);
+) -> Result<Vec<model::TreeContext>, errors::GardenError> {
+    let contexts = resolve_trees(app_context, config, None, query)?;
     let tree_pattern = glob::Pattern::new(pattern).unwrap_or_default();
     let mut result = Vec::with_capacity(contexts.len());
     for context in contexts {
@@ -123,14 +376,23 @@ pub(crate) fn resolve_and_filter_trees(
         }
     }
 
-    result
+    Ok(result)
+}
+
+/// Return tree contexts for every tree with a tag matching the specified pattern.
+fn tagged_trees(config: &model::Configuration, pattern: &glob::Pattern) -> Vec<model::TreeContext> {
+    config
+        .trees
+        .values()
+        .filter(|tree| tree.tags.iter().any(|tag| pattern.matches(tag)))
+        .map(|tree| model::TreeContext::new(tree.get_name(), config.graft_id(), None, None))
+        .collect()
 }
 
 /// Return tree contexts for every garden matching the specified pattern.
 /// Parameters:
 /// - config: `&garden::model::Configuration`
 /// - pattern: `&glob::Pattern`
-
 fn garden_trees(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
@@ -250,7 +512,6 @@ pub fn trees_from_group(
 /// - tree: Tree name `&str`
 /// - garden_name: optional name of the garden in which to operate.
 /// - group: optional name of the group in which to operate.
-
 pub fn tree_from_name(
     config: &model::Configuration,
     tree_name: &str,
@@ -282,7 +543,6 @@ pub fn tree_from_name(
 /// - config: `&garden::model::Configuration`
 /// - tree: Tree name pattern `&str`
 /// - garden_name: `Option<garden::model::GardenName>`
-
 pub fn trees_from_pattern(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
@@ -411,7 +671,6 @@ fn tree_from_pathbuf(
 }
 
 /// Return the name of an existing tree from the specified path.
-
 pub fn tree_name_from_path(
     config: &model::Configuration,
     path: &std::path::Path,
@@ -420,7 +679,6 @@ pub fn tree_name_from_path(
 }
 
 /// Return the name of an existing tree from an absolute path.
-
 pub(crate) fn tree_name_from_abspath(
     config: &model::Configuration,
     path: &std::path::Path,
@@ -449,7 +707,6 @@ pub(crate) fn tree_name_from_abspath(
 }
 
 /// Returns tree contexts matching the specified pattern
-
 fn trees(config: &model::Configuration, pattern: &glob::Pattern) -> Vec<model::TreeContext> {
     let mut result = Vec::new();
     for (tree_name, tree) in &config.trees {
@@ -466,9 +723,141 @@ fn trees(config: &model::Configuration, pattern: &glob::Pattern) -> Vec<model::T
     result
 }
 
+/// Group tree contexts into waves that respect the "depends" lists of each tree.
+///
+/// Trees run after every tree that they depend on, including dependencies that
+/// are reached through trees outside of the current query. Trees within a wave do
+/// not depend on each other and retain their original order.
+/// Missing dependencies and dependency cycles are reported as configuration errors.
+pub fn dependency_waves(
+    app_context: &model::ApplicationContext,
+    contexts: Vec<model::TreeContext>,
+) -> Result<Vec<Vec<model::TreeContext>>, errors::GardenError> {
+    let root_id = app_context.get_root_id();
+    let mut depths = DependencyDepths::new(app_context);
+    let mut waves: Vec<Vec<model::TreeContext>> = Vec::new();
+    for context in contexts {
+        let config_id = context.config.unwrap_or(root_id);
+        let depth = depths.depth(config_id, &context.tree)?;
+        if waves.len() <= depth {
+            waves.resize_with(depth + 1, Vec::new);
+        }
+        waves[depth].push(context);
+    }
+    waves.retain(|wave| !wave.is_empty());
+
+    Ok(waves)
+}
+
+/// Calculate and memoize the dependency depth for trees.
+/// Trees without dependencies have a depth of zero.
+struct DependencyDepths<'a> {
+    app_context: &'a model::ApplicationContext,
+    depths: std::collections::HashMap<(model::ConfigId, String), usize>,
+    /// Trees that are currently being visited are used to detect cycles.
+    stack: Vec<(model::ConfigId, String)>,
+}
+
+impl<'a> DependencyDepths<'a> {
+    fn new(app_context: &'a model::ApplicationContext) -> Self {
+        Self {
+            app_context,
+            depths: std::collections::HashMap::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Return the graft-qualified name for a tree.
+    fn display_name(&self, config_id: model::ConfigId, tree: &str) -> String {
+        let namespace = self.app_context.get_graft_namespace(config_id);
+        if namespace.is_empty() {
+            tree.to_string()
+        } else {
+            format!("{namespace}::{tree}")
+        }
+    }
+
+    /// Return a " (trees.<tree>.depends defined at <file>:<line>)" suffix for error messages.
+    fn depends_source(&self, config_id: model::ConfigId, tree: &str) -> String {
+        let key = format!("{}.{tree}.{}", constants::TREES, constants::DEPENDS);
+        match self.app_context.get_config(config_id).describe_source(&key) {
+            Some(source) => format!(" ({source})"),
+            None => String::new(),
+        }
+    }
+
+    fn depth(
+        &mut self,
+        config_id: model::ConfigId,
+        tree_name: &str,
+    ) -> Result<usize, errors::GardenError> {
+        let key = (config_id, tree_name.to_string());
+        if let Some(depth) = self.depths.get(&key) {
+            return Ok(*depth);
+        }
+        if let Some(index) = self.stack.iter().position(|entry| entry == &key) {
+            let mut cycle: Vec<String> = self.stack[index..]
+                .iter()
+                .map(|(id, name)| self.display_name(*id, name))
+                .collect();
+            cycle.push(self.display_name(config_id, tree_name));
+            // Report the "depends" entry that closes the cycle.
+            let source = match self.stack.last() {
+                Some((id, name)) => self.depends_source(*id, name),
+                None => String::new(),
+            };
+            return Err(errors::GardenError::InvalidConfiguration {
+                msg: format!("dependency cycle: {}{source}", cycle.join(" -> ")),
+            });
+        }
+        let app_context = self.app_context;
+        let config = app_context.get_config(config_id);
+        let tree = match config.trees.get(tree_name) {
+            Some(tree) => tree,
+            None => return Ok(0),
+        };
+
+        self.stack.push(key.clone());
+        let mut depth = 0;
+        for dependency in &tree.depends {
+            let (dep_config_id, dep_name) = find_dependency(app_context, config, dependency)
+                .ok_or_else(|| errors::GardenError::InvalidConfiguration {
+                    msg: format!(
+                        "{}: dependency does not exist: {dependency}{}",
+                        self.display_name(config_id, tree_name),
+                        self.depends_source(config_id, tree_name)
+                    ),
+                })?;
+            let dep_depth = self.depth(dep_config_id, &dep_name)?;
+            depth = depth.max(dep_depth + 1);
+        }
+        self.stack.pop();
+        self.depths.insert(key, depth);
+
+        Ok(depth)
+    }
+}
+
+/// Find the configuration and tree name for a "depends" entry.
+/// Dependencies can use "graft::tree" names to refer to grafted trees.
+pub(crate) fn find_dependency(
+    app_context: &model::ApplicationContext,
+    config: &model::Configuration,
+    dependency: &str,
+) -> Option<(model::ConfigId, String)> {
+    if syntax::is_graft(dependency) {
+        let (graft_id, remainder) = config.get_graft_id(dependency).ok()?;
+        return find_dependency(app_context, app_context.get_config(graft_id), remainder);
+    }
+    config
+        .trees
+        .get(dependency)
+        .and(config.get_id())
+        .map(|config_id| (config_id, dependency.to_string()))
+}
+
 /// Return a Result<garden::model::TreeContext, garden::errors::GardenError>
 /// when the tree and optional garden are present.
-
 pub fn tree_context(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
@@ -558,7 +947,7 @@ pub(crate) fn shared_worktree_path(
     app_context: &model::ApplicationContext,
     config: &model::Configuration,
     ctx: &model::TreeContext,
-) -> String {
+) -> Result<String, errors::GardenError> {
     let config = match ctx.config {
         Some(config_id) => app_context.get_config(config_id),
         None => config,
@@ -567,7 +956,7 @@ pub(crate) fn shared_worktree_path(
         Some(tree) => tree,
         None => match app_context.get_root_config().trees.get(&ctx.tree) {
             Some(tree) => tree,
-            None => return String::new(),
+            None => return Ok(String::new()),
         },
     };
     if tree.is_worktree {
@@ -578,7 +967,7 @@ pub(crate) fn shared_worktree_path(
             &ctx.tree,
             ctx.garden.as_ref(),
             &tree.worktree,
-        );
+        )?;
         if let Some(parent_ctx) =
             query::tree_from_name(config, &worktree, ctx.garden.as_ref(), ctx.group.as_ref())
         {
@@ -587,14 +976,14 @@ pub(crate) fn shared_worktree_path(
                 .get(&parent_ctx.tree)
                 .and_then(|tree| tree.path_as_ref().ok())
             {
-                return path.to_string();
+                return Ok(path.to_string());
             }
         }
     }
 
     if let Ok(path) = tree.path_as_ref() {
-        return path.to_string();
+        return Ok(path.to_string());
     }
 
-    tree.get_name().to_string()
+    Ok(tree.get_name().to_string())
 }
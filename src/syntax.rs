@@ -4,10 +4,16 @@ pub(crate) fn is_digit(string: &str) -> bool {
     string.chars().all(|c| c.is_ascii_digit())
 }
 
-/// Return true if `string` is an `$ exec` expression.
+/// Return true if `string` is an `$ exec` or `$! exec` expression.
 #[inline]
 pub(crate) fn is_exec(string: &str) -> bool {
-    string.starts_with("$ ")
+    string.starts_with("$ ") || is_required_exec(string)
+}
+
+/// Return true if `string` is a `$! exec` expression whose failure is an error.
+#[inline]
+pub(crate) fn is_required_exec(string: &str) -> bool {
+    string.starts_with("$! ")
 }
 
 /// Return true if `string` is a `:garden` expression.
@@ -28,13 +34,31 @@ pub(crate) fn is_tree(string: &str) -> bool {
     string.starts_with('@')
 }
 
-/// Return true if `string` is a variable "replace" operation.
+/// Return true if `string` is a `#tag` expression.
+#[inline]
+pub(crate) fn is_tag(string: &str) -> bool {
+    string.starts_with('#')
+}
+
+/// Return true if `string` is a variable "append" operation.
 #[inline]
 pub(crate) fn is_append_op(string: &str) -> bool {
     string.ends_with('+')
 }
 
-/// Return true if `string` is a variable "append" operation.
+/// Return true if `string` is a variable "prepend" operation.
+#[inline]
+pub(crate) fn is_prepend_op(string: &str) -> bool {
+    string.ends_with('^')
+}
+
+/// Return true if `string` is a variable "remove" operation.
+#[inline]
+pub(crate) fn is_remove_op(string: &str) -> bool {
+    string.ends_with('-')
+}
+
+/// Return true if `string` is a variable "replace" operation.
 #[inline]
 pub(crate) fn is_replace_op(string: &str) -> bool {
     string.ends_with('=')
@@ -59,10 +83,10 @@ pub(crate) fn is_git_dir(string: &str) -> bool {
     string.len() > 4 && string.ends_with(".git") && !string.ends_with("/.git")
 }
 
-/// Trim garden, group, and tree prefixes
+/// Trim garden, group, tag and tree prefixes
 #[inline]
 pub(crate) fn trim(string: &str) -> &str {
-    let needs_trim = is_group(string) || is_tree(string) || is_garden(string);
+    let needs_trim = is_group(string) || is_tree(string) || is_garden(string) || is_tag(string);
     if !string.is_empty() && needs_trim {
         &string[1..]
     } else {
@@ -70,10 +94,14 @@ pub(crate) fn trim(string: &str) -> &str {
     }
 }
 
-/// Trim the "$ " prefix from an exec expression
+/// Trim the "$ " or "$! " prefix from an exec expression
 #[inline]
 pub(crate) fn trim_exec(string: &str) -> &str {
-    let prefix = "$ ";
+    let prefix = if is_required_exec(string) {
+        "$! "
+    } else {
+        "$ "
+    };
     let prefix_len = prefix.len();
     if string.len() >= prefix_len && string.starts_with(prefix) {
         &string[prefix_len..]
@@ -82,7 +110,7 @@ pub(crate) fn trim_exec(string: &str) -> &str {
     }
 }
 
-/// Trim "+" and "=" suffixes in-place.
+/// Trim "+", "^", "-" and "=" suffixes in-place.
 #[inline]
 pub(crate) fn trim_op_inplace(string: &mut String) {
     let len = string.len();
@@ -184,6 +212,83 @@ pub(crate) fn escape_shell_variables(string: &str) -> String {
     result
 }
 
+/// Return the names and modifiers for the ${variable} expressions in a string.
+/// Escaped $${variable} expressions are ignored. Variables referenced by
+/// modifiers, e.g. ${name:-${default}}, are also returned.
+pub(crate) fn variable_references(string: &str) -> Vec<(&str, &str)> {
+    let mut names = Vec::new();
+    let bytes = string.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'$' && bytes.get(index + 1) == Some(&b'$') {
+            index += 2;
+            continue;
+        }
+        if bytes[index] == b'$' && bytes.get(index + 1) == Some(&b'{') {
+            let end = match find_variable_end(string, index) {
+                Some(end) => end,
+                None => break,
+            };
+            let (name, modifier) = split_variable_modifier(&string[index + 2..end]);
+            names.push((name, modifier));
+            names.extend(variable_references(modifier));
+            index = end + 1;
+            continue;
+        }
+        index += 1;
+    }
+
+    names
+}
+
+/// Return the index of the closing brace for the "${" expression at the specified index.
+/// Nested ${...} expressions are skipped.
+pub(crate) fn find_variable_end(string: &str, start: usize) -> Option<usize> {
+    let bytes = string.as_bytes();
+    let mut depth = 0;
+    let mut index = start;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'$' if bytes.get(index + 1) == Some(&b'$') => index += 1,
+            b'$' if bytes.get(index + 1) == Some(&b'{') => {
+                depth += 1;
+                index += 1;
+            }
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+
+    None
+}
+
+/// Split the body of a ${...} expression into the variable name and its modifier.
+/// "name:-default" returns ("name", ":-default"). The modifier is empty for plain names.
+/// Graft-qualified "graft::name" names are not split.
+pub(crate) fn split_variable_modifier(body: &str) -> (&str, &str) {
+    let bytes = body.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b':' if bytes.get(index + 1) == Some(&b':') => index += 1,
+            b':' if matches!(bytes.get(index + 1), Some(b'-') | Some(b'?')) => {
+                return body.split_at(index);
+            }
+            b'#' | b'%' | b'/' | b'|' => return body.split_at(index),
+            _ => (),
+        }
+        index += 1;
+    }
+
+    (body, "")
+}
+
 /// Return the value of a boolean as a string.
 #[inline]
 pub(crate) fn bool_to_string(value: bool) -> String {
@@ -203,6 +308,22 @@ pub(crate) fn string_to_bool(value: &str) -> Option<bool> {
     }
 }
 
+/// Parse a duration such as "90", "30s", "10m", "2h" or "1d". Plain numbers are seconds.
+pub(crate) fn parse_duration(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim();
+    let (number, seconds_per_unit) = match value.char_indices().last()? {
+        (idx, 's') => (&value[..idx], 1),
+        (idx, 'm') => (&value[..idx], 60),
+        (idx, 'h') => (&value[..idx], 60 * 60),
+        (idx, 'd') => (&value[..idx], 24 * 60 * 60),
+        _ => (value, 1),
+    };
+    let number: u64 = number.trim().parse().ok()?;
+    let seconds = number.checked_mul(seconds_per_unit)?;
+
+    Some(std::time::Duration::from_secs(seconds))
+}
+
 /// Add a pre-command suffix to a command name.
 #[inline]
 pub(crate) fn pre_command(name: &str) -> String {
@@ -313,9 +434,24 @@ mod tests {
         assert_eq!(post, "");
     }
 
+    #[test]
+    fn environment_ops() {
+        assert!(super::is_append_op("PATH+"), "PATH+ appends");
+        assert!(super::is_prepend_op("PATH^"), "PATH^ prepends");
+        assert!(super::is_remove_op("PATH-"), "PATH- removes");
+        assert!(super::is_replace_op("PATH="), "PATH= replaces");
+        assert!(!super::is_remove_op("PATH"), "PATH does not remove");
+
+        let mut name = String::from("PATH-");
+        super::trim_op_inplace(&mut name);
+        assert_eq!(name, "PATH");
+    }
+
     #[test]
     fn trim_exec() {
         assert_eq!("cmd", super::trim_exec("$ cmd"));
+        assert_eq!("cmd", super::trim_exec("$! cmd"));
+        assert_eq!("$!cmd", super::trim_exec("$!cmd"));
         assert_eq!("$cmd", super::trim_exec("$cmd"));
         assert_eq!("cmd", super::trim_exec("cmd"));
         assert_eq!("", super::trim_exec("$ "));
@@ -396,6 +532,46 @@ mod tests {
         assert_eq!("foo", value.unwrap());
     }
 
+    #[test]
+    fn variable_references() {
+        let value = super::variable_references("${a} $b ${graft::c}/${d}");
+        assert_eq!(value, vec![("a", ""), ("graft::c", ""), ("d", "")]);
+
+        let value = super::variable_references("echo $${escaped} ${used}");
+        assert_eq!(value, vec![("used", "")]);
+
+        let value = super::variable_references("${unterminated");
+        assert!(value.is_empty());
+
+        let value = super::variable_references("${a:-${b|upper}} ${c#prefix} ${graft::d%.*}");
+        assert_eq!(
+            value,
+            vec![
+                ("a", ":-${b|upper}"),
+                ("b", "|upper"),
+                ("c", "#prefix"),
+                ("graft::d", "%.*")
+            ]
+        );
+    }
+
+    #[test]
+    fn split_variable_modifier() {
+        assert_eq!(super::split_variable_modifier("name"), ("name", ""));
+        assert_eq!(
+            super::split_variable_modifier("name:-default"),
+            ("name", ":-default")
+        );
+        assert_eq!(super::split_variable_modifier("name:?"), ("name", ":?"));
+        assert_eq!(super::split_variable_modifier("a##*/"), ("a", "##*/"));
+        assert_eq!(super::split_variable_modifier("a/x/y"), ("a", "/x/y"));
+        assert_eq!(super::split_variable_modifier("a|upper"), ("a", "|upper"));
+        assert_eq!(
+            super::split_variable_modifier("graft::name%.txt"),
+            ("graft::name", "%.txt")
+        );
+    }
+
     #[test]
     fn escape_shell_variables() {
         let value = super::escape_shell_variables("$");
@@ -438,4 +614,18 @@ mod tests {
         let value = super::escape_shell_variables("echo $${value[@]:0:1}");
         assert_eq!(value, "echo $${value[@]:0:1}");
     }
+
+    #[test]
+    fn parse_duration() {
+        let duration = |seconds| Some(std::time::Duration::from_secs(seconds));
+        assert_eq!(super::parse_duration("90"), duration(90));
+        assert_eq!(super::parse_duration("30s"), duration(30));
+        assert_eq!(super::parse_duration("10m"), duration(600));
+        assert_eq!(super::parse_duration("2h"), duration(7200));
+        assert_eq!(super::parse_duration("1d"), duration(86400));
+        assert_eq!(super::parse_duration(""), None);
+        assert_eq!(super::parse_duration("m"), None);
+        assert_eq!(super::parse_duration("10x"), None);
+        assert_eq!(super::parse_duration("999999999999999999d"), None);
+    }
 }
@@ -172,6 +172,55 @@ pub fn garden_capture(args: &[&str]) -> String {
     utf8_result.unwrap().trim_end().into()
 }
 
+/// Execute garden and return the exit status and the captured stdout value.
+pub fn garden_capture_status(args: &[&str]) -> (i32, String) {
+    let mut argv: Vec<&str> = vec!["garden"];
+    argv.extend(args);
+    display::print_command_vec(&argv);
+
+    let mut exec = Command::cargo_bin("garden").expect("garden not found");
+    exec.args(args);
+    let capture = exec.output().expect("unable to run garden");
+    let status = capture.status.code().unwrap_or(errors::EX_ERROR);
+    let utf8_result = String::from_utf8(capture.stdout);
+    assert!(utf8_result.is_ok());
+
+    (status, utf8_result.unwrap().trim_end().into())
+}
+
+/// Execute garden and return the exit status and the captured stderr value.
+pub fn garden_capture_stderr(args: &[&str]) -> (i32, String) {
+    let mut argv: Vec<&str> = vec!["garden"];
+    argv.extend(args);
+    display::print_command_vec(&argv);
+
+    let mut exec = Command::cargo_bin("garden").expect("garden not found");
+    exec.args(args);
+    let capture = exec.output().expect("unable to run garden");
+    let status = capture.status.code().unwrap_or(errors::EX_ERROR);
+    let utf8_result = String::from_utf8(capture.stderr);
+    assert!(utf8_result.is_ok());
+
+    (status, utf8_result.unwrap().trim_end().into())
+}
+
+/// Execute garden with additional environment variables and return the captured stdout value.
+pub fn garden_capture_env(args: &[&str], env: &[(&str, &str)]) -> String {
+    let mut argv: Vec<&str> = vec!["garden"];
+    argv.extend(args);
+    display::print_command_vec(&argv);
+
+    let mut exec = Command::cargo_bin("garden").expect("garden not found");
+    exec.args(args);
+    exec.envs(env.iter().copied());
+    let capture = exec.output().expect("unable to run garden");
+    assert!(capture.status.success());
+    let utf8_result = String::from_utf8(capture.stdout);
+    assert!(utf8_result.is_ok());
+
+    utf8_result.unwrap().trim_end().into()
+}
+
 /// Execute a command and ensure that the exit status is returned.
 pub fn assert_cmd_status(cmd: &[&str], directory: &str, status: i32) {
     display::print_command_vec(cmd);
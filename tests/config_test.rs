@@ -85,7 +85,7 @@ fn commands() -> Result<()> {
     let config = app_context.get_root_config();
     assert_eq!(2, config.commands.len());
 
-    assert!(config.commands.get("test_cmd").is_some());
+    assert!(config.commands.contains_key("test_cmd"));
     assert_eq!(
         1,
         config
@@ -100,7 +100,7 @@ fn commands() -> Result<()> {
             .commands
             .get("test_cmd")
             .context("test_cmd command")?
-            .get(0)
+            .first()
             .context("test_cmd[0]")?
             .get_expr()
     );
@@ -269,24 +269,27 @@ fn trees() -> Result<()> {
     let user_name_var = tree0.gitconfig.get("user.name").context("user.name")?;
     assert_eq!(
         "A U Thor",
-        user_name_var.get(0).context("user.name expr")?.get_expr()
+        user_name_var.first().context("user.name expr")?.get_expr()
     );
     assert_eq!(
         None,
         user_name_var
-            .get(0)
+            .first()
             .context("None for user.name value")?
             .get_value()
     );
     let user_email_var = tree0.gitconfig.get("user.email").context("user.email")?;
     assert_eq!(
         "author@example.com",
-        user_email_var.get(0).context("user.email expr")?.get_expr()
+        user_email_var
+            .first()
+            .context("user.email expr")?
+            .get_expr()
     );
     assert_eq!(
         None,
         user_email_var
-            .get(0)
+            .first()
             .context("user.email value")?
             .get_value()
     );
@@ -333,9 +336,9 @@ fn trees() -> Result<()> {
 
     assert_eq!(3, tree1.commands.len());
     // From the tree
-    assert!(tree1.commands.get("build").is_some());
-    assert!(tree1.commands.get("install").is_some());
-    assert!(tree1.commands.get("test").is_some());
+    assert!(tree1.commands.contains_key("build"));
+    assert!(tree1.commands.contains_key("install"));
+    assert!(tree1.commands.contains_key("test"));
     // From the template
     let test_cmd = tree1.commands.get("test").context("test")?;
     assert_eq!(2, test_cmd.len());
@@ -355,7 +358,7 @@ fn trees() -> Result<()> {
     assert_eq!(
         "true",
         annex_ignore_var
-            .get(0)
+            .first()
             .context("remote.origin.annex-ignore expr")?
             .get_expr()
     );
@@ -378,7 +381,7 @@ fn trees() -> Result<()> {
     assert_eq!(
         "true",
         annex_ignore_var
-            .get(0)
+            .first()
             .context("annex-ignore expr")?
             .get_expr()
     );
@@ -498,7 +501,7 @@ fn test_gardens(config: &garden::model::Configuration) -> Result<()> {
         .context("user.name")?;
     assert_eq!(
         "A U Thor",
-        user_name_var.get(0).context("user.name expr")?.get_expr()
+        user_name_var.first().context("user.name expr")?.get_expr()
     );
 
     let user_email_var = config.gardens[1]
@@ -507,7 +510,10 @@ fn test_gardens(config: &garden::model::Configuration) -> Result<()> {
         .context("user.email")?;
     assert_eq!(
         "author@example.com",
-        user_email_var.get(0).context("user.email expr")?.get_expr()
+        user_email_var
+            .first()
+            .context("user.email expr")?
+            .get_expr()
     );
 
     Ok(())
@@ -1,6 +1,6 @@
 pub mod common;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use garden::string;
 
@@ -25,7 +25,7 @@ fn tree_variable() -> Result<()> {
     let config = app_context.get_root_config();
     let tree_name = garden::model::TreeName::from("git");
     let result =
-        garden::eval::tree_value(&app_context, config, None, "${prefix}", &tree_name, None);
+        garden::eval::tree_value(&app_context, config, None, "${prefix}", &tree_name, None)?;
     assert_eq!(result, "/home/test/.local");
 
     Ok(())
@@ -37,10 +37,10 @@ fn config_variable() -> Result<()> {
     let config = app_context.get_root_config();
     let tree_name = garden::model::TreeName::from("git");
 
-    let test = garden::eval::tree_value(&app_context, config, None, "${test}", &tree_name, None);
+    let test = garden::eval::tree_value(&app_context, config, None, "${test}", &tree_name, None)?;
     assert_eq!("TEST", test);
 
-    let local = garden::eval::tree_value(&app_context, config, None, "${local}", &tree_name, None);
+    let local = garden::eval::tree_value(&app_context, config, None, "${local}", &tree_name, None)?;
     assert_eq!("TEST/local", local);
 
     Ok(())
@@ -54,7 +54,7 @@ fn tree_name() -> Result<()> {
     let tree_name = garden::model::TreeName::from("git");
     let expect = "git";
     let actual =
-        garden::eval::tree_value(&app_context, config, None, "${TREE_NAME}", &tree_name, None);
+        garden::eval::tree_value(&app_context, config, None, "${TREE_NAME}", &tree_name, None)?;
     assert_eq!(expect, actual);
 
     Ok(())
@@ -68,7 +68,7 @@ fn tree_path() -> Result<()> {
     let tree_name = garden::model::TreeName::from("git");
     let expect = "/home/test/src/git";
     let actual =
-        garden::eval::tree_value(&app_context, config, None, "${TREE_PATH}", &tree_name, None);
+        garden::eval::tree_value(&app_context, config, None, "${TREE_PATH}", &tree_name, None)?;
     assert_eq!(expect, actual);
 
     Ok(())
@@ -80,7 +80,7 @@ fn garden_path() -> Result<()> {
     let app_context = common::garden_context()?;
     let config = app_context.get_root_config();
     let expect = "/home/test/src";
-    let actual = garden::eval::value(&app_context, config, "${GARDEN_ROOT}");
+    let actual = garden::eval::value(&app_context, config, "${GARDEN_ROOT}")?;
     assert_eq!(expect, actual);
 
     Ok(())
@@ -92,7 +92,7 @@ fn exec_expression() -> Result<()> {
     let config = app_context.get_root_config();
 
     // Simple exec expression
-    let value = garden::eval::value(&app_context, config, "$ echo test");
+    let value = garden::eval::value(&app_context, config, "$ echo test")?;
     assert_eq!(value, "test");
 
     // Exec expression found through variable indirection:
@@ -101,7 +101,7 @@ fn exec_expression() -> Result<()> {
     // Evaluation of ${echo_cmd_exec} produces "$ ${echo_cmd}"
     // which is further evaluated to "$ echo cmd" before getting
     // run through a shell to produce the final result.
-    let value = garden::eval::value(&app_context, config, "${echo_cmd_exec}");
+    let value = garden::eval::value(&app_context, config, "${echo_cmd_exec}")?;
     assert_eq!(value, "cmd");
 
     // Ensure that exec expressions are evaluated in the tree directory.
@@ -113,10 +113,10 @@ fn exec_expression() -> Result<()> {
         "$ echo $PWD",
         &context.tree,
         None,
-    );
+    )?;
     assert!(value == "/tmp" || value == "/private/tmp");
 
-    let value = garden::eval::tree_value(&app_context, config, None, "$ pwd", &context.tree, None);
+    let value = garden::eval::tree_value(&app_context, config, None, "$ pwd", &context.tree, None)?;
     assert!(value == "/tmp" || value == "/private/tmp");
 
     Ok(())
@@ -128,7 +128,7 @@ fn exec_expression_in_tree_context() -> Result<()> {
         garden::model::ApplicationContext::from_path_string("tests/data/garden.yaml")?;
     let config = app_context.get_root_config();
     let context = garden::query::tree_context(&app_context, config, "trees/prebuilt", None)?;
-    let value = garden::eval::tree_value(&app_context, config, None, "$ pwd", &context.tree, None);
+    let value = garden::eval::tree_value(&app_context, config, None, "$ pwd", &context.tree, None)?;
     assert!(value.ends_with("/trees/prebuilt"));
 
     Ok(())
@@ -141,11 +141,11 @@ fn shell_variable_syntax() -> Result<()> {
     let config = app_context.get_root_config();
 
     // Simple exec expression
-    let value = garden::eval::value(&app_context, config, "$ value=$(echo test); echo $value");
+    let value = garden::eval::value(&app_context, config, "$ value=$(echo test); echo $value")?;
     assert_eq!(value, "test");
 
     // Escaped ${braced} value
-    let value = garden::eval::value(&app_context, config, "$ echo '$${value[@]:0:1}'");
+    let value = garden::eval::value(&app_context, config, "$ echo '$${value[@]:0:1}'")?;
     assert_eq!(value, "${value[@]:0:1}");
 
     Ok(())
@@ -162,7 +162,7 @@ fn multi_variable_with_tree() -> Result<()> {
     assert_eq!("PATH", var.get_name());
 
     let context = garden::model::TreeContext::new("cola", None, None, None);
-    let values = garden::eval::multi_variable(&app_context, config, None, &mut var, &context);
+    let values = garden::eval::multi_variable(&app_context, config, None, &mut var, &context)?;
     assert_eq!(
         values,
         [
@@ -185,7 +185,7 @@ fn multi_variable_with_garden() -> Result<()> {
     assert_eq!("PATH", var.get_name());
 
     let context = garden::model::TreeContext::new("cola", None, Some(string!("cola")), None);
-    let values = garden::eval::multi_variable(&app_context, config, None, &mut var, &context);
+    let values = garden::eval::multi_variable(&app_context, config, None, &mut var, &context)?;
     assert_eq!(
         values,
         [
@@ -203,7 +203,7 @@ fn garden_environment() -> Result<()> {
     let config = app_context.get_root_config();
     // cola tree(1) and cola garden(Some(0))
     let context = garden::model::TreeContext::new("cola", None, Some(string!("cola")), None);
-    let values = garden::eval::environment(&app_context, config, &context);
+    let values = garden::eval::environment(&app_context, config, &context)?;
     assert_eq!(values.len(), 9);
 
     let mut idx = 0;
@@ -279,7 +279,7 @@ fn group_environment() -> Result<()> {
     let config = app_context.get_root_config();
     // cola tree(1) + cola group(Some(0))
     let context = garden::model::TreeContext::new("cola", None, None, Some(string!("cola")));
-    let values = garden::eval::environment(&app_context, config, &context);
+    let values = garden::eval::environment(&app_context, config, &context)?;
     assert_eq!(values.len(), 7);
 
     let mut idx = 0;
@@ -343,7 +343,7 @@ fn environment_empty_value() -> Result<()> {
     let app_context = common::garden_context()?;
     let config = app_context.get_root_config();
     let context = garden::query::tree_from_name(config, "tmp", None, None).unwrap();
-    let values = garden::eval::environment(&app_context, config, &context);
+    let values = garden::eval::environment(&app_context, config, &context)?;
     assert_eq!(values.len(), 5);
 
     let mut idx = 0;
@@ -378,7 +378,7 @@ fn command_garden_scope() -> Result<()> {
     let context = garden::model::TreeContext::new("cola", None, Some(string!("cola")), None);
 
     // Garden scope
-    let values = garden::eval::command(&app_context, &context, "build");
+    let values = garden::eval::command(&app_context, &context, "build")?;
     assert_eq!(values.len(), 1);
 
     let cmd_vec = &values[0];
@@ -397,7 +397,7 @@ fn command_tree_scope() -> Result<()> {
 
     // The ${prefix} variable should expand to the tree-local value.
     {
-        let values = garden::eval::command(&app_context, &context, "build");
+        let values = garden::eval::command(&app_context, &context, "build")?;
         assert_eq!(values.len(), 1);
         assert_eq!(values[0].len(), 1);
 
@@ -408,7 +408,7 @@ fn command_tree_scope() -> Result<()> {
     // Commands should include the template commands followed by the
     // tree-specific commands.
     {
-        let values = garden::eval::command(&app_context, &context, "test");
+        let values = garden::eval::command(&app_context, &context, "test")?;
         assert_eq!(values.len(), 1);
 
         assert_eq!(values[0].len(), 2);
@@ -426,7 +426,7 @@ fn environment_variables() -> Result<()> {
     // Environment variables in tree scope
     std::env::set_var("GARDEN_TEST_VALUE", "test");
 
-    let value = garden::eval::value(&app_context, config, "${GARDEN_TEST_VALUE}");
+    let value = garden::eval::value(&app_context, config, "${GARDEN_TEST_VALUE}")?;
     assert_eq!(value, "test");
 
     let value = garden::eval::tree_value(
@@ -436,7 +436,7 @@ fn environment_variables() -> Result<()> {
         "${GARDEN_TEST_VALUE}",
         "git",
         None,
-    );
+    )?;
     assert_eq!(value, "test");
 
     Ok(())
@@ -479,7 +479,7 @@ fn eval_graft_tree() -> Result<()> {
         "${TREE_PATH}",
         &ctx.tree,
         ctx.garden.as_ref(),
-    );
+    )?;
     assert!(path.ends_with("/graft"), "{path} does not end with /graft");
 
     // Evaluate a local variable that is overridden in the graft.
@@ -490,7 +490,7 @@ fn eval_graft_tree() -> Result<()> {
         "${current_config}",
         &ctx.tree,
         ctx.garden.as_ref(),
-    );
+    )?;
     assert_eq!("graft", actual);
 
     // Get a TreeContext for "example/tree".
@@ -507,7 +507,7 @@ fn eval_graft_tree() -> Result<()> {
         "${current_config}",
         &example_ctx.tree,
         example_ctx.garden.as_ref(),
-    );
+    )?;
     assert_eq!("main", actual);
 
     // References to unknown grafts evaluate to an empty string.
@@ -518,7 +518,7 @@ fn eval_graft_tree() -> Result<()> {
         "${undefined::variable}",
         &ctx.tree,
         ctx.garden.as_ref(),
-    );
+    )?;
     assert_eq!("", actual);
 
     // Evaluate a grafted variable from the context of "example/tree" from
@@ -530,7 +530,7 @@ fn eval_graft_tree() -> Result<()> {
         "${graft::current_config}",
         &ctx.tree,
         ctx.garden.as_ref(),
-    );
+    )?;
     assert_eq!("graft", actual);
 
     Ok(())
@@ -543,18 +543,124 @@ fn eval_graft_variables() -> Result<()> {
     let config = app_context.get_root_config();
 
     // Evaluate graft variables one level deep.
-    let actual = garden::eval::value(&app_context, config, "${graft::current_config}");
+    let actual = garden::eval::value(&app_context, config, "${graft::current_config}")?;
     assert_eq!("graft", actual);
 
-    let actual = garden::eval::value(&app_context, config, "${graft::variable}");
+    let actual = garden::eval::value(&app_context, config, "${graft::variable}")?;
     assert_eq!("graft value", actual);
 
     // Evaluate graft variables two levels deep.
-    let actual = garden::eval::value(&app_context, config, "${graft::deps::current_config}");
+    let actual = garden::eval::value(&app_context, config, "${graft::deps::current_config}")?;
     assert_eq!("deps", actual);
 
-    let actual = garden::eval::value(&app_context, config, "${graft::deps::deps_graft_value}");
+    let actual = garden::eval::value(&app_context, config, "${graft::deps::deps_graft_value}")?;
     assert_eq!("deps-graft-value", actual);
 
     Ok(())
 }
+
+/// ${name<modifier>} expressions provide defaults and transform values.
+#[test]
+fn variable_modifiers() -> Result<()> {
+    let string = string!(
+        r#"
+    variables:
+      file: /path/to/archive.tar.gz
+      empty: ""
+    trees:
+      tree:
+        path: ${empty:-default}
+        variables:
+          name: ${file|basename|upper}
+    "#
+    );
+    let app_context = common::garden_context_from_string(&string)?;
+    let config = app_context.get_root_config();
+    let eval = |expr: &str| garden::eval::value(&app_context, config, expr);
+
+    assert_eq!(eval("${empty:-fallback}")?, "fallback");
+    assert_eq!(eval("${missing:-${file|dirname}}")?, "/path/to");
+    assert_eq!(eval("${file:-unused}")?, "/path/to/archive.tar.gz");
+    assert_eq!(eval("${file#*/}")?, "path/to/archive.tar.gz");
+    assert_eq!(eval("${file##*/}")?, "archive.tar.gz");
+    assert_eq!(eval("${file%.*}")?, "/path/to/archive.tar");
+    assert_eq!(eval("${file%%.*}")?, "/path/to/archive");
+    assert_eq!(eval("${file/a/A}")?, "/pAth/to/archive.tar.gz");
+    assert_eq!(eval("${file//a/A}")?, "/pAth/to/Archive.tAr.gz");
+    assert_eq!(eval("${file/.tar}")?, "/path/to/archive.gz");
+    assert_eq!(eval("${file|basename|upper}")?, "ARCHIVE.TAR.GZ");
+    assert_eq!(eval("${file|dirname|lower}")?, "/path/to");
+    assert_eq!(eval("$${file|upper}")?, "${file|upper}");
+
+    // Modifiers are available in tree paths and tree variables.
+    let tree_name = garden::model::TreeName::from("tree");
+    let name = garden::eval::tree_value(&app_context, config, None, "${name}", &tree_name, None)?;
+    assert_eq!(name, "ARCHIVE.TAR.GZ");
+    let tree = config.trees.get("tree").context("tree")?;
+    let path = garden::eval::tree_value(
+        &app_context,
+        config,
+        None,
+        tree.get_path().get_expr(),
+        &tree_name,
+        None,
+    )?;
+    assert_eq!(path, "default");
+
+    Ok(())
+}
+
+/// Environment names support prepend, append, remove and replace operators.
+#[test]
+fn environment_operators() -> Result<()> {
+    let string = string!(
+        r#"
+    garden:
+      env-dedupe: true
+      env-separators:
+        PYTHONPATH: ";"
+    trees:
+      tree:
+        path: /tmp
+        environment:
+          PATH-: /bin
+          PATH+: /usr/bin
+          PATH^: /opt/bin
+          PYTHONPATH: /a
+          PYTHONPATH+: /b
+          PYTHONPATH^: /b
+          GARDEN_TEST_UNSET-: /bin
+    "#
+    );
+    let app_context = common::garden_context_from_string(&string)?;
+    let config = app_context.get_root_config();
+    let context = garden::query::tree_from_name(config, "tree", None, None).context("tree")?;
+    let values = garden::eval::environment(&app_context, config, &context)?;
+    let expect = [
+        ("PATH", "/usr/bin"), // "/bin" is removed from "/usr/bin:/bin"
+        ("PATH", "/usr/bin"), // The duplicate "/usr/bin" entry is removed
+        ("PATH", "/opt/bin:/usr/bin"),
+        ("PYTHONPATH", "/a"),
+        ("PYTHONPATH", "/a;/b"),
+        ("PYTHONPATH", "/b;/a"),
+    ];
+    let values: Vec<(&str, &str)> = values
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(values, expect);
+
+    // Environment values are visible to variable expressions in the tree's context.
+    let tree_name = garden::model::TreeName::from("tree");
+    let value = garden::eval::tree_value(
+        &app_context,
+        config,
+        None,
+        "${PYTHONPATH}",
+        &tree_name,
+        None,
+    )?;
+    assert_eq!(value, "/b;/a");
+
+    Ok(())
+}
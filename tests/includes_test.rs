@@ -11,13 +11,13 @@ fn read_includes() -> Result<()> {
     let config = app_context.get_root_config();
 
     // var_0 is from the included variables.yaml..
-    let actual = garden::eval::value(&app_context, config, "${var_0}");
+    let actual = garden::eval::value(&app_context, config, "${var_0}")?;
     assert_eq!(actual, "zero");
     // var_1 is provided by variables-transitive.yaml and overridden by includes.yaml.
-    let actual = garden::eval::value(&app_context, config, "${var_1}");
+    let actual = garden::eval::value(&app_context, config, "${var_1}")?;
     assert_eq!(actual, "ONE");
     // var_2 is provided by variables-transitive.yaml.
-    let actual = garden::eval::value(&app_context, config, "${var_2}");
+    let actual = garden::eval::value(&app_context, config, "${var_2}")?;
     assert_eq!(actual, "two");
 
     assert!(config.trees.contains_key("tree-zero")); // includes/trees.yaml
@@ -37,7 +37,7 @@ fn read_includes() -> Result<()> {
     // Nested include files are relative to the file that included them.
     // If the nested include file is not found relative to the parent include file
     // then a file relative to the config directory can be used.
-    let actual = garden::eval::value(&app_context, config, "${var_included}");
+    let actual = garden::eval::value(&app_context, config, "${var_included}")?;
     assert_eq!(actual, "relative to config");
 
     Ok(())
@@ -60,7 +60,7 @@ fn template_includes() -> Result<()> {
         "${template-variable}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "template");
     let constant = garden::eval::tree_value(
         &app_context,
@@ -69,7 +69,7 @@ fn template_includes() -> Result<()> {
         "${template-constant}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(constant, "constant");
     assert_eq!(1, tree.commands.len());
     let echo_cmd_opt = tree.commands.get("echo");
@@ -92,7 +92,7 @@ fn template_includes() -> Result<()> {
         "${template-variable}",
         &context.tree,
         None,
-    );
+    )?;
     let constant = garden::eval::tree_value(
         &app_context,
         config,
@@ -100,7 +100,7 @@ fn template_includes() -> Result<()> {
         "${template-constant}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "extended");
     assert_eq!(constant, "constant");
     assert_eq!(tree.commands.len(), 1);
@@ -125,7 +125,7 @@ fn template_includes() -> Result<()> {
         "${template-variable}",
         &context.tree,
         None,
-    );
+    )?;
     let constant = garden::eval::tree_value(
         &app_context,
         config,
@@ -133,7 +133,7 @@ fn template_includes() -> Result<()> {
         "${template-constant}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(constant, "constant");
     assert_eq!(result, "nested");
 
@@ -158,7 +158,7 @@ fn template_includes() -> Result<()> {
         "${template-variable}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "extended-tree");
 
     let result = garden::eval::tree_value(
@@ -168,7 +168,7 @@ fn template_includes() -> Result<()> {
         "${tree-variable}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "nested");
 
     let result = garden::eval::tree_value(
@@ -178,7 +178,7 @@ fn template_includes() -> Result<()> {
         "${tree-override}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "extended-tree");
 
     // Test a tree that uses "extend" on a tree defined via an include file.
@@ -192,7 +192,7 @@ fn template_includes() -> Result<()> {
         "${template-variable}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "top-level");
 
     let result = garden::eval::tree_value(
@@ -202,7 +202,7 @@ fn template_includes() -> Result<()> {
         "${tree-override}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "top-level");
 
     // "tree-variable" is provided by "tree-echo-nested" via "extend" and is not overriden.
@@ -213,7 +213,7 @@ fn template_includes() -> Result<()> {
         "${tree-variable}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "nested");
 
     // "extended-variable" is provided by the inner-most "tree-echo-extended-tree".
@@ -226,7 +226,7 @@ fn template_includes() -> Result<()> {
         "${extended-variable}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "extended-tree");
 
     // "replacement-tree" is not sparsely overriden -- it is replaced. The variables should
@@ -239,7 +239,7 @@ fn template_includes() -> Result<()> {
         "${tree-variable}",
         &context.tree,
         None,
-    );
+    )?;
     assert_eq!(result, "");
 
     let replacement_tree = config
@@ -275,8 +275,8 @@ fn command_overrides() -> Result<()> {
     let app_context = common::garden_context_from_string(&string)?;
     let config = app_context.get_root_config();
     assert_eq!(config.commands.len(), 2);
-    assert!(config.commands.get("echo").is_some());
-    assert!(config.commands.get("test").is_some());
+    assert!(config.commands.contains_key("echo"));
+    assert!(config.commands.contains_key("test"));
 
     let echo_cmd = config.commands.get("echo").context("echo")?;
     assert_eq!(1, echo_cmd.len());
@@ -307,8 +307,8 @@ fn command_overrides() -> Result<()> {
     let app_context = common::garden_context_from_string(&string)?;
     let config = app_context.get_root_config();
     assert_eq!(config.commands.len(), 2);
-    assert!(config.commands.get("echo").is_some());
-    assert!(config.commands.get("test").is_some());
+    assert!(config.commands.contains_key("echo"));
+    assert!(config.commands.contains_key("test"));
 
     let echo_cmd = config.commands.get("echo").context("echo")?;
     assert_eq!(1, echo_cmd.len());
@@ -332,8 +332,8 @@ fn command_overrides() -> Result<()> {
     let app_context = common::garden_context_from_string(&string)?;
     let config = app_context.get_root_config();
     assert_eq!(config.commands.len(), 2);
-    assert!(config.commands.get("echo").is_some());
-    assert!(config.commands.get("test").is_some());
+    assert!(config.commands.contains_key("echo"));
+    assert!(config.commands.contains_key("test"));
 
     let echo_cmd = config.commands.get("echo").context("echo")?;
     assert_eq!(1, echo_cmd.len());
@@ -341,3 +341,36 @@ fn command_overrides() -> Result<()> {
 
     Ok(())
 }
+
+/// Ensure that the file and line for entries are recorded.
+#[test]
+fn source_locations() -> Result<()> {
+    let app_context =
+        garden::model::ApplicationContext::from_path_string("tests/data/garden.yaml")?;
+    let config = app_context.get_root_config();
+
+    // Entries from included files record the included file.
+    let source = config.get_source("trees.tree-zero.path").context("path")?;
+    assert!(source.path.ends_with("tests/data/trees.yaml"));
+    assert_eq!(source.line, 4);
+
+    let source = config.get_source("variables.var_0").context("var_0")?;
+    assert!(source.path.ends_with("tests/data/variables.yaml"));
+    assert_eq!(source.line, 3);
+
+    // Entries in the current file take precedence over entries from includes.
+    let source = config
+        .get_source("trees.tree-echo-extended-tree.extend")
+        .context("extend")?;
+    assert!(source.path.ends_with("tests/data/garden.yaml"));
+    assert_eq!(source.line, 132);
+
+    // Fields that were not defined report the location of their parent entry.
+    let source = config.get_source("trees.tree-zero.url").context("url")?;
+    assert!(source.path.ends_with("tests/data/trees.yaml"));
+    assert_eq!(source.line, 3);
+
+    assert!(config.get_source("trees.missing").is_none());
+
+    Ok(())
+}
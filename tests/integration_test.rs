@@ -1,7 +1,7 @@
 pub mod common;
 use common::{
     assert_cmd, assert_cmd_capture, assert_ref, assert_ref_missing, exec_garden, garden_capture,
-    BareRepoFixture,
+    garden_capture_env, garden_capture_status, garden_capture_stderr, BareRepoFixture,
 };
 
 use garden::{git, model};
@@ -493,6 +493,62 @@ fn grow_worktree_and_parent() -> Result<()> {
     Ok(())
 }
 
+/// `garden grow --dry-run` prints commands without creating trees.
+#[test]
+#[named]
+fn grow_dry_run() -> Result<()> {
+    let fixture = BareRepoFixture::new(function_name!());
+    // garden grow --dry-run dev default
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "grow",
+        "--dry-run",
+        "dev",
+        "default",
+    ]);
+    // The parent repository is cloned once, before the child worktree is added.
+    assert_eq!(1, output.matches(": git clone").count());
+    let clone_pos = output.find(": git clone").unwrap_or(usize::MAX);
+    let worktree_pos = output
+        .find(": git worktree add --track -b dev ../dev origin/dev")
+        .unwrap_or(0);
+    assert!(clone_pos < worktree_pos);
+    // Nothing was created.
+    assert!(!fixture.root_pathbuf().join("default").exists());
+    assert!(!fixture.root_pathbuf().join("dev").exists());
+
+    Ok(())
+}
+
+/// `garden grow --jobs` clones repositories concurrently and grows worktrees afterwards.
+#[test]
+#[named]
+fn grow_jobs() -> Result<()> {
+    let fixture = BareRepoFixture::new(function_name!());
+    // garden grow --jobs 2 dev default
+    exec_garden(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "grow",
+        "--jobs",
+        "2",
+        "dev",
+        "default",
+    ])?;
+
+    let worktree_default = fixture.worktree("default");
+    let worktree_dev = fixture.worktree("dev");
+    assert_ref(&worktree_default, "default");
+    assert_ref(&worktree_dev, "dev");
+
+    Ok(())
+}
+
 /// `garden grow` uses the configured default remote when just "url" is configured.
 #[test]
 #[named]
@@ -676,6 +732,110 @@ fn eval_override_variables() {
     assert_eq!(output, "test");
 }
 
+/// `${name:?message}` reports an error when the variable is empty or unset.
+#[test]
+fn eval_required_variable() {
+    let (status, output) = garden_capture_status(&[
+        "--config",
+        "tests/data/garden.yaml",
+        "eval",
+        "${missing-variable:?must be set}",
+    ]);
+    assert_eq!(status, garden::errors::EX_CONFIG);
+    assert_eq!(output, "");
+
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/garden.yaml",
+        "eval",
+        "${current_config:?must be set}",
+    ]);
+    assert_eq!(output, "main");
+}
+
+/// Failing "$ exec" expressions are empty while failing "$! exec" expressions are errors.
+#[test]
+fn eval_exec_expressions() {
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/exec.yaml",
+        "eval",
+        "${exec-value}:${exec-failure}:${exec-required-value}",
+    ]);
+    assert_eq!(output, "value::required");
+
+    let (status, output) = garden_capture_status(&[
+        "--config",
+        "tests/data/exec.yaml",
+        "eval",
+        "${exec-required}",
+    ]);
+    assert_eq!(status, garden::errors::EX_CONFIG);
+    assert_eq!(output, "");
+
+    // garden.exec-timeout stops exec expressions that run for too long.
+    let start = std::time::Instant::now();
+    let (status, output) = garden_capture_status(&[
+        "--config",
+        "tests/data/exec.yaml",
+        "eval",
+        "${exec-timeout}",
+    ]);
+    assert_eq!(status, garden::errors::EX_CONFIG);
+    assert_eq!(output, "");
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+}
+
+/// "garden --debug timing" reports the time spent running commands and exec expressions.
+#[test]
+fn cmd_debug_timing() {
+    let (status, output) = garden_capture_stderr(&[
+        "--config",
+        "tests/data/exec.yaml",
+        "--debug",
+        "timing",
+        "cmd",
+        "exec",
+        "echo-value",
+    ]);
+    assert_eq!(status, garden::errors::EX_OK);
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("debug: timing: config: ")));
+    assert!(lines.iter().any(|line| {
+        line.starts_with("debug: timing: exec: ") && line.ends_with(": $ echo value")
+    }));
+    assert!(lines.iter().any(|line| {
+        line.starts_with("debug: timing: command: ") && line.ends_with(": echo value")
+    }));
+}
+
+/// Exec expressions with a "cache" setting are cached until "garden cache clear".
+#[test]
+#[named]
+fn eval_exec_cache() {
+    let fixture = BareRepoFixture::new(function_name!());
+    let cache_home = std::fs::canonicalize(fixture.root()).unwrap();
+    let cache_home = cache_home.join("cache");
+    let env = [("XDG_CACHE_HOME", cache_home.to_str().unwrap())];
+    let eval = [
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/exec.yaml",
+        "eval",
+        "${exec-cached}",
+    ];
+    // The command runs once and subsequent evaluations use the cached value.
+    assert_eq!(garden_capture_env(&eval, &env), "1");
+    assert_eq!(garden_capture_env(&eval, &env), "1");
+    assert!(cache_home.join("garden/exec").exists());
+
+    garden_capture_env(&["cache", "clear"], &env);
+    assert_eq!(garden_capture_env(&eval, &env), "2");
+}
+
 /// `garden -D value=expression` evaluates the expression.
 #[test]
 fn eval_override_expressions() {
@@ -715,6 +875,86 @@ fn eval_default_config_dir() {
     assert!(output.ends_with("/tests/data/config"));
 }
 
+/// `garden env` prints tree environments using shell, fish, .env and JSON syntax.
+#[test]
+fn env_formats() {
+    let env = |format: &str, query: &str| {
+        garden_capture(&[
+            "--config",
+            "tests/data/garden.yaml",
+            "env",
+            "--format",
+            format,
+            query,
+            "trees/prebuilt",
+        ])
+    };
+    let output = env("sh", "trees/prebuilt");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("export GARDEN_ENV_PATH='/"));
+    assert_eq!(
+        lines[1],
+        "export GARDEN_ENV_VALUE='trees/prebuilt/env/value'"
+    );
+
+    let output = env("fish", "trees/prebuilt");
+    assert!(output.ends_with("set -gx GARDEN_ENV_VALUE 'trees/prebuilt/env/value'"));
+
+    let output = env("dotenv", "trees/prebuilt");
+    assert!(output.ends_with("GARDEN_ENV_VALUE=\"trees/prebuilt/env/value\""));
+
+    // Variables that are updated by several trees are printed once.
+    let output = env("json", "garden/env");
+    let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        value["GARDEN_ENV_VALUE"],
+        "garden/env:graft/grafted-env/env/value:trees/prebuilt/env/value"
+    );
+    assert_eq!(value.as_object().unwrap().len(), 2);
+}
+
+/// `garden hook` loads tree environments and restores the previous values when leaving.
+#[test]
+fn hook_export_restores_environment() {
+    let export = |directory: &str, state: &str| {
+        garden_capture_env(
+            &[
+                "--root",
+                "tests/data",
+                "--config",
+                "tests/data/garden.yaml",
+                "--chdir",
+                directory,
+                "hook",
+                "bash",
+                "--export",
+            ],
+            &[("GARDEN_HOOK_STATE", state)],
+        )
+    };
+    let output = export("tests/data/trees/prebuilt", "");
+    assert!(output.contains("export GARDEN_ENV_VALUE='trees/prebuilt/env/value'\n"));
+    let state = output
+        .lines()
+        .find_map(|line| line.strip_prefix("export GARDEN_HOOK_STATE="))
+        .unwrap()
+        .trim_matches('\'');
+    assert!(state.contains("\"tree\":\"trees/prebuilt\""));
+
+    // Nothing changes while inside of the same tree.
+    let output = export("tests/data/trees/prebuilt", state);
+    assert_eq!(output, "");
+
+    // Variables are unset when leaving the tree.
+    let output = export("tests/data/trees", state);
+    assert!(output.contains("unset GARDEN_ENV_PATH\n"));
+    assert!(output.contains("unset GARDEN_ENV_VALUE\n"));
+
+    let output = garden_capture(&["hook", "bash"]);
+    assert!(output.contains("hook bash --export"));
+}
+
 /// `garden eval` evaluates variables from "environment" blocks.
 #[test]
 fn eval_environment_tree_names() {
@@ -905,6 +1145,81 @@ fn git_worktree_details() -> Result<()> {
     Ok(())
 }
 
+/// `garden::git::status(path)` returns upstream, staged, dirty, untracked and stash counts.
+/// `garden status` displays the status for each tree.
+#[test]
+#[named]
+fn git_status() -> Result<()> {
+    let fixture = BareRepoFixture::new(function_name!());
+    // garden grow default
+    exec_garden(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "grow",
+        "default",
+    ])?;
+    let worktree = fixture.worktree("default");
+    let status = git::status(&fixture.pathbuf("default"))?;
+    assert_eq!(
+        status,
+        model::GitTreeStatus {
+            upstream: Some((0, 0)),
+            ..Default::default()
+        }
+    );
+
+    // Create a stash, a local commit, a staged file, a modified file and untracked files.
+    let cmd = ["git", "config", "user.name", "A U Thor"];
+    assert_cmd(&cmd, &worktree);
+    let cmd = ["git", "config", "user.email", "author@example.com"];
+    assert_cmd(&cmd, &worktree);
+    std::fs::write(format!("{worktree}/stashed"), "stashed")?;
+    let cmd = ["git", "stash", "push", "--quiet", "--include-untracked"];
+    assert_cmd(&cmd, &worktree);
+    std::fs::write(format!("{worktree}/committed"), "committed")?;
+    let cmd = ["git", "add", "committed"];
+    assert_cmd(&cmd, &worktree);
+    let cmd = ["git", "commit", "--quiet", "-m", "committed"];
+    assert_cmd(&cmd, &worktree);
+    std::fs::write(format!("{worktree}/staged"), "staged")?;
+    let cmd = ["git", "add", "staged"];
+    assert_cmd(&cmd, &worktree);
+    std::fs::write(format!("{worktree}/committed"), "modified")?;
+    std::fs::write(format!("{worktree}/untracked1"), "untracked")?;
+    std::fs::write(format!("{worktree}/untracked2"), "untracked")?;
+
+    let status = git::status(&fixture.pathbuf("default"))?;
+    assert_eq!(
+        status,
+        model::GitTreeStatus {
+            upstream: Some((1, 0)),
+            staged: 1,
+            dirty: 1,
+            untracked: 2,
+            stashes: 1,
+            detached: false,
+        }
+    );
+
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "--quiet",
+        "status",
+        "default",
+    ]);
+    assert_eq!(
+        output,
+        "default  default  1      0       1       1      2          1"
+    );
+
+    Ok(())
+}
+
 /// Test eval behavior around the "--root" option
 #[test]
 fn eval_root_with_root() {
@@ -1386,6 +1701,97 @@ fn cmd_breadth_first_and_depth_first() {
     assert_eq!(expect, actual);
 }
 
+/// Test "garden cmd --jobs ..." and "garden <custom> --jobs ..."
+#[test]
+fn cmd_jobs() {
+    // Output is displayed in tree order when commands run in parallel.
+    let expect = "tree1\nx1\ntree2\nx2";
+    let actual = garden_capture(&[
+        "--chdir",
+        "tests/data",
+        "--quiet",
+        "cmd",
+        "--jobs",
+        "2",
+        "trees",
+        "tree-name",
+        "tree-var",
+    ]);
+    assert_eq!(expect, actual);
+
+    let expect = "tree1\ntree2\nx1\nx2";
+    let actual = garden_capture(&[
+        "--chdir",
+        "tests/data",
+        "--quiet",
+        "cmd",
+        "--breadth-first",
+        "-j2",
+        "trees",
+        "tree-name",
+        "tree-var",
+    ]);
+    assert_eq!(expect, actual);
+
+    // --keep-going and --no-errexit behave the same as sequential runs.
+    let output = garden_capture(&[
+        "--chdir",
+        "tests/data",
+        "--quiet",
+        "error-command",
+        "--jobs",
+        "2",
+        "--keep-going",
+        "--no-errexit",
+        "tree1",
+        "tree2",
+    ]);
+    assert_eq!(output, "ok\nafter error\nok\nafter error");
+}
+
+/// Test the --summary-json results for "garden cmd".
+#[test]
+fn cmd_summary_json() {
+    let output = garden_capture(&[
+        "--chdir",
+        "tests/data",
+        "--quiet",
+        "cmd",
+        "--keep-going",
+        "--summary-json",
+        "-",
+        "--trees",
+        "tree1",
+        "trees",
+        "tree-name",
+        "error-command",
+    ]);
+    // Command output is followed by the JSON summary.
+    let json_start = output.find('{').unwrap_or_default();
+    assert_eq!(&output[..json_start], "tree1\nok\n");
+    let summary: serde_json::Value =
+        serde_json::from_str(&output[json_start..]).unwrap_or_default();
+    assert_eq!(summary["passed"], 1);
+    assert_eq!(summary["failed"], 1);
+    assert_eq!(summary["skipped"], 1);
+
+    let results = &summary["results"];
+    assert_eq!(results[0]["tree"], "tree1");
+    assert_eq!(results[0]["command"], "tree-name");
+    assert_eq!(results[0]["status"], "passed");
+    assert_eq!(results[1]["tree"], "tree1");
+    assert_eq!(results[1]["command"], "error-command");
+    assert_eq!(results[1]["status"], "failed");
+    assert_eq!(results[1]["exit_status"], 1);
+    assert!(results[1]["failed_command"]
+        .as_str()
+        .unwrap_or_default()
+        .starts_with("echo ok\nfalse\n"));
+    assert_eq!(results[2]["tree"], "tree2");
+    assert_eq!(results[2]["status"], "skipped");
+    assert_eq!(results[2]["reason"], "filtered");
+}
+
 /// Test -n / --no-errexit and the shell "-e" behavior.
 #[test]
 fn cmd_no_errexit() {
@@ -1603,6 +2009,170 @@ fn cmd_pre_and_post_commands() {
     assert_eq!(output, "pre\ncmd\ndata\npost");
 }
 
+/// Commands defined using a mapping declare options and arguments that are
+/// available to the command as variables.
+#[test]
+fn cmd_declared_options_and_arguments() {
+    let deploy = |args: &[&str]| {
+        let mut argv = vec!["--config", "tests/data/commands.yaml", "--quiet", "deploy"];
+        argv.extend(args);
+        garden_capture_status(&argv)
+    };
+    let (status, output) = deploy(&["production", "data", "-r", "us-west", "--tag", "v1"]);
+    assert_eq!(status, garden::errors::EX_OK);
+    assert_eq!(output, "production us-west v1");
+
+    // Options use their default values when unspecified.
+    let (status, output) = deploy(&["staging", "data"]);
+    assert_eq!(status, garden::errors::EX_OK);
+    assert_eq!(output, "staging us-east");
+
+    // Default values do not replace values provided using "-D".
+    let define = |args: &[&str]| {
+        let mut argv = vec![
+            "--config",
+            "tests/data/commands.yaml",
+            "--quiet",
+            "--define",
+            "region=us-west",
+            "deploy",
+        ];
+        argv.extend(args);
+        garden_capture(&argv)
+    };
+    assert_eq!(define(&["staging", "data"]), "staging us-west");
+    assert_eq!(
+        define(&["staging", "data", "-r", "us-east"]),
+        "staging us-east"
+    );
+
+    // Required arguments and choices are enforced. Usage errors exit with status 2.
+    let (status, output) = deploy(&[]);
+    assert_eq!(status, 2);
+    assert_eq!(output, "");
+    let (status, output) = deploy(&["qa", "data"]);
+    assert_eq!(status, 2);
+    assert_eq!(output, "");
+
+    // "garden cmd" uses the default values and values provided using "-D".
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/commands.yaml",
+        "--quiet",
+        "--define",
+        "environment=staging",
+        "cmd",
+        "data",
+        "deploy",
+    ]);
+    assert_eq!(output, "staging us-east");
+
+    // Descriptions are displayed by "garden ls" and "garden <command> --help".
+    let output = garden_capture(&["--config", "tests/data/commands.yaml", "ls"]);
+    assert!(output.contains("  - deploy: Deploy trees to an environment"));
+    assert!(output.contains("  - simple"));
+    let output = garden_capture(&["--config", "tests/data/commands.yaml", "deploy", "--help"]);
+    assert!(output.starts_with("Deploy trees to an environment"));
+    assert!(output.contains("Usage: garden deploy [OPTIONS] <environment> [QUERIES]..."));
+}
+
+/// Problems with declared options and arguments report where they are defined.
+#[test]
+fn cmd_declared_options_problems() {
+    let (status, output) =
+        garden_capture_stderr(&["--config", "tests/data/check.yaml", "deploy", "tree"]);
+    assert_eq!(status, garden::errors::EX_CONFIG);
+    assert!(output.ends_with(
+        "commands.deploy.options.jobs: name is already in use \
+        (commands.deploy.options.jobs defined at tests/data/check.yaml:29)"
+    ));
+}
+
+/// Mappings are only valid for commands. Descriptions, options and arguments
+/// can only be declared by top-level commands.
+#[test]
+fn invalid_command_mappings() {
+    let (status, output) =
+        garden_capture_stderr(&["--config", "tests/data/invalid-gitconfig.yaml", "ls"]);
+    assert_eq!(status, garden::errors::EX_ERROR);
+    assert!(output.ends_with("error: invalid variables"));
+
+    let (status, output) =
+        garden_capture_stderr(&["--config", "tests/data/invalid-tree-commands.yaml", "ls"]);
+    assert_eq!(status, garden::errors::EX_ERROR);
+    assert!(output.ends_with(
+        "error: invalid commands: deploy: \"description\" is only supported by top-level commands"
+    ));
+}
+
+/// Commands can override the shell, working directory and environment.
+#[test]
+fn cmd_shell_cwd_and_environment_overrides() {
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/commands.yaml",
+        "--quiet",
+        "cmd",
+        "data",
+        "settings",
+        "tree-settings",
+        "simple",
+    ]);
+    assert_eq!(output, "trees\ntree:command\npipefail\ncommands\nsimple");
+
+    // The tree environment is not modified by the command environment.
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/commands.yaml",
+        "--quiet",
+        "exec",
+        "data",
+        "sh",
+        "-c",
+        "echo $LABEL",
+    ]);
+    assert_eq!(output, "tree");
+
+    // Commands run in the "cwd" directory and report it when verbose.
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/commands.yaml",
+        "-vv",
+        "cmd",
+        "data",
+        "tree-settings",
+    ]);
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].starts_with(": cd ") && lines[0].ends_with("/tests/data/commands"));
+
+    let (status, output) = garden_capture_stderr(&[
+        "--config",
+        "tests/data/commands.yaml",
+        "--debug",
+        "timing",
+        "cmd",
+        "data",
+        "tree-settings",
+    ]);
+    assert_eq!(status, garden::errors::EX_OK);
+    assert!(output.lines().any(|line| {
+        line.starts_with("debug: timing: command: ")
+            && line.ends_with("/tests/data/commands: basename \"$PWD\"")
+    }));
+
+    // Missing "cwd" directories are reported as errors.
+    let (status, output) = garden_capture_stderr(&[
+        "--config",
+        "tests/data/commands.yaml",
+        "cmd",
+        "data",
+        "missing-cwd",
+    ]);
+    assert_eq!(status, garden::errors::EX_CONFIG);
+    assert!(output.contains("error: missing-cwd: directory does not exist: "));
+    assert!(output.ends_with("/tests/data/nope"));
+}
+
 /// Test pre and post nested commands
 #[test]
 fn cmd_pre_and_post_nested_commands() {
@@ -2221,3 +2791,420 @@ fn cmd_prune_depth() -> Result<()> {
 
     Ok(())
 }
+
+/// Test "garden ls --format json" and "garden ls --format yaml".
+#[test]
+fn ls_format_json_and_yaml() -> Result<()> {
+    let output = garden_capture(&[
+        "--chdir",
+        "tests/data",
+        "ls",
+        "--format",
+        "json",
+        "tree1",
+        "example/tree",
+        "graft::prebuilt",
+    ]);
+    let value: serde_json::Value = serde_json::from_str(&output)?;
+    let trees = value.as_array().expect("ls --format json emits an array");
+    assert_eq!(trees.len(), 3);
+
+    assert_eq!(trees[0]["name"], "tree1");
+    assert_eq!(trees[0]["exists"], true);
+    assert_eq!(trees[0]["groups"], serde_json::json!(["trees"]));
+    assert_eq!(trees[0]["gardens"], serde_json::json!([]));
+    assert!(trees[0]["graft"].is_null());
+    assert!(trees[0]["worktree"].is_null());
+
+    assert_eq!(trees[1]["name"], "example/tree");
+    assert_eq!(trees[1]["exists"], false);
+    assert!(trees[1]["branch"].is_null());
+    assert!(trees[1]["path"]
+        .as_str()
+        .is_some_and(|path| path.ends_with("/tests/data/example/tree/repo")));
+    assert_eq!(
+        trees[1]["remotes"]["publish"],
+        "git@github.com:user/example.git"
+    );
+
+    assert_eq!(trees[2]["name"], "prebuilt");
+    assert_eq!(trees[2]["graft"], "graft");
+    assert_eq!(trees[2]["groups"], serde_json::json!(["grafted-group"]));
+    assert_eq!(trees[2]["gardens"], serde_json::json!(["grafted-garden"]));
+
+    let output = garden_capture(&[
+        "--chdir",
+        "tests/data",
+        "ls",
+        "--format",
+        "yaml",
+        "example/tree",
+    ]);
+    assert!(output.starts_with("---\n- name: example/tree\n"));
+    assert!(output.contains("\n  exists: false\n"));
+    assert!(output.contains("\n    publish: \"git@github.com:user/example.git\"\n"));
+
+    Ok(())
+}
+
+/// Test "garden sync" fast-forwards clean branches and reports diverged,
+/// dirty and detached trees.
+#[test]
+#[named]
+fn sync_fast_forward() -> Result<()> {
+    let fixture = BareRepoFixture::new(function_name!());
+    // garden grow default dev
+    exec_garden(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "grow",
+        "default",
+        "dev",
+    ])?;
+    let worktree = fixture.worktree("default");
+
+    // Nothing to do when the trees are up to date.
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "sync",
+        "default",
+        "dev",
+    ]);
+    assert_eq!(output, "up to date\nup to date");
+
+    // Push a new commit to the "default" branch from a separate clone.
+    let cmd = ["git", "clone", "--quiet", "repos/example.git", "upstream"];
+    assert_cmd(&cmd, &fixture.root());
+    let upstream = fixture.worktree("upstream");
+    let cmd = [
+        "git",
+        "-c",
+        "user.name=A U Thor",
+        "-c",
+        "user.email=author@example.com",
+        "commit",
+        "--quiet",
+        "--allow-empty",
+        "-m",
+        "upstream",
+    ];
+    assert_cmd(&cmd, &upstream);
+    let cmd = ["git", "push", "--quiet", "origin", "default"];
+    assert_cmd(&cmd, &upstream);
+
+    // Dirty trees are not updated.
+    std::fs::write(format!("{worktree}/README"), "modified")?;
+    let cmd = ["git", "add", "README"];
+    assert_cmd(&cmd, &worktree);
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "sync",
+        "default",
+    ]);
+    assert_eq!(output, "dirty: behind 1");
+
+    // Clean trees are fast-forwarded. "garden pull" is an alias for "garden sync".
+    let cmd = ["git", "reset", "--quiet", "--hard"];
+    assert_cmd(&cmd, &worktree);
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "pull",
+        "default",
+    ]);
+    assert_eq!(output, "fast-forwarded 1");
+    let upstream_head = assert_cmd_capture(&["git", "rev-parse", "HEAD"], &upstream);
+    let head = assert_cmd_capture(&["git", "rev-parse", "HEAD"], &worktree);
+    assert_eq!(head, upstream_head);
+
+    // Detached HEADs are not updated.
+    let cmd = ["git", "checkout", "--quiet", "--detach"];
+    assert_cmd(&cmd, &worktree);
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "sync",
+        "default",
+    ]);
+    assert_eq!(output, "detached HEAD");
+
+    Ok(())
+}
+
+/// `garden lock` records tree commits and `garden grow --locked` checks them out.
+#[test]
+#[named]
+fn lock_and_grow_locked() -> Result<()> {
+    let fixture = BareRepoFixture::new(function_name!());
+    std::fs::copy(
+        "tests/data/worktree.yaml",
+        format!("{}/garden.yaml", fixture.root()),
+    )?;
+    // garden grow default
+    exec_garden(&["--chdir", &fixture.root(), "grow", "default"])?;
+    let worktree = fixture.worktree("default");
+    let cmd = ["git", "rev-parse", "HEAD"];
+    let locked_commit = assert_cmd_capture(&cmd, &worktree);
+
+    // garden lock default
+    exec_garden(&["--chdir", &fixture.root(), "lock", "default"])?;
+    let lockfile = std::fs::read_to_string(fixture.path("garden.lock"))?;
+    assert_eq!(
+        lockfile,
+        format!("---\ntrees:\n  default:\n    commit: {locked_commit}\n")
+    );
+
+    // Create a new commit so that HEAD no longer matches the locked commit.
+    let cmd = [
+        "git",
+        "-c",
+        "user.name=A U Thor",
+        "-c",
+        "user.email=author@example.com",
+        "commit",
+        "--quiet",
+        "--allow-empty",
+        "-m",
+        "unlocked",
+    ];
+    assert_cmd(&cmd, &worktree);
+    let cmd = ["git", "rev-parse", "HEAD"];
+    assert_ne!(locked_commit, assert_cmd_capture(&cmd, &worktree));
+
+    // garden grow --locked default
+    exec_garden(&["--chdir", &fixture.root(), "grow", "--locked", "default"])?;
+    assert_eq!(locked_commit, assert_cmd_capture(&cmd, &worktree));
+
+    Ok(())
+}
+
+/// `garden log` and `garden diff` run Git commands across trees and skip empty output.
+#[test]
+#[named]
+fn log_and_diff() -> Result<()> {
+    let fixture = BareRepoFixture::new(function_name!());
+    // garden grow default dev
+    exec_garden(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "grow",
+        "default",
+        "dev",
+    ])?;
+
+    // garden log default -1 --format=%s
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "log",
+        "default",
+        "-1",
+        "--format=%s",
+    ]);
+    let worktree = fixture.worktree("default");
+    let cmd = ["git", "log", "-1", "--format=%s"];
+    assert_eq!(output, assert_cmd_capture(&cmd, &worktree));
+
+    // Trees without changes produce no output.
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "diff",
+        "*",
+        "--stat",
+    ]);
+    assert_eq!(output, "");
+
+    // Only trees with changes are displayed.
+    std::fs::write(format!("{worktree}/README"), "modified\n")?;
+    let cmd = ["git", "add", "README"];
+    assert_cmd(&cmd, &worktree);
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "diff",
+        "*",
+        "--cached",
+        "--name-only",
+    ]);
+    assert_eq!(output, "README");
+
+    Ok(())
+}
+
+/// Test the "--changed-since" post-query filter.
+#[test]
+#[named]
+fn exec_changed_since() -> Result<()> {
+    let fixture = BareRepoFixture::new(function_name!());
+    // garden grow default dev
+    exec_garden(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "grow",
+        "default",
+        "dev",
+    ])?;
+
+    // Trees without changes relative to "default" are skipped.
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "exec",
+        "--changed-since",
+        "default",
+        "*",
+        "pwd",
+    ]);
+    assert_eq!(output, "");
+
+    // Trees with uncommitted changes are included.
+    let worktree = fixture.worktree("dev");
+    std::fs::write(format!("{worktree}/README"), "untracked\n")?;
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "exec",
+        "--changed-since",
+        "default",
+        "*",
+        "pwd",
+    ]);
+    assert!(output.ends_with(&worktree));
+
+    // Trees whose HEAD differs from the reference are included.
+    let cmd = ["git", "add", "README"];
+    assert_cmd(&cmd, &worktree);
+    let cmd = [
+        "git",
+        "-c",
+        "user.name=A U Thor",
+        "-c",
+        "user.email=author@example.com",
+        "commit",
+        "--quiet",
+        "-m",
+        "README",
+    ];
+    assert_cmd(&cmd, &worktree);
+    let output = garden_capture(&[
+        "--chdir",
+        &fixture.root(),
+        "--config",
+        "tests/data/worktree.yaml",
+        "ls",
+        "--format",
+        "json",
+        "--changed-since",
+        "default",
+        "*",
+    ]);
+    let value: serde_json::Value = serde_json::from_str(&output)?;
+    assert_eq!(value[0]["name"], "dev");
+    assert_eq!(value.as_array().map(Vec::len), Some(1));
+
+    Ok(())
+}
+
+/// `garden check` reports configuration problems with file and line numbers.
+#[test]
+fn check_reports_problems() {
+    let (status, output) = garden_capture_status(&["--config", "tests/data/check.yaml", "check"]);
+    assert_eq!(status, garden::errors::EX_CONFIG);
+
+    let lines: Vec<&str> = output.lines().collect();
+    let config_path = std::path::PathBuf::from("tests/data/check.yaml")
+        .canonicalize()
+        .unwrap();
+    let tree_path = config_path.with_file_name("trees").join("tree");
+    let expect = [
+        "tests/data/check.yaml:4: garden.includes: include not found: missing.yaml".to_string(),
+        "tests/data/check.yaml:7: variables.a: self-referential variable: a -> b -> a".to_string(),
+        "tests/data/check.yaml:12: templates.base.url: undefined variable: ${undefined-variable}"
+            .to_string(),
+        "tests/data/check.yaml:16: trees.tree.templates.1: template does not exist: missing-template"
+            .to_string(),
+        "tests/data/check.yaml:17: trees.tree.depends: dependency does not exist: missing-tree"
+            .to_string(),
+        format!(
+            "tests/data/check.yaml:18: trees.duplicate: path is also used by tree tree: {}",
+            tree_path.display()
+        ),
+        "tests/data/check.yaml:20: trees.duplicate: unknown key: urls".to_string(),
+        "tests/data/check.yaml:23: groups.group.1: group member does not match any trees: missing-member"
+            .to_string(),
+        "tests/data/check.yaml:29: commands.deploy.options.jobs: name is already in use".to_string(),
+        "tests/data/check.yaml:30: commands.deploy.options.region: default is not one of the choices: moon"
+            .to_string(),
+        "tests/data/check.yaml:33: commands.deploy.options.region: unknown key: typo".to_string(),
+    ];
+    assert_eq!(lines, expect);
+
+    // Valid configurations pass.
+    let (status, output) = garden_capture_status(&["--config", "tests/data/bare.yaml", "check"]);
+    assert_eq!(status, garden::errors::EX_OK);
+    assert_eq!(output, "");
+}
+
+/// `garden vars` displays the definition that was used and the shadowed definitions.
+#[test]
+fn vars_shadowed_definitions() {
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/garden.yaml",
+        "--define",
+        "current_config=override",
+        "vars",
+        "--name",
+        "current_config",
+        "current",
+    ]);
+    let expect = [
+        "current_config = override",
+        "  override: override (command-line)",
+        "  shadowed global: main (variables.current_config defined at tests/data/garden.yaml:24)",
+    ];
+    assert_eq!(output.lines().collect::<Vec<_>>(), expect);
+
+    // Grafted trees use variables from the graft's global scope.
+    let output = garden_capture(&[
+        "--config",
+        "tests/data/garden.yaml",
+        "vars",
+        "--name",
+        "current_config",
+        "graft::prebuilt",
+    ]);
+    let expect = [
+        "current_config = graft",
+        "  graft global: graft (variables.current_config defined at tests/data/grafts/graft.yaml:12)",
+        "  shadowed global: main (variables.current_config defined at tests/data/garden.yaml:24)",
+    ];
+    assert_eq!(output.lines().collect::<Vec<_>>(), expect);
+}
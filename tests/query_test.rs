@@ -12,7 +12,7 @@ use garden::string;
 fn resolve_trees_default_query_finds_garden() -> Result<()> {
     let app_context = common::garden_context()?;
     let config = app_context.get_root_config();
-    let result = garden::query::resolve_trees(&app_context, config, None, "cola");
+    let result = garden::query::resolve_trees(&app_context, config, None, "cola")?;
     assert_eq!(3, result.len());
     assert_eq!(Some(string!("cola")), result[0].garden);
     assert_eq!("git", result[0].tree);
@@ -28,7 +28,7 @@ fn resolve_trees_default_query_finds_garden() -> Result<()> {
 fn resolve_trees_tree_query_wildcard() -> Result<()> {
     let app_context = common::garden_context()?;
     let config = app_context.get_root_config();
-    let result = garden::query::resolve_trees(&app_context, config, None, "@c*");
+    let result = garden::query::resolve_trees(&app_context, config, None, "@c*")?;
     assert_eq!(1, result.len());
     assert_eq!(None, result[0].garden);
     assert_eq!(None, result[0].group);
@@ -41,7 +41,7 @@ fn resolve_trees_tree_query_wildcard() -> Result<()> {
 fn resolve_trees_group_query() -> Result<()> {
     let app_context = common::garden_context()?;
     let config = app_context.get_root_config();
-    let result = garden::query::resolve_trees(&app_context, config, None, "%rev*");
+    let result = garden::query::resolve_trees(&app_context, config, None, "%rev*")?;
     assert_eq!(2, result.len());
     assert_eq!(None, result[0].garden);
     assert_eq!(Some(string!("reverse")), result[0].group);
@@ -58,7 +58,7 @@ fn resolve_trees_group_with_wildcards() -> Result<()> {
     let app_context = common::garden_context()?;
     let config = app_context.get_root_config();
     // annex group
-    let result = garden::query::resolve_trees(&app_context, config, None, "%annex");
+    let result = garden::query::resolve_trees(&app_context, config, None, "%annex")?;
     assert_eq!(2, result.len());
     // annex/data
     assert_eq!(None, result[0].garden);
@@ -202,3 +202,236 @@ fn tree_query() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn resolve_trees_with_set_operations() -> Result<()> {
+    let app_context = common::garden_context()?;
+    let config = app_context.get_root_config();
+
+    // Exclusion: the "reverse" group minus the "git" tree.
+    let result = garden::query::resolve_trees(&app_context, config, None, "%reverse - @git")?;
+    assert_eq!(1, result.len());
+    assert_eq!("cola", result[0].tree);
+
+    // Intersection: trees in both the "cola" garden and the "reverse" group.
+    let result = garden::query::resolve_trees(&app_context, config, None, ":cola & %reverse")?;
+    assert_eq!(2, result.len());
+    assert_eq!("git", result[0].tree);
+    assert_eq!("cola", result[1].tree);
+
+    // Union: duplicate trees are only reported once.
+    let result = garden::query::resolve_trees(&app_context, config, None, "@cola | %reverse")?;
+    assert_eq!(2, result.len());
+    assert_eq!("cola", result[0].tree);
+    assert_eq!("git", result[1].tree);
+
+    // Parentheses override the default precedence.
+    let result =
+        garden::query::resolve_trees(&app_context, config, None, ":cola - (@git | @cola)")?;
+    assert_eq!(1, result.len());
+    assert_eq!("python/qtpy", result[0].tree);
+
+    Ok(())
+}
+
+#[test]
+fn parse_query_expressions() -> Result<()> {
+    use garden::query::{parse_query, QueryExpression};
+
+    let query = |value: &str| Box::new(QueryExpression::Query(value.to_string()));
+
+    // Queries without operators are used as-is.
+    assert_eq!(
+        QueryExpression::Query(string!("annex/*")),
+        parse_query("annex/*")?
+    );
+    // Dashes inside of names are not operators.
+    assert_eq!(
+        QueryExpression::Query(string!("tree-name")),
+        parse_query("tree-name")?
+    );
+    // "&" binds more tightly than "-".
+    assert_eq!(
+        QueryExpression::Difference(
+            query(":platform"),
+            Box::new(QueryExpression::Intersection(
+                query("%legacy"),
+                query("%python")
+            )),
+        ),
+        parse_query(":platform - %legacy&%python")?
+    );
+    assert_eq!(
+        QueryExpression::Union(query("a"), query("b")),
+        parse_query("(a|b)")?
+    );
+    // Parentheses that are attached to words are part of the query.
+    assert_eq!(
+        QueryExpression::Query(string!("trees/dir(1)")),
+        parse_query("trees/dir(1)")?
+    );
+    assert_eq!(
+        QueryExpression::Query(string!("a(b)c")),
+        parse_query("a(b)c")?
+    );
+    assert_eq!(
+        QueryExpression::Difference(
+            Box::new(QueryExpression::Union(query("a"), query("b"))),
+            query("c(1)"),
+        ),
+        parse_query("(a | b) - c(1)")?
+    );
+
+    // Errors report the offset of the bad token.
+    let offset = |value: &str| match parse_query(value) {
+        Err(garden::errors::GardenError::QuerySyntaxError { offset, .. }) => Some(offset),
+        _ => None,
+    };
+    assert_eq!(Some(9), offset("%backend %python & x"));
+    assert_eq!(Some(9), offset("%backend %python | @docs"));
+    // Queries without operators are not parsed as expressions.
+    assert_eq!(
+        QueryExpression::Query(string!("%backend %python")),
+        parse_query("%backend %python")?
+    );
+    assert_eq!(Some(10), offset("%backend -"));
+    assert_eq!(Some(0), offset("& a"));
+    assert_eq!(Some(6), offset("(a | b"));
+    assert_eq!(Some(2), offset("a ) b"));
+
+    Ok(())
+}
+
+#[test]
+fn resolve_trees_tag_query() -> Result<()> {
+    let string = string!(
+        r#"
+    templates:
+        rust:
+            tags: rust
+    trees:
+        tool:
+            templates: rust
+            tags: [cli]
+        tool-fork:
+            extend: tool
+        lib:
+            tags: [rust, library]
+        docs:
+            tags: docs
+    "#
+    );
+    let app_context = common::garden_context_from_string(&string)?;
+    let config = app_context.get_root_config();
+
+    // Tags are inherited from templates and extended trees.
+    let tool_fork = config.trees.get("tool-fork").context("tool-fork")?;
+    assert!(tool_fork.tags.contains("rust"));
+    assert!(tool_fork.tags.contains("cli"));
+
+    let result = garden::query::resolve_trees(&app_context, config, None, "#rust")?;
+    assert_eq!(3, result.len());
+    assert_eq!("tool", result[0].tree);
+    assert_eq!("tool-fork", result[1].tree);
+    assert_eq!("lib", result[2].tree);
+
+    // Tag queries support wildcards and set operations.
+    let result = garden::query::resolve_trees(&app_context, config, None, "#lib* | #doc*")?;
+    assert_eq!(2, result.len());
+    assert_eq!("lib", result[0].tree);
+    assert_eq!("docs", result[1].tree);
+
+    let result = garden::query::resolve_trees(&app_context, config, None, "#rust - #cli")?;
+    assert_eq!(1, result.len());
+    assert_eq!("lib", result[0].tree);
+
+    Ok(())
+}
+
+#[test]
+fn dependency_waves() -> Result<()> {
+    let string = string!(
+        r#"
+    trees:
+        app:
+            depends: service
+        service:
+            depends: [lib, internal]
+        lib: {}
+        internal:
+            depends: lib
+        tool: {}
+    "#
+    );
+    let app_context = common::garden_context_from_string(&string)?;
+    let config = app_context.get_root_config();
+    let names = |waves: &Vec<Vec<garden::model::TreeContext>>| -> Vec<Vec<String>> {
+        waves
+            .iter()
+            .map(|wave| {
+                wave.iter()
+                    .map(|context| context.tree.to_string())
+                    .collect()
+            })
+            .collect()
+    };
+
+    let contexts = garden::query::resolve_trees(&app_context, config, None, "@*")?;
+    let waves = garden::query::dependency_waves(&app_context, contexts)?;
+    assert_eq!(
+        vec![
+            vec![string!("lib"), string!("tool")],
+            vec![string!("internal")],
+            vec![string!("service")],
+            vec![string!("app")],
+        ],
+        names(&waves)
+    );
+
+    // Dependencies outside of the query still order the selected trees.
+    let contexts = garden::query::resolve_trees(&app_context, config, None, "app | lib")?;
+    let waves = garden::query::dependency_waves(&app_context, contexts)?;
+    assert_eq!(
+        vec![vec![string!("lib")], vec![string!("app")]],
+        names(&waves)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn dependency_waves_errors() -> Result<()> {
+    let string = string!(
+        r#"
+    trees:
+        a:
+            depends: b
+        b:
+            depends: c
+        c:
+            depends: a
+        d:
+            depends: missing
+    "#
+    );
+    let app_context = common::garden_context_from_string(&string)?;
+    let config = app_context.get_root_config();
+
+    let contexts = garden::query::resolve_trees(&app_context, config, None, "@a")?;
+    let result = garden::query::dependency_waves(&app_context, contexts);
+    assert!(result.is_err());
+    assert_eq!(
+        "invalid configuration: dependency cycle: a -> b -> c -> a",
+        result.unwrap_err().to_string()
+    );
+
+    let contexts = garden::query::resolve_trees(&app_context, config, None, "@d")?;
+    let result = garden::query::dependency_waves(&app_context, contexts);
+    assert!(result.is_err());
+    assert_eq!(
+        "invalid configuration: d: dependency does not exist: missing",
+        result.unwrap_err().to_string()
+    );
+
+    Ok(())
+}